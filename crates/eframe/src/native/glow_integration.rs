@@ -247,7 +247,7 @@ impl<'app> GlowWinitApp<'app> {
             Some(Arc::clone(&gl)),
             Some(Box::new({
                 let painter = Rc::clone(&painter);
-                move |native| painter.borrow_mut().register_native_texture(native)
+                move |native| painter.borrow_mut().register_native_texture(native, None)
             })),
             #[cfg(feature = "wgpu_no_default_features")]
             None,
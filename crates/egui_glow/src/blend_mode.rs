@@ -0,0 +1,196 @@
+/// How the meshes painted by egui are blended with what's already in the framebuffer.
+///
+/// Set with [`crate::Painter::set_blend_mode`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Premultiplied alpha blending, which is what egui outputs.
+    ///
+    /// The alpha of the framebuffer accumulates the coverage of everything painted,
+    /// so a framebuffer cleared to transparent ends up with premultiplied colors and alpha
+    /// that composite over any background just like painting onto that background directly would.
+    /// This is what transparent windows need.
+    #[default]
+    Normal,
+
+    /// Add the colors to the framebuffer, e.g. for glowing particles.
+    ///
+    /// Since the colors are premultiplied, translucent colors add less, and transparent ones nothing.
+    Additive,
+
+    /// Multiply the framebuffer by the colors, e.g. for shadows and vignettes.
+    ///
+    /// Opaque colors multiply it like `blend_func(DST_COLOR, ZERO)` would.
+    /// Translucent ones only partly, so that the transparent parts of a mesh leave the framebuffer as it was,
+    /// where `ZERO` would blacken it.
+    Multiply,
+
+    /// Overwrite the framebuffer, ignoring alpha.
+    Opaque,
+
+    /// Any blend equation and functions, as `glow` constants (e.g. `glow::FUNC_ADD` and `glow::ONE`).
+    ///
+    /// Remember that egui outputs colors with premultiplied alpha.
+    Custom {
+        equation_rgb: u32,
+        equation_alpha: u32,
+        src_rgb: u32,
+        dst_rgb: u32,
+        src_alpha: u32,
+        dst_alpha: u32,
+    },
+}
+
+impl BlendMode {
+    /// The blend equations and functions of this mode,
+    /// as `[equation_rgb, equation_alpha, src_rgb, dst_rgb, src_alpha, dst_alpha]`.
+    ///
+    /// `None` for [`Self::Opaque`], which disables blending.
+    pub(crate) fn gl_blend_state(self) -> Option<[u32; 6]> {
+        // This is technically the correct alpha blend function
+        // when you want to make use of the framebuffer alpha (for screenshots, compositing, etc).
+        let [src_alpha, dst_alpha] = [glow::ONE_MINUS_DST_ALPHA, glow::ONE];
+
+        match self {
+            Self::Normal => Some([
+                glow::FUNC_ADD,
+                glow::FUNC_ADD,
+                // egui outputs colors with premultiplied alpha:
+                glow::ONE,
+                glow::ONE_MINUS_SRC_ALPHA,
+                src_alpha,
+                dst_alpha,
+            ]),
+            Self::Additive => Some([
+                glow::FUNC_ADD,
+                glow::FUNC_ADD,
+                glow::ONE,
+                glow::ONE,
+                src_alpha,
+                dst_alpha,
+            ]),
+            Self::Multiply => Some([
+                glow::FUNC_ADD,
+                glow::FUNC_ADD,
+                // dst * (src / src_alpha) * src_alpha + dst * (1 - src_alpha):
+                glow::DST_COLOR,
+                glow::ONE_MINUS_SRC_ALPHA,
+                src_alpha,
+                dst_alpha,
+            ]),
+            Self::Opaque => None,
+            Self::Custom {
+                equation_rgb,
+                equation_alpha,
+                src_rgb,
+                dst_rgb,
+                src_alpha,
+                dst_alpha,
+            } => Some([
+                equation_rgb,
+                equation_alpha,
+                src_rgb,
+                dst_rgb,
+                src_alpha,
+                dst_alpha,
+            ]),
+        }
+    }
+}
+
+/// What blending `src` onto `dst` with the blend state of `mode` results in, like the GPU would.
+///
+/// Only supports `FUNC_ADD` and the blend functions [`BlendMode`] uses.
+#[cfg(test)]
+fn blend(mode: BlendMode, src: [f32; 4], dst: [f32; 4]) -> [f32; 4] {
+    let Some(
+        [
+            glow::FUNC_ADD,
+            glow::FUNC_ADD,
+            src_rgb,
+            dst_rgb,
+            src_alpha,
+            dst_alpha,
+        ],
+    ) = mode.gl_blend_state()
+    else {
+        return src;
+    };
+    let factor = |function, i: usize| match function {
+        glow::ZERO => 0.0,
+        glow::ONE => 1.0,
+        glow::DST_COLOR => dst[i],
+        glow::ONE_MINUS_SRC_ALPHA => 1.0 - src[3],
+        glow::ONE_MINUS_DST_ALPHA => 1.0 - dst[3],
+        _ => panic!("Unsupported blend function 0x{function:X}"),
+    };
+    std::array::from_fn(|i| {
+        let (src_function, dst_function) = if i < 3 {
+            (src_rgb, dst_rgb)
+        } else {
+            (src_alpha, dst_alpha)
+        };
+        src[i] * factor(src_function, i) + dst[i] * factor(dst_function, i)
+    })
+}
+
+#[test]
+fn test_normal_blending_composites_like_painting_onto_background() {
+    // Premultiplied half-transparent red, then half-transparent blue on top:
+    let layers = [[0.5, 0.0, 0.0, 0.5], [0.0, 0.0, 0.5, 0.5]];
+    let paint = |background| {
+        layers
+            .into_iter()
+            .fold(background, |dst, src| blend(BlendMode::Normal, src, dst))
+    };
+
+    // A transparent window:
+    let window = paint([0.0; 4]);
+    assert_eq!(window, [0.25, 0.0, 0.5, 0.75]);
+
+    // Composited over an opaque background by the compositor, with premultiplied alpha:
+    let background = [0.2, 0.4, 0.6, 1.0];
+    let composited: [f32; 4] =
+        std::array::from_fn(|i| window[i] + background[i] * (1.0 - window[3]));
+    assert_eq!(composited, paint(background));
+}
+
+#[test]
+fn test_additive_blending() {
+    let dst = [0.25, 0.5, 0.0, 1.0];
+
+    // Opaque colors are added as they are:
+    assert_eq!(
+        blend(BlendMode::Additive, [0.5, 0.25, 0.5, 1.0], dst),
+        [0.75, 0.75, 0.5, 1.0]
+    );
+
+    // Premultiplied half-transparent white adds half as much:
+    assert_eq!(
+        blend(BlendMode::Additive, [0.5, 0.5, 0.5, 0.5], dst),
+        [0.75, 1.0, 0.5, 1.0]
+    );
+
+    // Transparent parts of a mesh change nothing:
+    assert_eq!(blend(BlendMode::Additive, [0.0; 4], dst), dst);
+}
+
+#[test]
+fn test_multiply_blending() {
+    let dst = [0.5, 1.0, 0.25, 1.0];
+
+    // Opaque colors multiply the framebuffer, like `blend_func(DST_COLOR, ZERO)`:
+    assert_eq!(
+        blend(BlendMode::Multiply, [0.5, 0.5, 1.0, 1.0], dst),
+        [0.25, 0.5, 0.25, 1.0]
+    );
+
+    // Premultiplied half-transparent black darkens halfway:
+    assert_eq!(
+        blend(BlendMode::Multiply, [0.0, 0.0, 0.0, 0.5], dst),
+        [0.25, 0.5, 0.125, 1.0]
+    );
+
+    // White and transparent parts of a mesh change nothing:
+    assert_eq!(blend(BlendMode::Multiply, [1.0; 4], dst), dst);
+    assert_eq!(blend(BlendMode::Multiply, [0.0; 4], dst), dst);
+}
@@ -0,0 +1,46 @@
+use egui::epaint::PaintCallbackInfo;
+
+use crate::{CallbackTarget, Painter, PainterError};
+
+// ----------------------------------------------------------------------------
+
+/// A custom renderer that is registered with the painter once, and painted by [`RegisteredCallback`]s.
+///
+/// Unlike a [`crate::CallbackFn`], which is boxed anew every frame,
+/// a renderer lives as long as it is registered, so it can create its GPU resources
+/// (buffers, programs, …) once, and keep them from frame to frame.
+///
+/// See [`Painter::register_callback_renderer`].
+pub trait CallbackRenderer {
+    /// Paint, like a [`crate::CallbackFn`] made with [`crate::CallbackFn::new_with_target`].
+    ///
+    /// # Errors
+    /// Whatever went wrong, which [`Painter::try_paint_primitives`] returns.
+    fn paint(
+        &mut self,
+        info: PaintCallbackInfo,
+        target: &CallbackTarget,
+        painter: &Painter,
+    ) -> Result<(), PainterError>;
+
+    /// Delete the GPU resources of the renderer.
+    ///
+    /// Called when the renderer is unregistered or replaced, and when the painter is destroyed.
+    fn destroy(&mut self, _gl: &glow::Context) {}
+}
+
+/// Put this in an [`egui::PaintCallback`] to paint the [`CallbackRenderer`] registered with this id.
+///
+/// See [`Painter::register_callback_renderer`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RegisteredCallback(pub egui::Id);
+
+impl RegisteredCallback {
+    /// A [`egui::PaintCallback`] that paints the renderer registered as `id` in `rect`.
+    pub fn paint_callback(id: egui::Id, rect: egui::Rect) -> egui::PaintCallback {
+        egui::PaintCallback {
+            rect,
+            callback: std::sync::Arc::new(Self(id)),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+#![expect(unsafe_code)]
+
+// ----------------------------------------------------------------------------
+
+/// A GL object that can be named for debuggers like `RenderDoc` and `apitrace`.
+///
+/// See [`crate::Painter::set_debug_labels`].
+#[derive(Clone, Copy)]
+pub(crate) enum DebugObject {
+    Program(glow::Program),
+    Buffer(glow::Buffer),
+    VertexArray(glow::VertexArray),
+    Texture(glow::Texture),
+    Framebuffer(glow::Framebuffer),
+}
+
+/// Name the object with `glObjectLabel`. Requires `KHR_debug`.
+///
+/// The object must have been bound at least once, or GL doesn't consider it created yet.
+pub(crate) unsafe fn set_debug_label(gl: &glow::Context, object: DebugObject, label: &str) {
+    // WebGL doesn't have KHR_debug, and its objects have no GL names to label:
+    #[cfg(target_arch = "wasm32")]
+    let _ = (gl, object, label);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    unsafe {
+        use glow::HasContext as _;
+
+        let (identifier, name) = match object {
+            DebugObject::Program(program) => (glow::PROGRAM, program.0.get()),
+            DebugObject::Buffer(buffer) => (glow::BUFFER, buffer.0.get()),
+            DebugObject::VertexArray(vertex_array) => (glow::VERTEX_ARRAY, vertex_array.0.get()),
+            DebugObject::Texture(texture) => (glow::TEXTURE, texture.0.get()),
+            DebugObject::Framebuffer(framebuffer) => (glow::FRAMEBUFFER, framebuffer.0.get()),
+        };
+        gl.object_label(identifier, name, Some(label));
+        crate::check_for_gl_error!(gl, "object_label");
+    }
+}
@@ -0,0 +1,114 @@
+#![expect(unsafe_code)]
+
+use glow::HasContext as _;
+
+// ----------------------------------------------------------------------------
+
+/// How severe a `KHR_debug` message from the driver is.
+///
+/// See [`enable_debug_output`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DebugSeverity {
+    /// Anything that isn't a problem, e.g. which memory a buffer was allocated in.
+    /// Logged with [`log::Level::Debug`].
+    Notification,
+
+    /// E.g. minor performance warnings. Logged with [`log::Level::Info`].
+    Low,
+
+    /// E.g. significant performance warnings or deprecated behavior. Logged with [`log::Level::Warn`].
+    Medium,
+
+    /// Errors and undefined behavior. Logged with [`log::Level::Error`].
+    High,
+}
+
+impl DebugSeverity {
+    const ALL: [Self; 4] = [Self::Notification, Self::Low, Self::Medium, Self::High];
+
+    fn glow_code(self) -> u32 {
+        match self {
+            Self::Notification => glow::DEBUG_SEVERITY_NOTIFICATION,
+            Self::Low => glow::DEBUG_SEVERITY_LOW,
+            Self::Medium => glow::DEBUG_SEVERITY_MEDIUM,
+            Self::High => glow::DEBUG_SEVERITY_HIGH,
+        }
+    }
+
+    fn from_glow_code(code: u32) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|severity| severity.glow_code() == code)
+    }
+
+    fn log_level(self) -> log::Level {
+        match self {
+            Self::Notification => log::Level::Debug,
+            Self::Low => log::Level::Info,
+            Self::Medium => log::Level::Warn,
+            Self::High => log::Level::Error,
+        }
+    }
+}
+
+/// Forward the `KHR_debug` messages of the driver that are at least `min_severity` to [`log`].
+///
+/// This catches problems that [`crate::check_for_gl_error!`] misses,
+/// like performance warnings and undefined behavior, right where they happen.
+///
+/// The callback can only be installed with exclusive access to the context,
+/// so call this before sharing the context with the [`crate::Painter`],
+/// or see [`crate::Painter::enable_debug_output`].
+///
+/// Requires OpenGL 4.3, OpenGL ES 3.2 or `KHR_debug`.
+/// Elsewhere (including WebGL) this does nothing but log a warning.
+pub fn enable_debug_output(gl: &mut glow::Context, min_severity: DebugSeverity) {
+    if !gl.supports_debug() {
+        log::warn!("This context doesn't support KHR_debug. Ignoring enable_debug_output.");
+        return;
+    }
+
+    unsafe {
+        gl.enable(glow::DEBUG_OUTPUT);
+        // So the messages are logged from the call that caused them:
+        gl.enable(glow::DEBUG_OUTPUT_SYNCHRONOUS);
+        for severity in DebugSeverity::ALL {
+            gl.debug_message_control(
+                glow::DONT_CARE,
+                glow::DONT_CARE,
+                severity.glow_code(),
+                &[],
+                min_severity <= severity,
+            );
+        }
+        gl.debug_message_callback(log_debug_message);
+    }
+    crate::check_for_gl_error!(gl, "enable_debug_output");
+}
+
+fn log_debug_message(_source: u32, message_type: u32, id: u32, severity: u32, message: &str) {
+    let level = DebugSeverity::from_glow_code(severity)
+        .map_or(log::Level::Debug, |severity| severity.log_level());
+    let message_type = match message_type {
+        glow::DEBUG_TYPE_ERROR => "error",
+        glow::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated behavior",
+        glow::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behavior",
+        glow::DEBUG_TYPE_PORTABILITY => "portability",
+        glow::DEBUG_TYPE_PERFORMANCE => "performance",
+        glow::DEBUG_TYPE_MARKER => "marker",
+        glow::DEBUG_TYPE_PUSH_GROUP | glow::DEBUG_TYPE_POP_GROUP => "debug group",
+        _ => "message",
+    };
+    log::log!(level, "OpenGL {message_type} (id 0x{id:X}): {message}");
+}
+
+#[test]
+fn test_debug_severity_glow_code() {
+    for severity in DebugSeverity::ALL {
+        assert_eq!(
+            DebugSeverity::from_glow_code(severity.glow_code()),
+            Some(severity)
+        );
+    }
+    assert_eq!(DebugSeverity::from_glow_code(glow::DONT_CARE), None);
+}
@@ -0,0 +1,157 @@
+#![expect(unsafe_code)]
+
+use glow::HasContext as _;
+
+use crate::check_for_gl_error;
+
+// ----------------------------------------------------------------------------
+
+/// The OpenGL state that painting egui changes, saved so it can be restored afterwards.
+///
+/// See [`crate::Painter::set_preserve_gl_state`].
+pub(crate) struct GlState {
+    /// Capabilities, and whether they were enabled.
+    capabilities: Vec<(u32, bool)>,
+
+    /// `[equation_rgb, equation_alpha, src_rgb, dst_rgb, src_alpha, dst_alpha]`
+    blend: [u32; 6],
+
+    color_mask: [bool; 4],
+    viewport: [i32; 4],
+    scissor_box: [i32; 4],
+    program: Option<glow::Program>,
+    active_texture: u32,
+
+    /// The texture bound to texture unit 0, which egui paints with.
+    texture: Option<glow::Texture>,
+
+    /// The sampler bound to texture unit 0, if sampler objects are supported.
+    sampler: Option<glow::Sampler>,
+    supports_samplers: bool,
+
+    array_buffer: Option<glow::Buffer>,
+
+    /// The bound vertex array object, if the binding can be queried.
+    vertex_array: Option<glow::VertexArray>,
+    supports_vertex_array_query: bool,
+}
+
+impl GlState {
+    /// Query the current state. This stalls the pipeline, so only do it when asked to.
+    pub(crate) unsafe fn save(
+        gl: &glow::Context,
+        capabilities: &[u32],
+        supports_samplers: bool,
+        supports_vertex_array_query: bool,
+    ) -> Self {
+        profiling::function_scope!();
+
+        unsafe {
+            let get_u32 = |parameter| gl.get_parameter_i32(parameter) as u32;
+            let get_i32_4 = |parameter| {
+                let mut values = [0; 4];
+                gl.get_parameter_i32_slice(parameter, &mut values);
+                values
+            };
+
+            let active_texture = get_u32(glow::ACTIVE_TEXTURE);
+            gl.active_texture(glow::TEXTURE0);
+            let texture = gl.get_parameter_texture(glow::TEXTURE_BINDING_2D);
+            let sampler = if supports_samplers {
+                gl.get_parameter_sampler(glow::SAMPLER_BINDING)
+            } else {
+                None
+            };
+            gl.active_texture(active_texture);
+
+            let state = Self {
+                capabilities: capabilities
+                    .iter()
+                    .map(|&capability| (capability, gl.is_enabled(capability)))
+                    .collect(),
+                blend: [
+                    get_u32(glow::BLEND_EQUATION_RGB),
+                    get_u32(glow::BLEND_EQUATION_ALPHA),
+                    get_u32(glow::BLEND_SRC_RGB),
+                    get_u32(glow::BLEND_DST_RGB),
+                    get_u32(glow::BLEND_SRC_ALPHA),
+                    get_u32(glow::BLEND_DST_ALPHA),
+                ],
+                color_mask: gl.get_parameter_bool_array(glow::COLOR_WRITEMASK),
+                viewport: get_i32_4(glow::VIEWPORT),
+                scissor_box: get_i32_4(glow::SCISSOR_BOX),
+                program: gl.get_parameter_program(glow::CURRENT_PROGRAM),
+                active_texture,
+                texture,
+                sampler,
+                supports_samplers,
+                array_buffer: gl.get_parameter_buffer(glow::ARRAY_BUFFER_BINDING),
+                vertex_array: if supports_vertex_array_query {
+                    gl.get_parameter_vertex_array(glow::VERTEX_ARRAY_BINDING)
+                } else {
+                    None
+                },
+                supports_vertex_array_query,
+            };
+            check_for_gl_error!(gl, "GlState::save");
+            state
+        }
+    }
+
+    pub(crate) unsafe fn restore(&self, gl: &glow::Context) {
+        profiling::function_scope!();
+
+        let Self {
+            capabilities,
+            blend:
+                [
+                    equation_rgb,
+                    equation_alpha,
+                    src_rgb,
+                    dst_rgb,
+                    src_alpha,
+                    dst_alpha,
+                ],
+            color_mask: [red, green, blue, alpha],
+            viewport: [x, y, width, height],
+            scissor_box: [scissor_x, scissor_y, scissor_width, scissor_height],
+            program,
+            active_texture,
+            texture,
+            sampler,
+            supports_samplers,
+            array_buffer,
+            vertex_array,
+            supports_vertex_array_query,
+        } = self;
+
+        unsafe {
+            for &(capability, enabled) in capabilities {
+                if enabled {
+                    gl.enable(capability);
+                } else {
+                    gl.disable(capability);
+                }
+            }
+            gl.blend_equation_separate(*equation_rgb, *equation_alpha);
+            gl.blend_func_separate(*src_rgb, *dst_rgb, *src_alpha, *dst_alpha);
+            gl.color_mask(*red, *green, *blue, *alpha);
+            gl.viewport(*x, *y, *width, *height);
+            gl.scissor(*scissor_x, *scissor_y, *scissor_width, *scissor_height);
+            gl.use_program(*program);
+
+            gl.active_texture(glow::TEXTURE0);
+            gl.bind_texture(glow::TEXTURE_2D, *texture);
+            if *supports_samplers {
+                gl.bind_sampler(0, *sampler);
+            }
+            gl.active_texture(*active_texture);
+
+            if *supports_vertex_array_query {
+                gl.bind_vertex_array(*vertex_array);
+            }
+            gl.bind_buffer(glow::ARRAY_BUFFER, *array_buffer);
+        }
+        check_for_gl_error!(gl, "GlState::restore");
+    }
+}
@@ -0,0 +1,135 @@
+#![expect(unsafe_code)]
+
+use std::{collections::VecDeque, time::Duration};
+
+use glow::HasContext as _;
+
+use crate::{PainterError, check_for_gl_error};
+
+// ----------------------------------------------------------------------------
+
+/// `GL_GPU_DISJOINT_EXT`, from `EXT_disjoint_timer_query`.
+const GPU_DISJOINT_EXT: u32 = 0x8FBB;
+
+/// How many frames can be in flight before we stop timing new ones.
+const MAX_PENDING_QUERIES: usize = 4;
+
+/// Measures how long the GPU takes to paint each frame with `GL_TIME_ELAPSED` queries.
+///
+/// The results arrive a frame or two late, and are polled without ever waiting for the GPU.
+///
+/// See [`crate::Painter::set_gpu_timing`].
+pub(crate) struct GpuTimer {
+    /// Queries of frames the GPU may still be painting, oldest first.
+    pending: VecDeque<glow::Query>,
+
+    /// Queries whose results have been read, for reuse.
+    free: Vec<glow::Query>,
+
+    /// Whether the timer can be disturbed (e.g. by power management), which only ES reports.
+    check_disjoint: bool,
+
+    /// The latest result.
+    last_frame_time: Option<Duration>,
+
+    /// Is a query between [`Self::begin`] and [`Self::end`]?
+    is_timing: bool,
+}
+
+impl GpuTimer {
+    /// Does the context support timer queries?
+    pub(crate) fn is_supported(gl: &glow::Context, is_webgl_1: bool) -> bool {
+        let has_extension = |name: &str| {
+            gl.supported_extensions()
+                .iter()
+                .any(|extension| extension.ends_with(name))
+        };
+
+        // WebGL1 has its own query API, which glow doesn't wrap:
+        !is_webgl_1
+            && if gl.version().is_embedded {
+                has_extension("EXT_disjoint_timer_query")
+                    || has_extension("EXT_disjoint_timer_query_webgl2")
+            } else {
+                let version = gl.version();
+                [version.major, version.minor] >= [3, 3] || has_extension("ARB_timer_query")
+            }
+    }
+
+    pub(crate) fn new(gl: &glow::Context) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            free: Vec::new(),
+            check_disjoint: gl.version().is_embedded,
+            last_frame_time: None,
+            is_timing: false,
+        }
+    }
+
+    /// The GPU time of the latest frame whose result has arrived.
+    pub(crate) fn last_frame_time(&self) -> Option<Duration> {
+        self.last_frame_time
+    }
+
+    /// Read the results that have arrived, and start timing a frame.
+    ///
+    /// Frames aren't timed while too many are in flight.
+    pub(crate) unsafe fn begin(&mut self, gl: &glow::Context) -> Result<(), PainterError> {
+        unsafe {
+            self.poll(gl);
+            if MAX_PENDING_QUERIES <= self.pending.len() {
+                return Ok(());
+            }
+
+            let query = match self.free.pop() {
+                Some(query) => query,
+                None => gl.create_query()?,
+            };
+            gl.begin_query(glow::TIME_ELAPSED, query);
+            check_for_gl_error!(gl, "GpuTimer::begin");
+            self.pending.push_back(query);
+            self.is_timing = true;
+            Ok(())
+        }
+    }
+
+    /// Stop timing the frame started with [`Self::begin`], if any.
+    pub(crate) unsafe fn end(&mut self, gl: &glow::Context) {
+        if std::mem::take(&mut self.is_timing) {
+            unsafe { gl.end_query(glow::TIME_ELAPSED) };
+            check_for_gl_error!(gl, "GpuTimer::end");
+        }
+    }
+
+    unsafe fn poll(&mut self, gl: &glow::Context) {
+        unsafe {
+            let mut latest = None;
+            while let Some(&query) = self.pending.front()
+                && gl.get_query_parameter_u32(query, glow::QUERY_RESULT_AVAILABLE) != 0
+            {
+                // Nanoseconds, which is over four seconds in 32 bits.
+                // `get_query_parameter_u64` needs extensions some drivers lack.
+                let nanos = gl.get_query_parameter_u32(query, glow::QUERY_RESULT);
+                latest = Some(Duration::from_nanos(nanos.into()));
+                self.free.extend(self.pending.pop_front());
+            }
+
+            // The results are garbage if the timer was disturbed:
+            if self.check_disjoint && gl.get_parameter_i32(GPU_DISJOINT_EXT) != 0 {
+                latest = None;
+            }
+            if latest.is_some() {
+                self.last_frame_time = latest;
+            }
+            check_for_gl_error!(gl, "GpuTimer::poll");
+        }
+    }
+
+    pub(crate) unsafe fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            for &query in self.pending.iter().chain(&self.free) {
+                gl.delete_query(query);
+            }
+        }
+    }
+}
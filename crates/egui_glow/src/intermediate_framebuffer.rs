@@ -0,0 +1,196 @@
+#![expect(unsafe_code)]
+
+use glow::HasContext as _;
+
+use crate::{PainterError, check_for_gl_error};
+
+// ----------------------------------------------------------------------------
+
+/// A framebuffer that egui is painted into when anti-aliasing, supersampling or using a color LUT,
+/// and the texture that is then painted onto the actual framebuffer.
+///
+/// See [`crate::Painter::new_with_msaa`], [`crate::Painter::set_supersample_factor`] and [`crate::Painter::set_color_lut`].
+pub(crate) struct IntermediateFramebuffer {
+    pub size: [u32; 2],
+    pub samples: i32,
+
+    /// The framebuffer egui is painted into.
+    pub fbo: glow::Framebuffer,
+
+    /// The multisampled color buffer, if multisampled.
+    color: Option<glow::Renderbuffer>,
+
+    /// For callbacks that paint 3D scenes.
+    depth_stencil: glow::Renderbuffer,
+
+    /// The framebuffer [`Self::texture`] is attached to,
+    /// which is [`Self::fbo`] unless multisampled.
+    resolve_fbo: glow::Framebuffer,
+
+    /// Owned by the painter, which paints it like any other native texture.
+    pub texture: glow::Texture,
+}
+
+impl IntermediateFramebuffer {
+    /// Create the framebuffers. Unbinds the framebuffer.
+    ///
+    /// `samples` is zero for a framebuffer that isn't multisampled,
+    /// and `filter` is how the texture is sampled when painted.
+    pub(crate) unsafe fn new(
+        gl: &glow::Context,
+        samples: i32,
+        [width, height]: [u32; 2],
+        depth_stencil_format: u32,
+        filter: u32,
+    ) -> Result<Self, PainterError> {
+        let [w, h] = [width as i32, height as i32];
+
+        unsafe {
+            let texture = gl.create_texture()?;
+            gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA as i32,
+                w,
+                h,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelUnpackData::Slice(None),
+            );
+            for (parameter, value) in [
+                (glow::TEXTURE_MIN_FILTER, filter),
+                (glow::TEXTURE_MAG_FILTER, filter),
+                (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE),
+                (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE),
+            ] {
+                gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, value as i32);
+            }
+            let resolve_fbo = gl.create_framebuffer()?;
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(resolve_fbo));
+            gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                Some(texture),
+                0,
+            );
+
+            let depth_stencil = gl.create_renderbuffer()?;
+            gl.bind_renderbuffer(glow::RENDERBUFFER, Some(depth_stencil));
+            let (fbo, color) = if 0 < samples {
+                gl.renderbuffer_storage_multisample(
+                    glow::RENDERBUFFER,
+                    samples,
+                    depth_stencil_format,
+                    w,
+                    h,
+                );
+                let color = gl.create_renderbuffer()?;
+                gl.bind_renderbuffer(glow::RENDERBUFFER, Some(color));
+                gl.renderbuffer_storage_multisample(glow::RENDERBUFFER, samples, glow::RGBA8, w, h);
+
+                let fbo = gl.create_framebuffer()?;
+                gl.bind_framebuffer(glow::FRAMEBUFFER, Some(fbo));
+                gl.framebuffer_renderbuffer(
+                    glow::FRAMEBUFFER,
+                    glow::COLOR_ATTACHMENT0,
+                    glow::RENDERBUFFER,
+                    Some(color),
+                );
+                (fbo, Some(color))
+            } else {
+                gl.renderbuffer_storage(glow::RENDERBUFFER, depth_stencil_format, w, h);
+                (resolve_fbo, None)
+            };
+            gl.bind_renderbuffer(glow::RENDERBUFFER, None);
+            gl.framebuffer_renderbuffer(
+                glow::FRAMEBUFFER,
+                glow::DEPTH_STENCIL_ATTACHMENT,
+                glow::RENDERBUFFER,
+                Some(depth_stencil),
+            );
+            let mut is_complete = true;
+            for framebuffer in [fbo, resolve_fbo] {
+                gl.bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+                is_complete &=
+                    gl.check_framebuffer_status(glow::FRAMEBUFFER) == glow::FRAMEBUFFER_COMPLETE;
+            }
+            gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            check_for_gl_error!(gl, "IntermediateFramebuffer::new");
+
+            let framebuffer = Self {
+                size: [width, height],
+                samples,
+                fbo,
+                color,
+                depth_stencil,
+                resolve_fbo,
+                texture,
+            };
+            if is_complete {
+                Ok(framebuffer)
+            } else {
+                framebuffer.destroy(gl);
+                gl.delete_texture(texture);
+                Err(format!(
+                    "Failed to create a {width}x{height} framebuffer with {samples} samples"
+                )
+                .into())
+            }
+        }
+    }
+
+    /// Bind the framebuffer egui is painted into, and clear it to transparent.
+    pub(crate) unsafe fn bind_and_clear(&self, gl: &glow::Context) {
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.fbo));
+            gl.disable(glow::SCISSOR_TEST);
+            gl.color_mask(true, true, true, true);
+            gl.depth_mask(true);
+            gl.clear_color(0.0, 0.0, 0.0, 0.0);
+            gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT | glow::STENCIL_BUFFER_BIT);
+        }
+    }
+
+    /// Resolve the multisampled framebuffer into [`Self::texture`], if multisampled.
+    ///
+    /// Leaves the read and draw framebuffers bound to those.
+    pub(crate) unsafe fn resolve(&self, gl: &glow::Context) {
+        if self.color.is_none() {
+            return;
+        }
+
+        let [w, h] = self.size.map(|side| side as i32);
+        unsafe {
+            gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(self.fbo));
+            gl.bind_framebuffer(glow::DRAW_FRAMEBUFFER, Some(self.resolve_fbo));
+            gl.blit_framebuffer(
+                0,
+                0,
+                w,
+                h,
+                0,
+                0,
+                w,
+                h,
+                glow::COLOR_BUFFER_BIT,
+                glow::NEAREST,
+            );
+        }
+        check_for_gl_error!(gl, "IntermediateFramebuffer::resolve");
+    }
+
+    /// Delete everything but [`Self::texture`], which is owned by the painter.
+    pub(crate) unsafe fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_framebuffer(self.resolve_fbo);
+            if let Some(color) = self.color {
+                gl.delete_framebuffer(self.fbo);
+                gl.delete_renderbuffer(color);
+            }
+            gl.delete_renderbuffer(self.depth_stencil);
+        }
+    }
+}
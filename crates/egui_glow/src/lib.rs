@@ -12,12 +12,40 @@
 
 pub mod painter;
 pub use glow;
-pub use painter::{CallbackFn, Painter, PainterError};
+pub use painter::{
+    ATTRIBUTE_LOCATIONS, CallbackFn, CallbackFnMut, CallbackTarget, ClipRounding,
+    IDENTITY_COLOR_MATRIX, IDENTITY_TRANSFORM, MissingTexturePolicy, PaintStats, Painter,
+    PainterCapabilities, PainterError, ShaderStage, SharedTextures,
+};
+mod blend_mode;
+mod callback_renderer;
+mod debug_label;
+mod debug_output;
+mod gl_state;
+mod gpu_timer;
+mod intermediate_framebuffer;
+mod mesh_cache;
 mod misc_util;
+mod painter_builder;
+mod render_target;
+mod sampler_options;
+mod screen_read;
 mod shader_version;
+mod streaming_buffer;
+mod texture_format;
+mod texture_upload;
 mod vao;
 
+pub use blend_mode::BlendMode;
+pub use callback_renderer::{CallbackRenderer, RegisteredCallback};
+pub use debug_output::{DebugSeverity, enable_debug_output};
+pub use painter_builder::PainterBuilder;
+pub use render_target::RenderTarget;
+pub use sampler_options::{SamplerOptions, WrapMode};
+pub use screen_read::ScreenReadId;
 pub use shader_version::ShaderVersion;
+pub use texture_format::TextureFormat;
+pub use texture_upload::{OversizePolicy, UploadOptions};
 
 #[cfg(feature = "winit")]
 pub mod winit;
@@ -64,25 +92,30 @@ macro_rules! check_for_gl_error_even_in_release {
     ($gl: expr, $context: literal) => {{ $crate::check_for_gl_error_impl($gl, file!(), line!(), $context) }};
 }
 
+/// The name of a code returned by `glGetError`.
+pub(crate) fn gl_error_name(error_code: u32) -> &'static str {
+    match error_code {
+        glow::INVALID_ENUM => "GL_INVALID_ENUM",
+        glow::INVALID_VALUE => "GL_INVALID_VALUE",
+        glow::INVALID_OPERATION => "GL_INVALID_OPERATION",
+        glow::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
+        glow::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
+        glow::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
+        glow::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
+        glow::CONTEXT_LOST => "GL_CONTEXT_LOST",
+        0x8031 => "GL_TABLE_TOO_LARGE1",
+        0x9242 => "CONTEXT_LOST_WEBGL",
+        _ => "<unknown>",
+    }
+}
+
 #[doc(hidden)]
 pub fn check_for_gl_error_impl(gl: &glow::Context, file: &str, line: u32, context: &str) {
     use glow::HasContext as _;
     #[expect(unsafe_code)]
     let error_code = unsafe { gl.get_error() };
     if error_code != glow::NO_ERROR {
-        let error_str = match error_code {
-            glow::INVALID_ENUM => "GL_INVALID_ENUM",
-            glow::INVALID_VALUE => "GL_INVALID_VALUE",
-            glow::INVALID_OPERATION => "GL_INVALID_OPERATION",
-            glow::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
-            glow::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
-            glow::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
-            glow::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
-            glow::CONTEXT_LOST => "GL_CONTEXT_LOST",
-            0x8031 => "GL_TABLE_TOO_LARGE1",
-            0x9242 => "CONTEXT_LOST_WEBGL",
-            _ => "<unknown>",
-        };
+        let error_str = gl_error_name(error_code);
 
         if context.is_empty() {
             log::error!(
@@ -0,0 +1,174 @@
+#![expect(unsafe_code)]
+
+use std::collections::{HashMap, HashSet};
+
+use egui::epaint::Mesh;
+use glow::HasContext as _;
+
+use crate::check_for_gl_error;
+
+// ----------------------------------------------------------------------------
+
+/// A mesh kept on the GPU in its own buffers.
+pub(crate) struct CachedMesh {
+    pub vbo: glow::Buffer,
+    pub element_array_buffer: glow::Buffer,
+    texture_id: egui::TextureId,
+
+    /// The size of both buffers, in bytes.
+    bytes: usize,
+
+    /// The frame the mesh was last painted in.
+    last_used: u64,
+}
+
+/// Keeps the meshes that are painted frame after frame on the GPU, so they don't need to be uploaded again.
+///
+/// Meshes are identified by a hash of their vertices and indices.
+/// A mesh is only cached once it is painted in two frames in a row,
+/// so content that changes every frame doesn't churn through buffers.
+/// When the cache grows past its budget, the least recently painted meshes are evicted.
+pub(crate) struct MeshCache {
+    /// The maximum size of all cached buffers, in bytes.
+    budget: usize,
+    bytes: usize,
+    meshes: HashMap<u64, CachedMesh>,
+    frame: u64,
+    painted_last_frame: HashSet<u64>,
+    painted_this_frame: HashSet<u64>,
+
+    /// Buffers to delete at the end of the frame, since they may still be bound until then.
+    buffers_to_destroy: Vec<glow::Buffer>,
+}
+
+impl MeshCache {
+    pub(crate) fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            bytes: 0,
+            meshes: Default::default(),
+            frame: 0,
+            painted_last_frame: Default::default(),
+            painted_this_frame: Default::default(),
+            buffers_to_destroy: Vec::new(),
+        }
+    }
+
+    pub(crate) fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+    }
+
+    pub(crate) fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// The cached buffers of `mesh`, uploading them if the mesh was also painted last frame.
+    ///
+    /// Returns `None` if `mesh` should be streamed as usual.
+    ///
+    /// Uploading binds the new buffers, so `bound_element_array_buffer` is updated
+    /// for the painter to know what is bound.
+    pub(crate) unsafe fn get_or_upload(
+        &mut self,
+        gl: &glow::Context,
+        mesh: &Mesh,
+        bound_element_array_buffer: &mut Option<glow::Buffer>,
+    ) -> Option<&CachedMesh> {
+        profiling::function_scope!();
+
+        let vertices: &[u8] = bytemuck::cast_slice(&mesh.vertices);
+        let indices: &[u8] = bytemuck::cast_slice(&mesh.indices);
+        let key = egui::util::hash((vertices, indices));
+
+        // The buffers don't depend on the texture, but a mesh painted with another texture
+        // is a different mesh as far as the caller is concerned:
+        if let Some(cached) = self.meshes.get(&key)
+            && cached.texture_id != mesh.texture_id
+        {
+            self.remove(key);
+        }
+
+        let is_new = !self.meshes.contains_key(&key);
+        if is_new {
+            if !self.painted_last_frame.contains(&key) {
+                self.painted_this_frame.insert(key);
+                return None;
+            }
+
+            let cached = unsafe {
+                let vbo = gl.create_buffer().ok()?;
+                gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+                gl.buffer_data_u8_slice(glow::ARRAY_BUFFER, vertices, glow::STATIC_DRAW);
+                let Ok(element_array_buffer) = gl.create_buffer() else {
+                    gl.delete_buffer(vbo);
+                    return None;
+                };
+                gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(element_array_buffer));
+                *bound_element_array_buffer = Some(element_array_buffer);
+                gl.buffer_data_u8_slice(glow::ELEMENT_ARRAY_BUFFER, indices, glow::STATIC_DRAW);
+                CachedMesh {
+                    vbo,
+                    element_array_buffer,
+                    texture_id: mesh.texture_id,
+                    bytes: vertices.len() + indices.len(),
+                    last_used: self.frame,
+                }
+            };
+            check_for_gl_error!(gl, "MeshCache::get_or_upload");
+            self.bytes += cached.bytes;
+            self.meshes.insert(key, cached);
+        }
+
+        self.painted_this_frame.insert(key);
+        let cached = self.meshes.get_mut(&key)?;
+        cached.last_used = self.frame;
+        Some(cached)
+    }
+
+    /// Evict the least recently painted meshes until the cache fits its budget.
+    ///
+    /// Call after painting, since this deletes buffers.
+    pub(crate) unsafe fn end_frame(&mut self, gl: &glow::Context) {
+        if self.budget < self.bytes {
+            let mut by_age: Vec<(u64, u64)> = self
+                .meshes
+                .iter()
+                .map(|(&key, cached)| (cached.last_used, key))
+                .collect();
+            by_age.sort_unstable();
+            for (_, key) in by_age {
+                if self.bytes <= self.budget {
+                    break;
+                }
+                self.remove(key);
+            }
+        }
+        for buffer in self.buffers_to_destroy.drain(..) {
+            unsafe { gl.delete_buffer(buffer) };
+        }
+
+        self.painted_last_frame = std::mem::take(&mut self.painted_this_frame);
+        self.frame += 1;
+    }
+
+    fn remove(&mut self, key: u64) {
+        if let Some(cached) = self.meshes.remove(&key) {
+            self.bytes -= cached.bytes;
+            self.buffers_to_destroy
+                .extend([cached.vbo, cached.element_array_buffer]);
+        }
+    }
+
+    pub(crate) unsafe fn destroy(&self, gl: &glow::Context) {
+        #[expect(clippy::iter_over_hash_type)]
+        for cached in self.meshes.values() {
+            unsafe {
+                gl.delete_buffer(cached.vbo);
+                gl.delete_buffer(cached.element_array_buffer);
+            }
+        }
+        for &buffer in &self.buffers_to_destroy {
+            unsafe { gl.delete_buffer(buffer) };
+        }
+    }
+}
@@ -2,7 +2,8 @@
 
 use glow::HasContext as _;
 
-pub(crate) unsafe fn compile_shader(
+/// Start compiling a shader. Check whether it compiled with [`check_shader`].
+pub(crate) unsafe fn start_compiling_shader(
     gl: &glow::Context,
     shader_type: u32,
     source: &str,
@@ -14,17 +15,16 @@ pub(crate) unsafe fn compile_shader(
 
         gl.compile_shader(shader);
 
-        if gl.get_shader_compile_status(shader) {
-            Ok(shader)
-        } else {
-            Err(gl.get_shader_info_log(shader))
-        }
+        Ok(shader)
     }
 }
 
-pub(crate) unsafe fn link_program<'a, T: IntoIterator<Item = &'a glow::Shader>>(
+/// Start linking the shaders into a program, binding each of the `attributes` to its index in the slice.
+/// Check whether it linked with [`check_program`].
+pub(crate) unsafe fn start_linking_program<'a, T: IntoIterator<Item = &'a glow::Shader>>(
     gl: &glow::Context,
     shaders: T,
+    attributes: &[&str],
 ) -> Result<glow::Program, String> {
     unsafe {
         let program = gl.create_program()?;
@@ -33,10 +33,35 @@ pub(crate) unsafe fn link_program<'a, T: IntoIterator<Item = &'a glow::Shader>>(
             gl.attach_shader(program, *shader);
         }
 
+        for (index, name) in attributes.iter().enumerate() {
+            gl.bind_attrib_location(program, index as u32, name);
+        }
+
         gl.link_program(program);
 
+        Ok(program)
+    }
+}
+
+/// Wait for the shader to compile, and return its info log if it failed to.
+pub(crate) unsafe fn check_shader(gl: &glow::Context, shader: glow::Shader) -> Result<(), String> {
+    unsafe {
+        if gl.get_shader_compile_status(shader) {
+            Ok(())
+        } else {
+            Err(gl.get_shader_info_log(shader))
+        }
+    }
+}
+
+/// Wait for the program to link, and return its info log if it failed to.
+pub(crate) unsafe fn check_program(
+    gl: &glow::Context,
+    program: glow::Program,
+) -> Result<(), String> {
+    unsafe {
         if gl.get_program_link_status(program) {
-            Ok(program)
+            Ok(())
         } else {
             Err(gl.get_program_info_log(program))
         }
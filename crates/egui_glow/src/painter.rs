@@ -1,19 +1,39 @@
-#![expect(clippy::unwrap_used)]
 #![expect(unsafe_code)]
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    sync::Arc,
+};
 
 use egui::{
     emath::Rect,
-    epaint::{Mesh, PaintCallbackInfo, Primitive, Vertex},
+    epaint::{Mesh, PaintCallbackInfo, Primitive, Vertex, ViewportInPixels},
+    mutex::Mutex,
 };
 use glow::HasContext as _;
 use memoffset::offset_of;
 
+use crate::callback_renderer::{CallbackRenderer, RegisteredCallback};
 use crate::check_for_gl_error;
-use crate::misc_util::{compile_shader, link_program};
+use crate::debug_label::{DebugObject, set_debug_label};
+use crate::gl_state::GlState;
+use crate::gpu_timer::GpuTimer;
+use crate::intermediate_framebuffer::IntermediateFramebuffer;
+use crate::mesh_cache::MeshCache;
+use crate::misc_util::{
+    check_program, check_shader, start_compiling_shader, start_linking_program,
+};
+use crate::screen_read::{PendingScreenRead, ScreenReadId};
 use crate::shader_version::ShaderVersion;
+use crate::streaming_buffer::StreamingBuffer;
+use crate::texture_upload::{downscale, flip_rows, pack_rows, premultiply_alpha, unmultiply_alpha};
 use crate::vao;
+use crate::{
+    BlendMode, OversizePolicy, PainterBuilder, RenderTarget, SamplerOptions, TextureFormat,
+    UploadOptions, WrapMode,
+};
 
 /// Re-exported [`glow::Context`].
 pub use glow::Context;
@@ -21,6 +41,37 @@ pub use glow::Context;
 const VERT_SRC: &str = include_str!("shader/vertex.glsl");
 const FRAG_SRC: &str = include_str!("shader/fragment.glsl");
 
+/// The `user_postprocess` of the fragment shader without [`PainterBuilder::fragment_postprocess`].
+const IDENTITY_POSTPROCESS: &str = "vec4 user_postprocess(vec4 color, vec2 uv) { return color; }";
+
+/// The vertex attributes of the egui shader, each bound to its index in this list.
+const ATTRIBUTES: [&str; 3] = ["a_pos", "a_tc", "a_srgba"];
+
+/// The locations of the `[a_pos, a_tc, a_srgba]` vertex attributes of [`Painter::program`],
+/// which are the same for every painter.
+pub const ATTRIBUTE_LOCATIONS: [u32; 3] = [0, 1, 2];
+
+/// `GL_TEXTURE_EXTERNAL_OES`, from `OES_EGL_image_external`.
+const TEXTURE_EXTERNAL_OES: u32 = 0x8D65;
+
+/// Meshes with fewer indices than this are drawn with their `u32` indices as-is,
+/// since converting them to `u16` would cost more than the bandwidth it saves.
+const MIN_INDICES_FOR_U16: usize = 1024;
+
+/// The color matrix that leaves colors as they are. See [`Painter::set_color_matrix`].
+pub const IDENTITY_COLOR_MATRIX: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+/// The value of `u_tint` that leaves colors as they are.
+const WHITE_TINT: [f32; 4] = [1.0; 4];
+
+/// The transform that leaves everything where egui put it. See [`Painter::set_transform`].
+pub const IDENTITY_TRANSFORM: [[f32; 3]; 2] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+
 trait TextureFilterExt {
     fn glow_code(&self, mipmap: Option<egui::TextureFilter>) -> u32;
 }
@@ -52,636 +103,5537 @@ impl TextureWrapModeExt for egui::TextureWrapMode {
     }
 }
 
-#[derive(Debug)]
-pub struct PainterError(String);
-
-impl std::error::Error for PainterError {}
-
-impl std::fmt::Display for PainterError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "OpenGL: {}", self.0)
+impl TextureWrapModeExt for WrapMode {
+    fn glow_code(&self) -> u32 {
+        match self {
+            Self::ClampToEdge => glow::CLAMP_TO_EDGE,
+            Self::Repeat => glow::REPEAT,
+            Self::MirroredRepeat => glow::MIRRORED_REPEAT,
+            Self::ClampToBorder => glow::CLAMP_TO_BORDER,
+        }
     }
 }
 
-impl From<String> for PainterError {
-    #[inline]
-    fn from(value: String) -> Self {
-        Self(value)
-    }
+/// The OpenGL enums used for uploading a [`TextureFormat`] on the current context.
+#[derive(Clone, Copy, Debug)]
+struct GlTextureFormat {
+    internal_format: u32,
+    src_format: u32,
+    pixel_type: u32,
 }
 
-/// An OpenGL painter using [`glow`].
-///
-/// This is responsible for painting egui and managing egui textures.
-/// You can access the underlying [`glow::Context`] with [`Self::gl`].
-///
-/// This struct must be destroyed with [`Painter::destroy`] before dropping, to ensure OpenGL
-/// objects have been properly deleted and are not leaked.
-///
-/// NOTE: all egui viewports share the same painter.
-pub struct Painter {
-    gl: Arc<glow::Context>,
+/// What the egui shader is compiled from, kept around to compile variants of it later.
+struct ShaderSource {
+    version: ShaderVersion,
+    prefix: String,
 
-    max_texture_side: usize,
+    /// See [`PainterBuilder::fragment_postprocess`].
+    postprocess: Option<String>,
+
+    /// Replace [`VERT_SRC`] and [`FRAG_SRC`], see [`Painter::reload_shaders`].
+    vert: Option<String>,
+    frag: Option<String>,
+}
 
+/// The variants of the egui shader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ShaderVariant {
+    Default,
+
+    /// Samples a `samplerExternalOES` instead of a `sampler2D`.
+    ExternalOes,
+
+    /// Also maps the colors through a 3D lookup table, see [`Painter::set_color_lut`].
+    ColorLut,
+}
+
+/// A compiled variant of the egui shader.
+struct ShaderProgram {
     program: glow::Program,
     u_screen_size: glow::UniformLocation,
+    u_transform: glow::UniformLocation,
     u_sampler: glow::UniformLocation,
-    is_webgl_1: bool,
-    vao: crate::vao::VertexArrayObject,
-    srgb_textures: bool,
-    supports_srgb_framebuffer: bool,
-    vbo: glow::Buffer,
-    element_array_buffer: glow::Buffer,
+    u_dithering: glow::UniformLocation,
+    u_gamma: glow::UniformLocation,
+    u_color_matrix: glow::UniformLocation,
 
-    textures: HashMap<egui::TextureId, glow::Texture>,
+    u_linear_output: glow::UniformLocation,
 
-    next_native_tex_id: u64,
+    /// White, except while painting a mesh of [`Painter::paint_user_mesh_tinted`].
+    u_tint: glow::UniformLocation,
 
-    /// Stores outdated OpenGL textures that are yet to be deleted
-    textures_to_destroy: Vec<glow::Texture>,
+    /// Only in [`ShaderVariant::ColorLut`].
+    u_color_lut: Option<glow::UniformLocation>,
 
-    /// Used to make sure we are destroyed correctly.
-    destroyed: bool,
-}
+    /// Optimized away with the identity postprocess.
+    u_postprocess: Option<glow::UniformLocation>,
 
-/// A callback function that can be used to compose an [`egui::PaintCallback`] for custom rendering
-/// with [`glow`].
-///
-/// The callback is passed, the [`egui::PaintCallbackInfo`] and the [`Painter`] which can be used to
-/// access the OpenGL context.
-///
-/// # Example
-///
-/// See the [`custom3d_glow`](https://github.com/emilk/egui/blob/main/crates/egui_demo_app/src/apps/custom3d_wgpu.rs) demo source for a detailed usage example.
-pub struct CallbackFn {
-    f: Box<dyn Fn(PaintCallbackInfo, &Painter) + Sync + Send>,
+    /// What was last uploaded to the uniforms above, to skip uploading it again.
+    ///
+    /// Uniforms belong to the program, so this stays valid while callbacks bind their own programs.
+    uploaded_uniforms: Cell<Option<ShaderUniforms>>,
 }
 
-impl CallbackFn {
-    pub fn new<F: Fn(PaintCallbackInfo, &Painter) + Sync + Send + 'static>(callback: F) -> Self {
-        let f = Box::new(callback);
-        Self { f }
-    }
+/// The values [`Painter`] sets on the uniforms of a [`ShaderProgram`] before painting.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ShaderUniforms {
+    screen_size: [f32; 2],
+    transform: [[f32; 3]; 3],
+    dithering: f32,
+    gamma: f32,
+    color_matrix: [[f32; 4]; 4],
+    linear_output: f32,
 }
 
-impl Painter {
-    /// Create painter.
-    ///
-    /// Set `pp_fb_extent` to the framebuffer size to enable `sRGB` support on OpenGL ES and WebGL.
-    ///
-    /// Set `shader_prefix` if you want to turn on shader workaround e.g. `"#define APPLY_BRIGHTENING_GAMMA\n"`
-    /// (see <https://github.com/emilk/egui/issues/794>).
-    ///
-    /// # Errors
-    /// will return `Err` below cases
-    /// * failed to compile shader
-    /// * failed to create postprocess on webgl with `sRGB` support
-    /// * failed to create buffer
-    pub fn new(
-        gl: Arc<glow::Context>,
-        shader_prefix: &str,
-        shader_version: Option<ShaderVersion>,
-        dithering: bool,
+impl ShaderProgram {
+    /// Compile a variant of the egui shader.
+    unsafe fn new(
+        gl: &glow::Context,
+        source: &ShaderSource,
+        variant: ShaderVariant,
     ) -> Result<Self, PainterError> {
-        profiling::function_scope!();
-        crate::check_for_gl_error_even_in_release!(&gl, "before Painter::new");
-
-        // some useful debug info. all three of them are present in gl 1.1.
         unsafe {
-            let version = gl.get_parameter_string(glow::VERSION);
-            let renderer = gl.get_parameter_string(glow::RENDERER);
-            let vendor = gl.get_parameter_string(glow::VENDOR);
-            log::debug!(
-                "\nopengl version: {version}\nopengl renderer: {renderer}\nopengl vendor: {vendor}"
-            );
+            let pending = PendingProgram::compile(gl, source, variant)?;
+            pending
+                .finish(gl)
+                .inspect_err(|_| gl.delete_program(pending.program))
         }
+    }
+}
 
-        #[cfg(not(target_arch = "wasm32"))]
-        if gl.version().major < 2 {
-            // this checks on desktop that we are not using opengl 1.1 microsoft sw rendering context.
-            // ShaderVersion::get fn will segfault due to SHADING_LANGUAGE_VERSION (added in gl2.0)
-            return Err(PainterError("egui_glow requires opengl 2.0+. ".to_owned()));
-        }
+/// A [`ShaderProgram`] that was sent to the driver to compile and link, which may still be doing so in parallel.
+struct PendingProgram {
+    program: glow::Program,
 
-        let max_texture_side = unsafe { gl.get_parameter_i32(glow::MAX_TEXTURE_SIZE) } as usize;
-        let shader_version = shader_version.unwrap_or_else(|| ShaderVersion::get(&gl));
-        let is_webgl_1 = shader_version == ShaderVersion::Es100;
-        let shader_version_declaration = shader_version.version_declaration();
-        log::debug!("Shader header: {shader_version_declaration:?}.");
+    /// The vertex and fragment shaders.
+    shaders: [glow::Shader; 2],
 
-        let supported_extensions = gl.supported_extensions();
-        log::trace!("OpenGL extensions: {supported_extensions:?}");
-        let srgb_textures = false; // egui wants normal sRGB-unaware textures
+    /// The sources of [`Self::shaders`], for [`PainterError::ShaderCompile`].
+    sources: [String; 2],
+}
 
-        let supports_srgb_framebuffer = !cfg!(target_arch = "wasm32")
-            && supported_extensions.iter().any(|extension| {
-                // {GL,GLX,WGL}_ARB_framebuffer_sRGB, …
-                extension.ends_with("ARB_framebuffer_sRGB")
-            });
-        log::debug!("SRGB framebuffer Support: {supports_srgb_framebuffer}");
+impl PendingProgram {
+    /// Start compiling a variant of the egui shader.
+    unsafe fn compile(
+        gl: &glow::Context,
+        source: &ShaderSource,
+        variant: ShaderVariant,
+    ) -> Result<Self, PainterError> {
+        let ShaderSource {
+            version,
+            prefix,
+            postprocess,
+            vert: vert_src,
+            frag: frag_src,
+        } = source;
+        let postprocess = postprocess.as_deref().unwrap_or(IDENTITY_POSTPROCESS);
+        let vert_src = vert_src.as_deref().unwrap_or(VERT_SRC);
+        let frag_src = frag_src.as_deref().unwrap_or(FRAG_SRC);
+        let version_declaration = version.version_declaration();
+        let new_shader_interface = version.is_new_shader_interface() as i32;
 
-        unsafe {
-            let vert = compile_shader(
-                &gl,
-                glow::VERTEX_SHADER,
-                &format!(
-                    "{}\n#define NEW_SHADER_INTERFACE {}\n{}\n{}",
-                    shader_version_declaration,
-                    shader_version.is_new_shader_interface() as i32,
-                    shader_prefix,
-                    VERT_SRC
-                ),
-            )?;
-            let frag = compile_shader(
-                &gl,
-                glow::FRAGMENT_SHADER,
-                &format!(
-                    "{}\n#define NEW_SHADER_INTERFACE {}\n#define DITHERING {}\n{}\n{}",
-                    shader_version_declaration,
-                    shader_version.is_new_shader_interface() as i32,
-                    dithering as i32,
-                    shader_prefix,
-                    FRAG_SRC
-                ),
-            )?;
-            let program = link_program(&gl, [vert, frag].iter())?;
-            gl.detach_shader(program, vert);
-            gl.detach_shader(program, frag);
-            gl.delete_shader(vert);
-            gl.delete_shader(frag);
-            let u_screen_size = gl.get_uniform_location(program, "u_screen_size").unwrap();
-            let u_sampler = gl.get_uniform_location(program, "u_sampler").unwrap();
+        let vert_source = format!(
+            "{version_declaration}\n#define NEW_SHADER_INTERFACE {new_shader_interface}\n{prefix}\n{vert_src}"
+        );
+        let frag_source = format!(
+            "{version_declaration}\n#define NEW_SHADER_INTERFACE {new_shader_interface}\n#define EXTERNAL_OES {}\n#define COLOR_LUT {}\n{prefix}\n{frag_src}\n{postprocess}",
+            (variant == ShaderVariant::ExternalOes) as i32,
+            (variant == ShaderVariant::ColorLut) as i32,
+        );
 
-            let vbo = gl.create_buffer()?;
+        unsafe {
+            let vert = start_compiling_shader(gl, glow::VERTEX_SHADER, &vert_source)?;
+            let frag = start_compiling_shader(gl, glow::FRAGMENT_SHADER, &frag_source)?;
+            let program = start_linking_program(gl, [vert, frag].iter(), &ATTRIBUTES)?;
+            Ok(Self {
+                program,
+                shaders: [vert, frag],
+                sources: [vert_source, frag_source],
+            })
+        }
+    }
 
-            let a_pos_loc = gl.get_attrib_location(program, "a_pos").unwrap();
-            let a_tc_loc = gl.get_attrib_location(program, "a_tc").unwrap();
-            let a_srgba_loc = gl.get_attrib_location(program, "a_srgba").unwrap();
+    /// Has the driver finished compiling and linking, successfully or not?
+    ///
+    /// Only call this with `KHR_parallel_shader_compile`.
+    unsafe fn is_complete(&self, gl: &glow::Context) -> bool {
+        unsafe { gl.get_program_completion_status(self.program) }
+    }
 
-            let stride = std::mem::size_of::<Vertex>() as i32;
-            let buffer_infos = vec![
-                vao::BufferInfo {
-                    location: a_pos_loc,
-                    vector_size: 2,
-                    data_type: glow::FLOAT,
-                    normalized: false,
-                    stride,
-                    offset: offset_of!(Vertex, pos) as i32,
-                },
-                vao::BufferInfo {
-                    location: a_tc_loc,
-                    vector_size: 2,
-                    data_type: glow::FLOAT,
-                    normalized: false,
-                    stride,
-                    offset: offset_of!(Vertex, uv) as i32,
-                },
-                vao::BufferInfo {
-                    location: a_srgba_loc,
-                    vector_size: 4,
-                    data_type: glow::UNSIGNED_BYTE,
-                    normalized: false,
-                    stride,
-                    offset: offset_of!(Vertex, color) as i32,
-                },
-            ];
-            let vao = crate::vao::VertexArrayObject::new(&gl, vbo, buffer_infos);
+    /// Wait for the driver to finish compiling and linking, and look up the uniforms.
+    ///
+    /// The shaders are deleted either way, but the program is left for the caller to delete on failure.
+    unsafe fn finish(&self, gl: &glow::Context) -> Result<ShaderProgram, PainterError> {
+        let program = self.program;
+        let [vert, frag] = self.shaders;
+        let [vert_source, frag_source] = &self.sources;
+        let error = |stage, source: &str, log| PainterError::ShaderCompile {
+            stage,
+            source: source.to_owned(),
+            log,
+        };
 
-            let element_array_buffer = gl.create_buffer()?;
+        unsafe {
+            let status = check_shader(gl, vert)
+                .map_err(|log| error(ShaderStage::Vertex, vert_source, log))
+                .and_then(|()| {
+                    check_shader(gl, frag)
+                        .map_err(|log| error(ShaderStage::Fragment, frag_source, log))
+                })
+                .and_then(|()| {
+                    check_program(gl, program).map_err(|log| error(ShaderStage::Link, "", log))
+                });
+            for shader in self.shaders {
+                gl.detach_shader(program, shader);
+                gl.delete_shader(shader);
+            }
+            status?;
 
-            crate::check_for_gl_error_even_in_release!(&gl, "after Painter::new");
+            // Shaders of `Painter::reload_shaders` may lack some:
+            let uniform = |name| {
+                gl.get_uniform_location(program, name).ok_or_else(|| {
+                    PainterError::Other(format!("The egui shader has no {name} uniform"))
+                })
+            };
+            let u_screen_size = uniform("u_screen_size")?;
+            let u_transform = uniform("u_transform")?;
+            let u_sampler = uniform("u_sampler")?;
+            let u_dithering = uniform("u_dithering")?;
+            let u_gamma = uniform("u_gamma")?;
+            let u_color_matrix = uniform("u_color_matrix")?;
+            let u_linear_output = uniform("u_linear_output")?;
+            let u_tint = uniform("u_tint")?;
+            let u_color_lut = gl.get_uniform_location(program, "u_color_lut");
+            let u_postprocess = gl.get_uniform_location(program, "u_postprocess");
 
-            Ok(Self {
-                gl,
-                max_texture_side,
+            Ok(ShaderProgram {
                 program,
                 u_screen_size,
+                u_transform,
                 u_sampler,
-                is_webgl_1,
-                vao,
-                srgb_textures,
-                supports_srgb_framebuffer,
-                vbo,
-                element_array_buffer,
-                textures: Default::default(),
-                next_native_tex_id: 1 << 32,
-                textures_to_destroy: Vec::new(),
-                destroyed: false,
+                u_dithering,
+                u_gamma,
+                u_color_matrix,
+                u_linear_output,
+                u_tint,
+                u_color_lut,
+                u_postprocess,
+                uploaded_uniforms: Cell::new(None),
             })
         }
     }
+}
 
-    /// Access the shared glow context.
-    pub fn gl(&self) -> &Arc<glow::Context> {
-        &self.gl
-    }
+/// The default variant of the egui shader, which may still be compiling. See [`Painter::is_ready`].
+enum MainProgram {
+    Compiling(PendingProgram),
+    Ready(ShaderProgram),
 
-    pub fn max_texture_side(&self) -> usize {
-        self.max_texture_side
+    /// It failed to compile or link, which every paint reports until [`Painter::reload_shaders`] succeeds.
+    Failed(glow::Program, PainterError),
+}
+
+impl MainProgram {
+    fn program(&self) -> glow::Program {
+        match self {
+            Self::Compiling(pending) => pending.program,
+            Self::Ready(program) => program.program,
+            Self::Failed(program, _) => *program,
+        }
     }
+}
 
-    /// The framebuffer we use as an intermediate render target,
-    /// or `None` if we are painting to the screen framebuffer directly.
-    ///
-    /// This is the framebuffer that is bound when [`egui::Shape::Callback`] is called,
-    /// and is where any callbacks should ultimately render onto.
-    ///
-    /// So if in a [`egui::Shape::Callback`] you need to use an offscreen FBO, you should
-    /// then restore to this afterwards with
-    /// `gl.bind_framebuffer(glow::FRAMEBUFFER, painter.intermediate_fbo());`
-    #[expect(clippy::unused_self)]
-    pub fn intermediate_fbo(&self) -> Option<glow::Framebuffer> {
-        // We don't currently ever render to an offscreen buffer,
-        // but we may want to start to in order to do anti-aliasing on web, for instance.
-        None
+/// Where the meshes of a frame are painted, in the framebuffer egui is painted into.
+#[derive(Clone, Copy, Debug)]
+struct PaintFrame {
+    /// The size of the screen of egui in physical pixels, which differs from the real one when supersampling.
+    size_px: [u32; 2],
+    pixels_per_point: f32,
+
+    /// The `[left, top, from_bottom]` offsets of the screen of egui in the framebuffer, see [`Painter::set_viewport`].
+    origin_px: [i32; 3],
+
+    /// The framebuffer egui is painting into, for [`CallbackTarget::framebuffer`].
+    framebuffer: Option<glow::Framebuffer>,
+}
+
+impl PaintFrame {
+    fn viewport_px(&self) -> ViewportInPixels {
+        let [left_px, top_px, from_bottom_px] = self.origin_px;
+        ViewportInPixels {
+            left_px,
+            top_px,
+            from_bottom_px,
+            width_px: self.size_px[0] as i32,
+            height_px: self.size_px[1] as i32,
+        }
     }
+}
 
-    unsafe fn prepare_painting(
-        &mut self,
-        [width_in_pixels, height_in_pixels]: [u32; 2],
-        pixels_per_point: f32,
-    ) {
-        unsafe {
-            self.gl.enable(glow::SCISSOR_TEST);
-            // egui outputs mesh in both winding orders
-            self.gl.disable(glow::CULL_FACE);
-            self.gl.disable(glow::DEPTH_TEST);
+/// What [`Painter::end_frame`] needs to finish the frame and give the OpenGL state back to the host.
+struct HostState {
+    /// With [`Painter::set_preserve_gl_state`].
+    saved_gl_state: Option<GlState>,
 
-            self.gl.color_mask(true, true, true, true);
+    /// The capabilities egui needs off, and whether they were on.
+    host_capabilities: [(u32, bool); 3],
 
-            self.gl.enable(glow::BLEND);
-            self.gl
-                .blend_equation_separate(glow::FUNC_ADD, glow::FUNC_ADD);
-            self.gl.blend_func_separate(
-                // egui outputs colors with premultiplied alpha:
-                glow::ONE,
-                glow::ONE_MINUS_SRC_ALPHA,
-                // Less important, but this is technically the correct alpha blend function
-                // when you want to make use of the framebuffer alpha (for screenshots, compositing, etc).
-                glow::ONE_MINUS_DST_ALPHA,
-                glow::ONE,
-            );
+    /// The framebuffer that was bound, to bind again if painting changes it.
+    host_framebuffer: Option<glow::Framebuffer>,
+    rebind_host_framebuffer: bool,
 
-            if self.supports_srgb_framebuffer {
-                self.gl.disable(glow::FRAMEBUFFER_SRGB);
-                check_for_gl_error!(&self.gl, "FRAMEBUFFER_SRGB");
-            }
+    /// What the intermediate framebuffer is painted onto.
+    destination: Option<glow::Framebuffer>,
 
-            let width_in_points = width_in_pixels as f32 / pixels_per_point;
-            let height_in_points = height_in_pixels as f32 / pixels_per_point;
+    /// The viewport and `pixels_per_point` of the screen, if painting into the intermediate framebuffer.
+    intermediate: Option<(ViewportInPixels, f32)>,
+}
 
-            self.gl
-                .viewport(0, 0, width_in_pixels as i32, height_in_pixels as i32);
-            self.gl.use_program(Some(self.program));
+/// Something a paint callback asked the painter to do once it returns.
+enum CallbackCommand {
+    /// See [`Painter::paint_user_mesh`], [`Painter::paint_user_mesh_tinted`]
+    /// and [`Painter::paint_user_mesh_with_blend_mode`].
+    PaintMesh(Mesh, egui::Color32, BlendMode),
 
-            self.gl
-                .uniform_2_f32(Some(&self.u_screen_size), width_in_points, height_in_points);
-            self.gl.uniform_1_i32(Some(&self.u_sampler), 0);
-            self.gl.active_texture(glow::TEXTURE0);
+    /// See [`Painter::push_clip_mask`].
+    PushClipMask(Mesh),
 
-            self.vao.bind(&self.gl);
-            self.gl
-                .bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(self.element_array_buffer));
-        }
+    /// See [`Painter::pop_clip_mask`].
+    PopClipMask,
+}
 
-        check_for_gl_error!(&self.gl, "prepare_painting");
-    }
+/// A layer of a native `TEXTURE_2D_ARRAY` texture, registered with [`Painter::register_native_texture_array_layer`].
+#[derive(Clone, Copy, Debug)]
+struct ArrayLayer {
+    array: glow::Texture,
+    layer: u32,
+    size: [usize; 2],
+}
 
-    pub fn clear(&self, screen_size_in_pixels: [u32; 2], clear_color: [f32; 4]) {
-        clear(&self.gl, screen_size_in_pixels, clear_color);
-    }
+/// Where to set the sampling parameters of a texture.
+#[derive(Clone, Copy, Debug)]
+enum ParameterTarget {
+    /// The texture bound to `TEXTURE_2D`.
+    BoundTexture,
 
-    /// You are expected to have cleared the color buffer before calling this.
-    pub fn paint_and_update_textures(
-        &mut self,
-        screen_size_px: [u32; 2],
-        pixels_per_point: f32,
-        clipped_primitives: &[egui::ClippedPrimitive],
-        textures_delta: &egui::TexturesDelta,
-    ) {
-        profiling::function_scope!();
+    /// A sampler object, which overrides the parameters of any texture sampled with it.
+    Sampler(glow::Sampler),
+}
 
-        for (id, image_delta) in &textures_delta.set {
-            self.set_texture(*id, image_delta);
+impl ParameterTarget {
+    unsafe fn set_i32(self, gl: &glow::Context, parameter: u32, value: i32) {
+        unsafe {
+            match self {
+                Self::BoundTexture => gl.tex_parameter_i32(glow::TEXTURE_2D, parameter, value),
+                Self::Sampler(sampler) => gl.sampler_parameter_i32(sampler, parameter, value),
+            }
         }
+    }
 
-        self.paint_primitives(screen_size_px, pixels_per_point, clipped_primitives);
-
-        for &id in &textures_delta.free {
-            self.free_texture(id);
+    unsafe fn set_f32(self, gl: &glow::Context, parameter: u32, value: f32) {
+        unsafe {
+            match self {
+                Self::BoundTexture => gl.tex_parameter_f32(glow::TEXTURE_2D, parameter, value),
+                Self::Sampler(sampler) => gl.sampler_parameter_f32(sampler, parameter, value),
+            }
         }
     }
 
-    /// Main entry-point for painting a frame.
-    ///
-    /// You should call `target.clear_color(..)` before
-    /// and `target.finish()` after this.
-    ///
+    unsafe fn set_f32_slice(self, gl: &glow::Context, parameter: u32, values: &[f32]) {
+        unsafe {
+            match self {
+                Self::BoundTexture => {
+                    gl.tex_parameter_f32_slice(glow::TEXTURE_2D, parameter, values);
+                }
+                Self::Sampler(sampler) => {
+                    gl.sampler_parameter_f32_slice(sampler, parameter, values);
+                }
+            }
+        }
+    }
+}
+
+/// A texture known to the [`Painter`].
+#[derive(Clone, Copy, Debug)]
+struct PainterTexture {
+    texture: glow::Texture,
+
+    /// What to bind the texture to: `TEXTURE_2D`, or [`TEXTURE_EXTERNAL_OES`].
+    target: u32,
+
+    /// The immutable storage allocated with `tex_storage_2d`, if any.
+    ///
+    /// `None` for native textures, and for textures with mutable storage.
+    storage: Option<TextureStorage>,
+
+    /// The options the texture was last uploaded with, and is sampled with.
+    ///
+    /// `None` for native textures that keep their own sampling parameters,
+    /// and for new texture objects, whose parameters are yet to be set.
+    options: Option<egui::TextureOptions>,
+
+    /// Does the texture belong to the app, see [`Painter::register_native_texture`]?
+    is_native: bool,
+
+    /// The size of the texture, if known.
+    ///
+    /// For native textures, this is the size hint they were registered with.
+    size: Option<[usize; 2]>,
+
+    /// The format the texels were last uploaded in.
+    ///
+    /// Assumed to be [`TextureFormat::Rgba8`] for native textures.
+    format: TextureFormat,
+}
+
+impl PainterTexture {
+    fn native(texture: glow::Texture, target: u32, size: Option<[usize; 2]>) -> Self {
+        Self {
+            texture,
+            target,
+            storage: None,
+            options: None,
+            is_native: true,
+            size,
+            format: TextureFormat::Rgba8,
+        }
+    }
+
+    /// The memory used by the base level of the texture, or zero if its size is unknown.
+    fn memory_bytes(&self) -> usize {
+        self.size
+            .map_or(0, |[w, h]| w * h * self.format.bytes_per_texel())
+    }
+}
+
+/// The textures of one or more [`Painter`]s, by [`egui::TextureId`].
+///
+/// Windows with separate OpenGL contexts in a group that shares objects can have a painter each,
+/// and still share the textures uploaded by any of them, e.g. the font atlas, instead of uploading them once per context.
+/// Only share this between painters whose contexts share objects!
+///
+/// Cloning gives another handle to the same textures. See [`PainterBuilder::shared_textures`] and [`Painter::shared_textures`].
+#[derive(Clone, Default)]
+pub struct SharedTextures(Arc<Mutex<TextureStore>>);
+
+struct TextureStore {
+    textures: HashMap<egui::TextureId, PainterTexture>,
+
+    /// Shared too, so painters don't give out the same ids to their native textures.
+    next_native_tex_id: u64,
+}
+
+impl Default for TextureStore {
+    fn default() -> Self {
+        Self {
+            textures: HashMap::default(),
+            next_native_tex_id: 1 << 32,
+        }
+    }
+}
+
+impl std::fmt::Debug for SharedTextures {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SharedTextures")
+            .field("len", &self.0.lock().textures.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl SharedTextures {
+    fn get(&self, tex_id: &egui::TextureId) -> Option<PainterTexture> {
+        self.0.lock().textures.get(tex_id).copied()
+    }
+
+    fn insert(&self, tex_id: egui::TextureId, texture: PainterTexture) -> Option<PainterTexture> {
+        self.0.lock().textures.insert(tex_id, texture)
+    }
+
+    fn remove(&self, tex_id: &egui::TextureId) -> Option<PainterTexture> {
+        self.0.lock().textures.remove(tex_id)
+    }
+
+    fn update(&self, tex_id: &egui::TextureId, update: impl FnOnce(&mut PainterTexture)) {
+        if let Some(texture) = self.0.lock().textures.get_mut(tex_id) {
+            update(texture);
+        }
+    }
+
+    /// All textures, in no particular order.
+    fn all(&self) -> Vec<(egui::TextureId, PainterTexture)> {
+        self.0
+            .lock()
+            .textures
+            .iter()
+            .map(|(&id, &texture)| (id, texture))
+            .collect()
+    }
+
+    fn len(&self) -> usize {
+        self.0.lock().textures.len()
+    }
+
+    fn next_native_tex_id(&self) -> egui::TextureId {
+        let mut store = self.0.lock();
+        store.next_native_tex_id += 1;
+        egui::TextureId::User(store.next_native_tex_id - 1)
+    }
+
+    /// Is no other painter using these textures?
+    fn is_unshared(&self) -> bool {
+        Arc::strong_count(&self.0) == 1
+    }
+}
+
+/// A copy of a texture managed by egui, kept by [`Painter::set_retain_texture_data`]
+/// so it can be uploaded again after the context is lost.
+struct RetainedTexture {
+    /// The image as it was given to the painter, with any partial updates applied.
+    image: Arc<egui::ColorImage>,
+    options: egui::TextureOptions,
+    upload: UploadOptions,
+}
+
+/// The immutable storage of a texture, which can't be resized or changed to another format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct TextureStorage {
+    size: [usize; 2],
+    internal_format: u32,
+    levels: i32,
+}
+
+#[derive(Clone, Debug)]
+pub enum PainterError {
+    /// A shader of egui failed to compile, or its program failed to link.
+    ///
+    /// Displayed with the source, numbered by line like the info logs of most drivers.
+    ShaderCompile {
+        stage: ShaderStage,
+
+        /// The source that was compiled, with the `#version` and `#define`s the painter prepends.
+        /// Empty for [`ShaderStage::Link`].
+        source: String,
+
+        /// The info log of the shader or program.
+        log: String,
+    },
+
+    /// Anything else that went wrong.
+    Other(String),
+}
+
+impl std::error::Error for PainterError {}
+
+impl std::fmt::Display for PainterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ShaderCompile { stage, source, log } => {
+                write!(f, "OpenGL: Failed to {stage}: {}", log.trim_end())?;
+                if !source.is_empty() {
+                    write!(f, "\n\nSource:")?;
+                    for (index, line) in source.lines().enumerate() {
+                        write!(f, "\n{:4}: {line}", index + 1)?;
+                    }
+                }
+                Ok(())
+            }
+            Self::Other(message) => write!(f, "OpenGL: {message}"),
+        }
+    }
+}
+
+impl From<String> for PainterError {
+    #[inline]
+    fn from(value: String) -> Self {
+        Self::Other(value)
+    }
+}
+
+/// Which step of building a shader program failed, see [`PainterError::ShaderCompile`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShaderStage {
+    /// Compiling the vertex shader.
+    Vertex,
+
+    /// Compiling the fragment shader.
+    Fragment,
+
+    /// Linking the compiled shaders into a program.
+    Link,
+}
+
+impl std::fmt::Display for ShaderStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Vertex => "compile the vertex shader",
+            Self::Fragment => "compile the fragment shader",
+            Self::Link => "link the shader program",
+        })
+    }
+}
+
+/// What the OpenGL context of a [`Painter`] can do, e.g. to size texture atlases to fit the device.
+///
+/// See [`Painter::capabilities`]. For anything not covered here, query [`Painter::gl`] directly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PainterCapabilities {
+    /// The largest width and height of a texture, see [`Painter::max_texture_side`].
+    pub max_texture_side: usize,
+
+    /// The number of textures a fragment shader can sample at once (`GL_MAX_TEXTURE_IMAGE_UNITS`).
+    pub max_texture_image_units: usize,
+
+    /// The highest [`SamplerOptions::anisotropy`] supported, if any.
+    pub max_anisotropy: Option<f32>,
+
+    /// The shader version egui's shaders are compiled with.
+    pub shader_version: ShaderVersion,
+
+    /// Is this WebGL1 (or OpenGL ES 2.0), which lacks most of the optional features?
+    pub is_webgl_1: bool,
+
+    /// See [`Painter::set_srgb_framebuffer`].
+    pub supports_srgb_framebuffer: bool,
+
+    /// Can single-channel textures be sampled as alpha masks? See [`TextureFormat::R8`].
+    pub supports_texture_swizzle: bool,
+
+    /// See [`SamplerOptions::wrap_mode_s`] and [`WrapMode::ClampToBorder`].
+    pub supports_clamp_to_border: bool,
+
+    /// See [`Painter::begin_screen_read`].
+    pub supports_async_screen_read: bool,
+
+    /// See [`Painter::set_gpu_timing`].
+    pub supports_timer_queries: bool,
+
+    /// See [`Painter::set_debug_labels`] and [`crate::enable_debug_output`].
+    pub supports_debug: bool,
+
+    /// Vertex array objects need OpenGL 3.0, OpenGL ES 3.0 (WebGL2) or `OES_vertex_array_object`.
+    ///
+    /// Without them, the painter sets up the vertex attributes before each frame and callback,
+    /// which costs a few more GL calls.
+    pub supports_vertex_array_objects: bool,
+}
+
+/// What [`Painter`] does with meshes whose texture it doesn't know.
+///
+/// See [`Painter::set_missing_texture_policy`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MissingTexturePolicy {
+    /// Don't paint the mesh, and log a warning.
+    Skip,
+
+    /// Paint the mesh with a magenta checkerboard instead, so the missing texture is easy to spot.
+    Fallback,
+
+    /// Don't paint the mesh, and return an error from [`Painter::try_paint_primitives`]
+    /// (or log it, with [`Painter::paint_primitives`]).
+    #[default]
+    Error,
+}
+
+/// How [`Painter`] snaps the edges of clip rectangles to whole physical pixels for the scissor test.
+///
+/// With a fractional `pixels_per_point`, or while a panel is being resized,
+/// the edges of a clip rectangle often fall between pixels.
+///
+/// See [`Painter::set_clip_rounding`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ClipRounding {
+    /// Round each edge to the nearest pixel.
+    ///
+    /// Each edge can jump by a pixel independently of the others,
+    /// which can make content at the edges of a moving or resizing panel shimmer.
+    #[default]
+    Round,
+
+    /// Floor the min corner and ceil the max corner, so no content is clipped by a sub-pixel.
+    ///
+    /// The clip rectangle can grow by up to a pixel on each side,
+    /// letting through slivers of content that should have been clipped.
+    Expand,
+
+    /// Round the min corner to the nearest pixel, and the size to the nearest whole number of pixels.
+    ///
+    /// The clip rectangle keeps its size in pixels while it moves by fractions of a pixel.
+    Nearest,
+}
+
+impl ClipRounding {
+    /// The `[min, max]` of a clip rectangle along one axis, in whole pixels.
+    fn snap(self, min: f32, max: f32) -> [i32; 2] {
+        match self {
+            Self::Round => [min.round() as i32, max.round() as i32],
+            Self::Expand => [min.floor() as i32, max.ceil() as i32],
+            Self::Nearest => {
+                let min_px = min.round() as i32;
+                [min_px, min_px + (max - min).round() as i32]
+            }
+        }
+    }
+}
+
+/// Statistics about the last painted frame,
+/// e.g. to show the cost of rendering in a debug overlay.
+///
+/// See [`Painter::paint_primitives_with_stats`] and [`Painter::paint_stats`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PaintStats {
+    /// The number of `draw_elements` calls.
+    pub draw_calls: usize,
+
+    /// The number of vertices drawn.
+    pub vertices: usize,
+
+    /// The number of indices drawn, i.e. three per triangle.
+    pub indices: usize,
+
+    /// The number of [`egui::PaintCallback`]s run.
+    pub callbacks: usize,
+
+    /// The number of texture uploads, full or partial.
+    ///
+    /// Only counted by [`Painter::paint_and_update_textures`].
+    pub texture_uploads: usize,
+
+    /// The number of meshes drawn from the mesh cache, without uploading them.
+    ///
+    /// See [`Painter::set_mesh_cache_budget`].
+    pub cached_meshes: usize,
+
+    /// The number of meshes not painted at all, because they are clipped away entirely.
+    pub skipped_primitives: usize,
+
+    /// The number of meshes merged into the mesh before them,
+    /// because they share its clip rectangle and texture, saving a draw call each.
+    pub merged_meshes: usize,
+}
+
+/// An OpenGL painter using [`glow`].
+///
+/// This is responsible for painting egui and managing egui textures.
+/// You can access the underlying [`glow::Context`] with [`Self::gl`].
+///
+/// This struct must be destroyed with [`Painter::destroy`] before dropping, to ensure OpenGL
+/// objects have been properly deleted and are not leaked.
+///
+/// NOTE: all egui viewports share the same painter.
+pub struct Painter {
+    gl: Arc<glow::Context>,
+
+    max_texture_side: usize,
+    max_texture_image_units: usize,
+
+    /// `GL_VERSION`, `GL_RENDERER` and `GL_VENDOR`, as queried by [`Self::new`].
+    gl_version_string: String,
+    renderer_string: String,
+    vendor_string: String,
+
+    /// Set with [`Self::set_oversize_policy`].
+    oversize_policy: OversizePolicy,
+
+    /// Set with [`Self::set_missing_texture_policy`].
+    missing_texture_policy: MissingTexturePolicy,
+
+    /// Set with [`Self::set_clip_rounding`].
+    clip_rounding: ClipRounding,
+
+    /// Set with [`Self::set_max_indices_per_draw`].
+    max_indices_per_draw: Option<usize>,
+
+    /// The checkerboard of [`MissingTexturePolicy::Fallback`], created on first use.
+    fallback_texture: Option<glow::Texture>,
+
+    /// `None` if anisotropic filtering is not supported.
+    max_anisotropy: Option<f32>,
+
+    shader_source: ShaderSource,
+    program: MainProgram,
+
+    /// The shader variant for [`TEXTURE_EXTERNAL_OES`] textures, compiled on first use.
+    external_oes_program: Option<ShaderProgram>,
+
+    /// Is [`Self::external_oes_program`] the program in use?
+    using_external_oes_program: bool,
+
+    /// The shader variant for [`Self::set_color_lut`], compiled on first use.
+    color_lut_program: Option<ShaderProgram>,
+
+    /// The texture and sampler last bound by [`Self::paint_mesh`], to skip binding them again.
+    ///
+    /// Reset by [`Self::prepare_painting`], since callbacks may bind their own,
+    /// and by anything else that binds a texture, see [`Self::bind_texture_2d`].
+    bound_texture: Option<(glow::Texture, Option<glow::Sampler>)>,
+
+    /// The element array buffer bound to the VAO, which is [`Self::element_array_buffer`]
+    /// unless the last mesh was drawn from the mesh cache.
+    ///
+    /// Set by [`Self::prepare_painting`].
+    bound_element_array_buffer: Option<glow::Buffer>,
+
+    /// Set with [`Self::set_dithering`].
+    dithering: bool,
+
+    /// Set with [`Self::set_gamma`].
+    gamma: f32,
+
+    /// Set with [`Self::set_color_matrix`].
+    color_matrix: [[f32; 4]; 4],
+
+    /// Set with [`Self::set_transform`].
+    transform: [[f32; 3]; 2],
+
+    /// Set with [`Self::set_color_lut`].
+    color_lut: Option<glow::Texture>,
+
+    /// Set with [`Self::set_srgb_framebuffer`].
+    srgb_framebuffer: bool,
+
+    /// Set with [`Self::set_blend_mode`].
+    blend_mode: BlendMode,
+
+    /// Set with [`Self::set_preserve_gl_state`].
+    preserve_gl_state: bool,
+
+    /// Set with [`Self::set_debug_labels`].
+    debug_labels: bool,
+
+    /// Set with [`Self::set_render_target`].
+    render_target: Option<glow::Framebuffer>,
+
+    /// Set with [`Self::set_viewport`]: the top left corner and size in physical pixels.
+    viewport: Option<([u32; 2], [u32; 2])>,
+
+    /// The union of the damage rects of the frame being painted with [`Self::paint_primitives_damaged`].
+    damage_rect: Option<Rect>,
+
+    /// Where the frame being painted is painted, between [`Self::begin_frame`] and [`Self::end_frame`].
+    paint_frame: Option<PaintFrame>,
+
+    /// What to restore at [`Self::end_frame`].
+    host_state: Option<HostState>,
+
+    /// The number of samples to anti-alias with, or zero for none. See [`Self::new_with_msaa`].
+    msaa_samples: i32,
+
+    /// Set with [`Self::set_supersample_factor`].
+    supersample_factor: f32,
+
+    /// The framebuffer egui is painted into when multisampling, supersampling or using a color LUT.
+    ///
+    /// Created on first use, and recreated when the size changes.
+    intermediate_framebuffer: Option<IntermediateFramebuffer>,
+
+    /// The id [`IntermediateFramebuffer::texture`] is registered with.
+    intermediate_texture_id: Option<egui::TextureId>,
+
+    is_webgl_1: bool,
+    vao: crate::vao::VertexArrayObject,
+    srgb_textures: bool,
+    supports_srgb_framebuffer: bool,
+    supports_texture_swizzle: bool,
+    supports_unpack_row_length: bool,
+    supports_clamp_to_border: bool,
+    supports_mipmap_levels: bool,
+    supports_lod_bias: bool,
+    supports_float_linear: bool,
+    supports_texture_storage: bool,
+    supports_samplers: bool,
+    supports_async_screen_read: bool,
+    supports_timer_queries: bool,
+    vbo: StreamingBuffer,
+    element_array_buffer: StreamingBuffer,
+
+    /// Set with [`PainterBuilder::shared_textures`].
+    textures: SharedTextures,
+
+    /// Set with [`Self::set_retain_texture_data`].
+    retained_textures: Option<HashMap<egui::TextureId, RetainedTexture>>,
+
+    /// Set with [`Self::set_sampler_options`].
+    sampler_options: HashMap<egui::TextureId, SamplerOptions>,
+
+    /// The texture array layers that textures are copies of.
+    array_layers: HashMap<egui::TextureId, ArrayLayer>,
+
+    /// Sampler objects, created on first use, if [`Self::supports_samplers`].
+    samplers: HashMap<(egui::TextureOptions, SamplerOptions), glow::Sampler>,
+
+    /// Stores outdated OpenGL textures that are yet to be deleted
+    textures_to_destroy: Vec<glow::Texture>,
+
+    /// Have we warned that WebGL1 can't repeat or mipmap non-power-of-two textures?
+    warned_about_npot_textures: bool,
+
+    /// Have we warned that [`TextureFormat::Rgba32F`] textures can't be filtered linearly here?
+    warned_about_float_filtering: bool,
+
+    /// Are we uploading the texture deltas of [`Self::set_textures`]?
+    batching_uploads: bool,
+
+    /// The texture bound to `TEXTURE_2D` by the current batch of uploads, if any.
+    batch_bound_texture: Option<glow::Texture>,
+
+    /// Reused to merge consecutive meshes into one draw call.
+    merged_mesh: Mesh,
+
+    /// Reused to upload the indices of meshes as `u16`.
+    indices_u16: Vec<u16>,
+
+    /// Set with [`Self::set_mesh_cache_budget`].
+    mesh_cache: Option<MeshCache>,
+
+    stats: PaintStats,
+
+    /// Set with [`Self::set_gpu_timing`].
+    gpu_timer: Option<GpuTimer>,
+
+    /// What went wrong painting this frame: missing textures, GL errors and failed callbacks.
+    paint_errors: Vec<PainterError>,
+
+    /// What the callback being painted asked for, e.g. with [`Self::paint_user_mesh`],
+    /// or `None` outside of callbacks.
+    callback_commands: RefCell<Option<Vec<CallbackCommand>>>,
+
+    /// The masks pushed with [`Self::push_clip_mask`] this frame, which are in the stencil buffer.
+    clip_masks: Vec<Mesh>,
+
+    /// How many masks were pushed with [`Self::push_clip_mask`] without a stencil buffer to put them in.
+    ignored_clip_masks: usize,
+
+    /// Set with [`Self::register_callback_renderer`].
+    callback_renderers: HashMap<egui::Id, Box<dyn CallbackRenderer>>,
+
+    /// Started with [`Self::begin_screen_read`].
+    screen_reads: HashMap<ScreenReadId, PendingScreenRead>,
+    next_screen_read_id: u64,
+
+    /// Used to make sure we are destroyed correctly.
+    destroyed: bool,
+
+    /// Set with [`Self::set_destroy_on_drop`].
+    destroy_on_drop: bool,
+}
+
+/// A callback function that can be used to compose an [`egui::PaintCallback`] for custom rendering
+/// with [`glow`].
+///
+/// The callback is passed, the [`egui::PaintCallbackInfo`] and the [`Painter`] which can be used to
+/// access the OpenGL context.
+///
+/// # Example
+///
+/// See the [`custom3d_glow`](https://github.com/emilk/egui/blob/main/crates/egui_demo_app/src/apps/custom3d_wgpu.rs) demo source for a detailed usage example.
+pub struct CallbackFn {
+    f: BoxedCallbackFn,
+}
+
+type BoxedCallbackFn = Box<
+    dyn Fn(PaintCallbackInfo, &CallbackTarget, &Painter) -> Result<(), PainterError> + Sync + Send,
+>;
+
+impl CallbackFn {
+    pub fn new<F: Fn(PaintCallbackInfo, &Painter) + Sync + Send + 'static>(callback: F) -> Self {
+        Self::new_fallible(move |info, painter| {
+            callback(info, painter);
+            Ok(())
+        })
+    }
+
+    /// A callback that can fail, e.g. on a missing resource.
+    ///
+    /// The errors of a frame are returned by [`Painter::try_paint_primitives`],
+    /// and logged by [`Painter::paint_primitives`].
+    pub fn new_fallible<
+        F: Fn(PaintCallbackInfo, &Painter) -> Result<(), PainterError> + Sync + Send + 'static,
+    >(
+        callback: F,
+    ) -> Self {
+        Self::new_with_target(move |info, _target, painter| callback(info, painter))
+    }
+
+    /// A callback that is also given where egui is painting, see [`CallbackTarget`].
+    ///
+    /// Like [`Self::new_fallible`], the callback can fail.
+    pub fn new_with_target<
+        F: Fn(PaintCallbackInfo, &CallbackTarget, &Painter) -> Result<(), PainterError>
+            + Sync
+            + Send
+            + 'static,
+    >(
+        callback: F,
+    ) -> Self {
+        let f = Box::new(callback);
+        Self { f }
+    }
+}
+
+/// Like [`CallbackFn`], but for a callback that mutates what it captures,
+/// e.g. to keep a rotation angle or a cache from frame to frame.
+///
+/// The callback is kept behind a mutex, which is locked while it is called.
+pub struct CallbackFnMut {
+    f: Mutex<BoxedCallbackFnMut>,
+}
+
+type BoxedCallbackFnMut =
+    Box<dyn FnMut(PaintCallbackInfo, &CallbackTarget, &Painter) -> Result<(), PainterError> + Send>;
+
+impl CallbackFnMut {
+    pub fn new<F: FnMut(PaintCallbackInfo, &Painter) + Send + 'static>(mut callback: F) -> Self {
+        Self::new_fallible(move |info, painter| {
+            callback(info, painter);
+            Ok(())
+        })
+    }
+
+    /// A callback that can fail, see [`CallbackFn::new_fallible`].
+    pub fn new_fallible<
+        F: FnMut(PaintCallbackInfo, &Painter) -> Result<(), PainterError> + Send + 'static,
+    >(
+        mut callback: F,
+    ) -> Self {
+        Self::new_with_target(move |info, _target, painter| callback(info, painter))
+    }
+
+    /// A callback that is also given where egui is painting, see [`CallbackFn::new_with_target`].
+    pub fn new_with_target<
+        F: FnMut(PaintCallbackInfo, &CallbackTarget, &Painter) -> Result<(), PainterError>
+            + Send
+            + 'static,
+    >(
+        callback: F,
+    ) -> Self {
+        let f = Mutex::new(Box::new(callback) as BoxedCallbackFnMut);
+        Self { f }
+    }
+}
+
+/// Where egui is painting when a callback is called, for callbacks that paint into
+/// framebuffers of their own before painting onto egui's.
+pub struct CallbackTarget {
+    /// The framebuffer egui is painting into, which is bound when the callback is called.
+    ///
+    /// This is [`Painter::intermediate_fbo`], or else the framebuffer that was bound when painting began.
+    pub framebuffer: Option<glow::Framebuffer>,
+
+    /// The viewport of the callback in physical pixels, which is set when the callback is called.
+    pub viewport_px: ViewportInPixels,
+
+    /// The clip rectangle of the callback in physical pixels.
+    ///
+    /// egui sets its scissor box to this, but leaves the scissor test for the callback to enable.
+    pub clip_rect_px: ViewportInPixels,
+}
+
+impl CallbackTarget {
+    /// Bind [`Self::framebuffer`] and set the viewport to [`Self::viewport_px`] again,
+    /// e.g. after painting into a framebuffer of your own.
+    pub fn restore(&self, gl: &glow::Context) {
+        let ViewportInPixels {
+            left_px,
+            from_bottom_px,
+            width_px,
+            height_px,
+            ..
+        } = self.viewport_px;
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, self.framebuffer);
+            gl.viewport(left_px, from_bottom_px, width_px, height_px);
+        }
+    }
+}
+
+impl Painter {
+    /// Create painter.
+    ///
+    /// Set `pp_fb_extent` to the framebuffer size to enable `sRGB` support on OpenGL ES and WebGL.
+    ///
+    /// Set `shader_prefix` if you want to turn on shader workaround e.g. `"#define APPLY_BRIGHTENING_GAMMA\n"`
+    /// (see <https://github.com/emilk/egui/issues/794>), or adjust [`Self::set_gamma`] at runtime instead.
+    ///
+    /// # Errors
+    /// will return `Err` below cases
+    /// * failed to compile shader (unless it's compiled in parallel, see [`Self::is_ready`])
+    /// * failed to create postprocess on webgl with `sRGB` support
+    /// * failed to create buffer
+    ///
+    /// See [`crate::PainterBuilder`] for more options.
+    pub fn new(
+        gl: Arc<glow::Context>,
+        shader_prefix: &str,
+        shader_version: Option<ShaderVersion>,
+        dithering: bool,
+    ) -> Result<Self, PainterError> {
+        PainterBuilder::new()
+            .shader_prefix(shader_prefix)
+            .shader_version(shader_version)
+            .dithering(dithering)
+            .build(gl)
+    }
+
+    /// Everything [`PainterBuilder::build`] does before applying the options that have setters.
+    pub(crate) fn new_impl(
+        gl: Arc<glow::Context>,
+        shader_prefix: &str,
+        shader_version: Option<ShaderVersion>,
+        fragment_postprocess: Option<&str>,
+        dithering: bool,
+    ) -> Result<Self, PainterError> {
+        profiling::function_scope!();
+        crate::check_for_gl_error_even_in_release!(&gl, "before Painter::new");
+
+        // some useful debug info. all three of them are present in gl 1.1.
+        let (gl_version_string, renderer_string, vendor_string) = unsafe {
+            let version = gl.get_parameter_string(glow::VERSION);
+            let renderer = gl.get_parameter_string(glow::RENDERER);
+            let vendor = gl.get_parameter_string(glow::VENDOR);
+            log::debug!(
+                "\nopengl version: {version}\nopengl renderer: {renderer}\nopengl vendor: {vendor}"
+            );
+            (version, renderer, vendor)
+        };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if gl.version().major < 2 {
+            // this checks on desktop that we are not using opengl 1.1 microsoft sw rendering context.
+            // ShaderVersion::get fn will segfault due to SHADING_LANGUAGE_VERSION (added in gl2.0)
+            return Err(PainterError::Other(
+                "egui_glow requires opengl 2.0+. ".to_owned(),
+            ));
+        }
+
+        let max_texture_side = unsafe { gl.get_parameter_i32(glow::MAX_TEXTURE_SIZE) } as usize;
+        let max_texture_image_units =
+            unsafe { gl.get_parameter_i32(glow::MAX_TEXTURE_IMAGE_UNITS) } as usize;
+        let shader_version = shader_version.unwrap_or_else(|| ShaderVersion::get(&gl));
+        let is_webgl_1 = shader_version == ShaderVersion::Es100;
+        log::debug!("Shader header: {:?}.", shader_version.version_declaration());
+
+        let supported_extensions = gl.supported_extensions();
+        log::trace!("OpenGL extensions: {supported_extensions:?}");
+        let srgb_textures = false; // egui wants normal sRGB-unaware textures
+
+        let supports_srgb_framebuffer = !cfg!(target_arch = "wasm32")
+            && supported_extensions.iter().any(|extension| {
+                // {GL,GLX,WGL}_ARB_framebuffer_sRGB, …
+                extension.ends_with("ARB_framebuffer_sRGB")
+            });
+        log::debug!("SRGB framebuffer Support: {supports_srgb_framebuffer}");
+
+        // WebGL2 does not have texture swizzling, even though OpenGL ES 3.0 does.
+        let supports_texture_swizzle = !cfg!(target_arch = "wasm32")
+            && (is_gl_version_at_least(&gl, [3, 3], [3, 0])
+                || supported_extensions
+                    .iter()
+                    .any(|extension| extension.ends_with("ARB_texture_swizzle")));
+        log::debug!("Texture swizzle support: {supports_texture_swizzle}");
+
+        let supports_unpack_row_length = !is_webgl_1 && is_gl_version_at_least(&gl, [1, 0], [3, 0]);
+
+        // WebGL has no border clamping at all.
+        let supports_clamp_to_border = !cfg!(target_arch = "wasm32")
+            && (is_gl_version_at_least(&gl, [1, 3], [3, 2])
+                || supported_extensions
+                    .iter()
+                    .any(|extension| extension.ends_with("texture_border_clamp")));
+        log::debug!("Clamp to border support: {supports_clamp_to_border}");
+
+        let max_anisotropy = supported_extensions
+            .iter()
+            .any(|extension| extension.ends_with("texture_filter_anisotropic"))
+            .then(|| unsafe { gl.get_parameter_f32(glow::MAX_TEXTURE_MAX_ANISOTROPY_EXT) });
+        log::debug!("Max anisotropy: {max_anisotropy:?}");
+
+        let supports_mipmap_levels = !is_webgl_1 && is_gl_version_at_least(&gl, [1, 2], [3, 0]);
+
+        let supports_texture_storage = !is_webgl_1
+            && (is_gl_version_at_least(&gl, [4, 2], [3, 0])
+                || supported_extensions
+                    .iter()
+                    .any(|extension| extension.ends_with("ARB_texture_storage")));
+        log::debug!("Immutable texture storage support: {supports_texture_storage}");
+
+        let supports_samplers = !is_webgl_1 && is_gl_version_at_least(&gl, [3, 3], [3, 0]);
+        log::debug!("Sampler object support: {supports_samplers}");
+
+        // Pixel buffer objects need OpenGL 2.1, but checking on them needs fences:
+        let supports_async_screen_read = !is_webgl_1 && is_gl_version_at_least(&gl, [3, 2], [3, 0]);
+
+        let supports_timer_queries = GpuTimer::is_supported(&gl, is_webgl_1);
+        log::debug!("Timer query support: {supports_timer_queries}");
+
+        // OpenGL ES and WebGL only have a per-sample bias in the shader.
+        let supports_lod_bias = !cfg!(target_arch = "wasm32") && {
+            let version = gl.version();
+            !version.is_embedded && [version.major, version.minor] >= [1, 4]
+        };
+
+        // Desktop OpenGL can always filter float textures, OpenGL ES and WebGL only with this:
+        let supports_float_linear = !gl.version().is_embedded
+            || supported_extensions
+                .iter()
+                .any(|extension| extension.ends_with("OES_texture_float_linear"));
+        log::debug!("Linear filtering of float textures: {supports_float_linear}");
+
+        let shader_source = ShaderSource {
+            version: shader_version,
+            prefix: shader_prefix.to_owned(),
+            postprocess: fragment_postprocess.map(ToOwned::to_owned),
+            vert: None,
+            frag: None,
+        };
+
+        unsafe {
+            let pending = PendingProgram::compile(&gl, &shader_source, ShaderVariant::Default)?;
+            // {GL,WebGL}_KHR_parallel_shader_compile, GL_ARB_parallel_shader_compile:
+            let program = if supported_extensions
+                .iter()
+                .any(|extension| extension.ends_with("parallel_shader_compile"))
+            {
+                log::debug!("Compiling the shader in parallel");
+                MainProgram::Compiling(pending)
+            } else {
+                match pending.finish(&gl) {
+                    Ok(program) => MainProgram::Ready(program),
+                    Err(err) => {
+                        gl.delete_program(pending.program);
+                        return Err(err);
+                    }
+                }
+            };
+
+            let vbo = gl.create_buffer()?;
+
+            let [a_pos_loc, a_tc_loc, a_srgba_loc] = ATTRIBUTE_LOCATIONS;
+
+            let stride = std::mem::size_of::<Vertex>() as i32;
+            let buffer_infos = vec![
+                vao::BufferInfo {
+                    location: a_pos_loc,
+                    vector_size: 2,
+                    data_type: glow::FLOAT,
+                    normalized: false,
+                    stride,
+                    offset: offset_of!(Vertex, pos) as i32,
+                },
+                vao::BufferInfo {
+                    location: a_tc_loc,
+                    vector_size: 2,
+                    data_type: glow::FLOAT,
+                    normalized: false,
+                    stride,
+                    offset: offset_of!(Vertex, uv) as i32,
+                },
+                vao::BufferInfo {
+                    location: a_srgba_loc,
+                    vector_size: 4,
+                    data_type: glow::UNSIGNED_BYTE,
+                    normalized: false,
+                    stride,
+                    offset: offset_of!(Vertex, color) as i32,
+                },
+            ];
+            let vao = crate::vao::VertexArrayObject::new(&gl, vbo, buffer_infos);
+
+            let element_array_buffer = gl.create_buffer()?;
+
+            // WebGL1 writes every mesh to the start of the buffers instead:
+            let vbo = StreamingBuffer::new(vbo, glow::ARRAY_BUFFER, !is_webgl_1);
+            let element_array_buffer = StreamingBuffer::new(
+                element_array_buffer,
+                glow::ELEMENT_ARRAY_BUFFER,
+                !is_webgl_1,
+            );
+
+            crate::check_for_gl_error_even_in_release!(&gl, "after Painter::new");
+
+            Ok(Self {
+                gl,
+                max_texture_side,
+                max_texture_image_units,
+                gl_version_string,
+                renderer_string,
+                vendor_string,
+                oversize_policy: OversizePolicy::default(),
+                missing_texture_policy: MissingTexturePolicy::default(),
+                clip_rounding: ClipRounding::default(),
+                max_indices_per_draw: None,
+                fallback_texture: None,
+                max_anisotropy,
+                shader_source,
+                program,
+                external_oes_program: None,
+                using_external_oes_program: false,
+                color_lut_program: None,
+                bound_texture: None,
+                bound_element_array_buffer: None,
+                dithering,
+                gamma: 1.0,
+                color_matrix: IDENTITY_COLOR_MATRIX,
+                transform: IDENTITY_TRANSFORM,
+                color_lut: None,
+                srgb_framebuffer: false,
+                blend_mode: BlendMode::default(),
+                preserve_gl_state: false,
+                debug_labels: false,
+                render_target: None,
+                viewport: None,
+                damage_rect: None,
+                paint_frame: None,
+                host_state: None,
+                msaa_samples: 0,
+                supersample_factor: 1.0,
+                intermediate_framebuffer: None,
+                intermediate_texture_id: None,
+                is_webgl_1,
+                vao,
+                srgb_textures,
+                supports_srgb_framebuffer,
+                supports_texture_swizzle,
+                supports_unpack_row_length,
+                supports_clamp_to_border,
+                supports_mipmap_levels,
+                supports_lod_bias,
+                supports_float_linear,
+                supports_texture_storage,
+                supports_samplers,
+                supports_async_screen_read,
+                supports_timer_queries,
+                vbo,
+                element_array_buffer,
+                textures: Default::default(),
+                retained_textures: None,
+                sampler_options: Default::default(),
+                array_layers: Default::default(),
+                samplers: Default::default(),
+                textures_to_destroy: Vec::new(),
+                warned_about_npot_textures: false,
+                warned_about_float_filtering: false,
+                batching_uploads: false,
+                batch_bound_texture: None,
+                merged_mesh: Mesh::default(),
+                indices_u16: Vec::new(),
+                mesh_cache: None,
+                stats: PaintStats::default(),
+                gpu_timer: None,
+                paint_errors: Vec::new(),
+                callback_commands: RefCell::new(None),
+                clip_masks: Vec::new(),
+                ignored_clip_masks: 0,
+                callback_renderers: HashMap::default(),
+                screen_reads: HashMap::default(),
+                next_screen_read_id: 0,
+                destroyed: false,
+                destroy_on_drop: false,
+            })
+        }
+    }
+
+    /// Like [`Self::new`], but anti-aliases everything egui paints with multisampling,
+    /// for when the framebuffer being painted into can't be multisampled itself, e.g. on the web.
+    ///
+    /// egui is painted into a multisampled framebuffer with `msaa_samples` samples,
+    /// which is then resolved and painted onto the framebuffer that was bound (or [`Self::set_render_target`]).
+    /// Callbacks paint into the multisampled framebuffer too, see [`Self::intermediate_fbo`].
+    ///
+    /// The number of samples is clamped to `GL_MAX_SAMPLES`.
+    /// Multisampled framebuffers require OpenGL 3.0 or OpenGL ES 3.0 (WebGL2),
+    /// and without them this logs a warning and paints without anti-aliasing.
+    ///
+    /// # Errors
+    /// Same as [`Self::new`].
+    pub fn new_with_msaa(
+        gl: Arc<glow::Context>,
+        shader_prefix: &str,
+        shader_version: Option<ShaderVersion>,
+        dithering: bool,
+        msaa_samples: u8,
+    ) -> Result<Self, PainterError> {
+        PainterBuilder::new()
+            .shader_prefix(shader_prefix)
+            .shader_version(shader_version)
+            .dithering(dithering)
+            .msaa_samples(msaa_samples)
+            .build(gl)
+    }
+
+    /// See [`Self::new_with_msaa`].
+    pub(crate) fn init_msaa(&mut self, msaa_samples: u8) {
+        if msaa_samples <= 1 {
+            return;
+        }
+        if self.is_webgl_1 || !is_gl_version_at_least(&self.gl, [3, 0], [3, 0]) {
+            log::warn!(
+                "Multisampled framebuffers require OpenGL 3.0 or OpenGL ES 3.0. Painting without anti-aliasing."
+            );
+        } else {
+            let max_samples = unsafe { self.gl.get_parameter_i32(glow::MAX_SAMPLES) };
+            if max_samples < msaa_samples as i32 {
+                log::warn!(
+                    "{msaa_samples} MSAA samples requested, but at most {max_samples} are supported"
+                );
+            }
+            self.msaa_samples = (msaa_samples as i32).min(max_samples);
+        }
+    }
+
+    /// Access the shared glow context.
+    pub fn gl(&self) -> &Arc<glow::Context> {
+        &self.gl
+    }
+
+    /// The egui shader program, e.g. to paint your own meshes with it. See also [`ATTRIBUTE_LOCATIONS`].
+    ///
+    /// Its uniforms are set before each frame, and the painter assumes they keep their values,
+    /// so changing them (or relinking the program) may leave egui painted wrong.
+    /// Textures that need another variant of the shader (e.g. `GL_TEXTURE_EXTERNAL_OES` ones) are painted with that instead.
+    pub fn program(&self) -> glow::Program {
+        self.program.program()
+    }
+
+    /// Has the egui shader finished compiling?
+    ///
+    /// With `KHR_parallel_shader_compile`, [`Self::new`] only starts compiling the shader, so it doesn't block.
+    /// Until it's done, [`Self::paint_primitives`] paints nothing, so show e.g. a spinner some other way meanwhile.
+    /// Without the extension, the shader is compiled by [`Self::new`], and this is always `true`.
+    ///
+    /// Also `true` if compiling failed, which every paint then reports as an error, until [`Self::reload_shaders`] succeeds.
+    pub fn is_ready(&self) -> bool {
+        match &self.program {
+            MainProgram::Compiling(pending) => unsafe { pending.is_complete(&self.gl) },
+            MainProgram::Ready(_) | MainProgram::Failed(..) => true,
+        }
+    }
+
+    /// Compile the egui shader from other sources, e.g. to try out changes to it without recreating the painter.
+    ///
+    /// `None` stands for the built-in source. Like to the built-in sources, the `#version`, the `#define`s
+    /// and the shader prefix are prepended, and [`PainterBuilder::fragment_postprocess`] is appended to the fragment shader.
+    /// The shaders need the attributes and uniforms of the built-in ones, since the painter sets them all.
+    ///
+    /// The variants of the shader the painter uses (e.g. for [`Self::set_color_lut`]) are compiled again too.
+    /// Only once they all compiled are the old programs deleted, so on failure the painter keeps painting with those.
+    /// The textures and the rest of the painter are kept either way.
+    ///
+    /// # Errors
+    /// - If a shader fails to compile or link, see [`PainterError::ShaderCompile`].
+    /// - If a uniform of the egui shader is missing (or optimized away, since it isn't used).
+    /// - Between [`Self::begin_frame`] and [`Self::end_frame`].
+    pub fn reload_shaders(
+        &mut self,
+        vert_src: Option<&str>,
+        frag_src: Option<&str>,
+    ) -> Result<(), PainterError> {
+        self.assert_not_destroyed();
+
+        if self.paint_frame.is_some() {
+            return Err(PainterError::Other(
+                "Painter::reload_shaders can't be called between begin_frame and end_frame"
+                    .to_owned(),
+            ));
+        }
+
+        let source = ShaderSource {
+            version: self.shader_source.version,
+            prefix: self.shader_source.prefix.clone(),
+            postprocess: self.shader_source.postprocess.clone(),
+            vert: vert_src.map(ToOwned::to_owned),
+            frag: frag_src.map(ToOwned::to_owned),
+        };
+        let compile = |variant| unsafe { ShaderProgram::new(&self.gl, &source, variant) };
+        let delete = |program: &ShaderProgram| unsafe { self.gl.delete_program(program.program) };
+
+        let program = compile(ShaderVariant::Default)?;
+        let external_oes_program = match self
+            .external_oes_program
+            .as_ref()
+            .map(|_| compile(ShaderVariant::ExternalOes))
+            .transpose()
+        {
+            Ok(external_oes_program) => external_oes_program,
+            Err(err) => {
+                delete(&program);
+                return Err(err);
+            }
+        };
+        let color_lut_program = match self
+            .color_lut_program
+            .as_ref()
+            .map(|_| compile(ShaderVariant::ColorLut))
+            .transpose()
+        {
+            Ok(color_lut_program) => color_lut_program,
+            Err(err) => {
+                delete(&program);
+                external_oes_program.iter().for_each(delete);
+                return Err(err);
+            }
+        };
+
+        unsafe { self.delete_programs() };
+        self.program = MainProgram::Ready(program);
+        self.external_oes_program = external_oes_program;
+        self.color_lut_program = color_lut_program;
+        self.shader_source = source;
+        if self.debug_labels {
+            self.set_debug_labels(true);
+        }
+        Ok(())
+    }
+
+    /// Finish compiling the egui shader if the driver is done with it, or if `wait`ing for it,
+    /// and return whether it can be painted with. If it failed to compile, that's reported as a paint error every time.
+    fn finish_program(&mut self, wait: bool) -> bool {
+        if let MainProgram::Compiling(pending) = &self.program {
+            if !wait && !unsafe { pending.is_complete(&self.gl) } {
+                return false;
+            }
+            let program = pending.program;
+            self.program = match unsafe { pending.finish(&self.gl) } {
+                Ok(program) => MainProgram::Ready(program),
+                Err(err) => MainProgram::Failed(program, err),
+            };
+        }
+        match &self.program {
+            MainProgram::Ready(_) => true,
+            MainProgram::Compiling(_) => false,
+            MainProgram::Failed(_, err) => {
+                self.paint_errors.push(err.clone());
+                false
+            }
+        }
+    }
+
+    /// The egui shader, once [`Self::finish_program`] has made sure it's ready.
+    fn main_program(&self) -> &ShaderProgram {
+        match &self.program {
+            MainProgram::Ready(program) => program,
+            MainProgram::Compiling(_) | MainProgram::Failed(..) => {
+                panic!("The egui shader is not ready to paint with")
+            }
+        }
+    }
+
+    /// The buffer the vertices of egui are uploaded to, as [`egui::epaint::Vertex`].
+    ///
+    /// Meshes are streamed into it at varying offsets (or drawn from their own buffers with
+    /// [`Self::set_mesh_cache_budget`]), so don't rely on what it contains outside of painting.
+    /// Reallocating or deleting it will desync the painter.
+    pub fn vertex_buffer(&self) -> glow::Buffer {
+        self.vbo.buffer
+    }
+
+    /// The buffer the indices of egui are uploaded to, as `u16` or `u32`.
+    ///
+    /// Same caveats as [`Self::vertex_buffer`].
+    pub fn element_buffer(&self) -> glow::Buffer {
+        self.element_array_buffer.buffer
+    }
+
+    pub fn max_texture_side(&self) -> usize {
+        self.max_texture_side
+    }
+
+    /// What to do with textures larger than [`Self::max_texture_side`].
+    ///
+    /// With [`OversizePolicy::Downscale`], e.g. a user-supplied 16k image is shown at 8k
+    /// on a GPU that only supports 8k textures, instead of failing to upload.
+    ///
+    /// Default: [`OversizePolicy::Error`].
+    pub fn set_oversize_policy(&mut self, oversize_policy: OversizePolicy) {
+        self.oversize_policy = oversize_policy;
+    }
+
+    pub fn oversize_policy(&self) -> OversizePolicy {
+        self.oversize_policy
+    }
+
+    /// What to do with meshes whose texture is missing, e.g. because it was freed too early.
+    ///
+    /// [`MissingTexturePolicy::Fallback`] is useful during development,
+    /// since the missing texture shows up as a magenta checkerboard instead of a gap.
+    ///
+    /// Default: [`MissingTexturePolicy::Error`].
+    pub fn set_missing_texture_policy(&mut self, missing_texture_policy: MissingTexturePolicy) {
+        self.missing_texture_policy = missing_texture_policy;
+    }
+
+    pub fn missing_texture_policy(&self) -> MissingTexturePolicy {
+        self.missing_texture_policy
+    }
+
+    /// How the edges of clip rectangles are snapped to whole pixels.
+    ///
+    /// Use [`ClipRounding::Nearest`] or [`ClipRounding::Expand`] if text at the edge of a panel
+    /// shimmers while the window is being resized.
+    ///
+    /// Default: [`ClipRounding::Round`].
+    pub fn set_clip_rounding(&mut self, clip_rounding: ClipRounding) {
+        self.clip_rounding = clip_rounding;
+    }
+
+    pub fn clip_rounding(&self) -> ClipRounding {
+        self.clip_rounding
+    }
+
+    /// Split meshes with more indices than this into several `draw_elements` calls,
+    /// to work around drivers that silently drop the geometry of large draws.
+    ///
+    /// Rounded down to whole triangles. Each draw counts towards [`PaintStats::draw_calls`].
+    ///
+    /// Default: `None` (unlimited).
+    pub fn set_max_indices_per_draw(&mut self, max_indices_per_draw: Option<usize>) {
+        self.max_indices_per_draw = max_indices_per_draw;
+    }
+
+    pub fn max_indices_per_draw(&self) -> Option<usize> {
+        self.max_indices_per_draw
+    }
+
+    /// The highest [`SamplerOptions::anisotropy`] supported,
+    /// or `None` if the context lacks `EXT_texture_filter_anisotropic`.
+    pub fn max_anisotropy(&self) -> Option<f32> {
+        self.max_anisotropy
+    }
+
+    /// The `GL_VERSION` of the context, e.g. "4.6.0 NVIDIA 535.183.01" or "WebGL 2.0".
+    ///
+    /// Useful for bug reports, without having to query (on the right thread) yourself.
+    pub fn gl_version_string(&self) -> &str {
+        &self.gl_version_string
+    }
+
+    /// The `GL_RENDERER` of the context, which is usually the name of the GPU.
+    pub fn renderer_string(&self) -> &str {
+        &self.renderer_string
+    }
+
+    /// The `GL_VENDOR` of the context, which is usually the company behind the driver.
+    pub fn vendor_string(&self) -> &str {
+        &self.vendor_string
+    }
+
+    /// The version egui's shaders are compiled with,
+    /// either the one passed to [`Self::new`] or the one detected from the context.
+    pub fn glsl_version(&self) -> ShaderVersion {
+        self.shader_source.version
+    }
+
+    /// What the OpenGL context can do, as detected by [`Self::new`].
+    pub fn capabilities(&self) -> PainterCapabilities {
+        PainterCapabilities {
+            max_texture_side: self.max_texture_side,
+            max_texture_image_units: self.max_texture_image_units,
+            max_anisotropy: self.max_anisotropy,
+            shader_version: self.shader_source.version,
+            is_webgl_1: self.is_webgl_1,
+            supports_srgb_framebuffer: self.supports_srgb_framebuffer,
+            supports_texture_swizzle: self.supports_texture_swizzle,
+            supports_clamp_to_border: self.supports_clamp_to_border,
+            supports_async_screen_read: self.supports_async_screen_read,
+            supports_timer_queries: self.supports_timer_queries,
+            supports_debug: self.gl.supports_debug(),
+            supports_vertex_array_objects: !self.vao.is_emulated(),
+        }
+    }
+
+    /// The framebuffer we use as an intermediate render target,
+    /// or `None` if we are painting to the screen framebuffer directly.
+    ///
+    /// This is the framebuffer that is bound when [`egui::Shape::Callback`] is called,
+    /// and is where any callbacks should ultimately render onto.
+    ///
+    /// So if in a [`egui::Shape::Callback`] you need to use an offscreen FBO, you should
+    /// then restore to this afterwards with
+    /// `gl.bind_framebuffer(glow::FRAMEBUFFER, painter.intermediate_fbo());`
+    /// or with [`CallbackTarget::restore`], for callbacks made with [`CallbackFn::new_with_target`].
+    pub fn intermediate_fbo(&self) -> Option<glow::Framebuffer> {
+        self.intermediate_framebuffer
+            .as_ref()
+            .map(|intermediate_framebuffer| intermediate_framebuffer.fbo)
+            .or(self.render_target)
+    }
+
+    /// Paint egui at `factor` times the resolution into an offscreen framebuffer,
+    /// and then downsample it with linear filtering onto the framebuffer being painted into.
+    ///
+    /// This smooths the edges of everything egui paints, at the cost of `factor²` times the fill rate.
+    /// A factor of 2 averages each 2x2 block of pixels, while larger factors skip some pixels,
+    /// so 2 is the sweet spot. Non-integer factors work, but blur the result.
+    ///
+    /// Callbacks are passed the supersampled screen size and `pixels_per_point`.
+    /// The factor is reduced if the supersampled framebuffer would exceed [`Self::max_texture_side`].
+    /// Can be combined with [`Self::new_with_msaa`]. `1.0` (the default) turns supersampling off.
+    pub fn set_supersample_factor(&mut self, factor: f32) {
+        self.supersample_factor = factor.max(1.0);
+        if !self.uses_intermediate_framebuffer() {
+            self.destroy_intermediate_framebuffer();
+        }
+    }
+
+    pub fn supersample_factor(&self) -> f32 {
+        self.supersample_factor
+    }
+
+    /// Map the colors of everything egui paints through a 3D lookup table, e.g. for color grading.
+    ///
+    /// `color_lut` is a `TEXTURE_3D` texture of your own, indexed by the red, green and blue
+    /// of a color (in gamma space, without premultiplied alpha) along its width, height and depth.
+    /// Give it `LINEAR` filtering and `CLAMP_TO_EDGE` wrapping, and keep it alive while it is set.
+    ///
+    /// egui is painted into an offscreen framebuffer, which is then painted through the table
+    /// onto the framebuffer being painted into. The table is bound to texture unit 1 while painting.
+    ///
+    /// The lookup table requires OpenGL 3.1 or OpenGL ES 3.0 (WebGL2),
+    /// and without them this logs a warning and does nothing.
+    /// `None` (the default) turns the lookup table off.
+    pub fn set_color_lut(&mut self, color_lut: Option<glow::Texture>) {
+        if color_lut.is_some() && self.color_lut_program.is_none() {
+            if self.is_webgl_1 || !self.shader_source.version.is_new_shader_interface() {
+                log::warn!(
+                    "3D color lookup tables require OpenGL 3.1 or OpenGL ES 3.0. Ignoring the color LUT."
+                );
+                return;
+            }
+            match unsafe {
+                ShaderProgram::new(&self.gl, &self.shader_source, ShaderVariant::ColorLut)
+            } {
+                Ok(program) => {
+                    self.debug_label(
+                        DebugObject::Program(program.program),
+                        format_args!("egui color LUT program"),
+                    );
+                    self.color_lut_program = Some(program);
+                }
+                Err(err) => {
+                    log::warn!("{err}. Ignoring the color LUT.");
+                    return;
+                }
+            }
+        }
+
+        self.color_lut = color_lut;
+        if !self.uses_intermediate_framebuffer() {
+            self.destroy_intermediate_framebuffer();
+        }
+    }
+
+    pub fn color_lut(&self) -> Option<glow::Texture> {
+        self.color_lut
+    }
+
+    /// Enable `GL_FRAMEBUFFER_SRGB` while painting, for when the framebuffer being painted into
+    /// is sRGB-capable and you want the hardware to encode its colors as sRGB. Off by default.
+    ///
+    /// egui's colors are already sRGB-encoded (in gamma space), so with this on,
+    /// the shader decodes them to linear, for the hardware to encode them back on write.
+    /// Don't also encode them yourself, or the colors will come out twice as bright.
+    /// Blending then happens in linear space, so anti-aliased edges and translucent
+    /// shapes look a little different than egui intends.
+    /// On a framebuffer that isn't sRGB-capable, the colors come out too dark.
+    ///
+    /// Only supported on desktop OpenGL with `ARB_framebuffer_sRGB`; elsewhere this logs a warning and does nothing.
+    /// Off, `GL_FRAMEBUFFER_SRGB` is disabled while painting, as before.
+    pub fn set_srgb_framebuffer(&mut self, srgb_framebuffer: bool) {
+        if srgb_framebuffer && !self.supports_srgb_framebuffer {
+            log::warn!(
+                "This context doesn't support sRGB framebuffers. Ignoring set_srgb_framebuffer."
+            );
+            return;
+        }
+        self.srgb_framebuffer = srgb_framebuffer;
+    }
+
+    pub fn srgb_framebuffer(&self) -> bool {
+        self.srgb_framebuffer
+    }
+
+    /// Is egui painted into an offscreen framebuffer before it is painted onto the actual one?
+    fn uses_intermediate_framebuffer(&self) -> bool {
+        0 < self.msaa_samples || 1.0 < self.supersample_factor || self.color_lut.is_some()
+    }
+
+    /// Paint into a framebuffer of your own instead of the one bound when painting,
+    /// e.g. to use the UI as a texture in a 3D scene, or to post-process it.
+    ///
+    /// [`Self::paint_primitives`] binds the framebuffer while painting, and then rebinds the previous one.
+    /// [`Self::read_screen_rgba`] and [`Self::read_screen_rgb`] read from it.
+    /// Pass the size of the framebuffer as the screen size when painting.
+    ///
+    /// `None` (the default) paints into whatever framebuffer is bound.
+    pub fn set_render_target(&mut self, render_target: Option<glow::Framebuffer>) {
+        self.render_target = render_target;
+    }
+
+    /// Paint egui into a region of the framebuffer instead of all of it,
+    /// e.g. to letterbox the UI on an ultra-wide display, or leave a border around a kiosk UI.
+    ///
+    /// `origin_px` is the top left corner of the region in physical pixels, from the top left of the framebuffer,
+    /// and `size_px` its size, which is the screen size egui should lay out for.
+    /// Keep passing the size of the whole framebuffer as the screen size when painting.
+    ///
+    /// Everything egui paints is clipped to the region, and the [`CallbackTarget`] of paint callbacks is offset to it
+    /// (unlike the [`PaintCallbackInfo`], which is relative to the region).
+    /// Use [`Self::clear_viewport`] to clear only the region.
+    pub fn set_viewport(&mut self, origin_px: [u32; 2], size_px: [u32; 2]) {
+        self.viewport = Some((origin_px, size_px));
+    }
+
+    /// Paint egui into the whole framebuffer again, see [`Self::set_viewport`].
+    pub fn reset_viewport(&mut self) {
+        self.viewport = None;
+    }
+
+    /// The top left corner and size of the region set with [`Self::set_viewport`], if any.
+    pub fn viewport(&self) -> Option<([u32; 2], [u32; 2])> {
+        self.viewport
+    }
+
+    /// Where egui is painted in a framebuffer of the given size, see [`Self::set_viewport`].
+    fn viewport_in_pixels(&self, framebuffer_size_px: [u32; 2]) -> ViewportInPixels {
+        let ([left_px, top_px], [width_px, height_px]) =
+            self.viewport.unwrap_or(([0, 0], framebuffer_size_px));
+        ViewportInPixels {
+            left_px: left_px as i32,
+            top_px: top_px as i32,
+            from_bottom_px: framebuffer_size_px[1] as i32 - (top_px + height_px) as i32,
+            width_px: width_px as i32,
+            height_px: height_px as i32,
+        }
+    }
+
+    /// Set up the GL state for painting egui into `viewport_px`.
+    unsafe fn prepare_painting(&mut self, viewport_px: &ViewportInPixels, pixels_per_point: f32) {
+        let ViewportInPixels {
+            left_px,
+            from_bottom_px,
+            width_px,
+            height_px,
+            ..
+        } = *viewport_px;
+
+        unsafe {
+            if let Some(fbo) = self.intermediate_fbo() {
+                self.gl.bind_framebuffer(glow::FRAMEBUFFER, Some(fbo));
+            }
+
+            self.gl.enable(glow::SCISSOR_TEST);
+            // egui outputs mesh in both winding orders
+            self.gl.disable(glow::CULL_FACE);
+            self.gl.disable(glow::DEPTH_TEST);
+            self.set_clip_mask_stencil();
+
+            self.gl.color_mask(true, true, true, true);
+
+            self.apply_blend_mode(self.blend_mode);
+
+            if self.supports_srgb_framebuffer {
+                if self.srgb_framebuffer {
+                    self.gl.enable(glow::FRAMEBUFFER_SRGB);
+                } else {
+                    self.gl.disable(glow::FRAMEBUFFER_SRGB);
+                }
+                check_for_gl_error!(&self.gl, "FRAMEBUFFER_SRGB");
+            }
+            // The intermediate framebuffer isn't sRGB, so only the final pass onto the actual one is linear:
+            let linear_output = self.srgb_framebuffer && self.intermediate_framebuffer.is_none();
+
+            let width_in_points = width_px as f32 / pixels_per_point;
+            let height_in_points = height_px as f32 / pixels_per_point;
+
+            self.gl
+                .viewport(left_px, from_bottom_px, width_px, height_px);
+            let uniforms = ShaderUniforms {
+                screen_size: [width_in_points, height_in_points],
+                transform: transform_columns(&self.transform),
+                dithering: self.dithering as i32 as f32,
+                gamma: self.gamma,
+                color_matrix: column_major(&self.color_matrix),
+                linear_output: linear_output as i32 as f32,
+            };
+            // This runs again after every callback, usually with the same values:
+            for program in self
+                .external_oes_program
+                .iter()
+                .chain(&self.color_lut_program)
+                .chain([self.main_program()])
+            {
+                if program.uploaded_uniforms.get() == Some(uniforms) {
+                    continue;
+                }
+                let first_upload = program.uploaded_uniforms.get().is_none();
+                self.gl.use_program(Some(program.program));
+                self.gl
+                    .uniform_2_f32_slice(Some(&program.u_screen_size), &uniforms.screen_size);
+                self.gl.uniform_matrix_3_f32_slice(
+                    Some(&program.u_transform),
+                    false,
+                    uniforms.transform.as_flattened(),
+                );
+                if first_upload {
+                    self.gl.uniform_1_i32(Some(&program.u_sampler), 0);
+                    self.gl
+                        .uniform_4_f32_slice(Some(&program.u_tint), &WHITE_TINT);
+                    self.gl.uniform_1_f32(program.u_postprocess.as_ref(), 1.0);
+                }
+                self.gl
+                    .uniform_1_f32(Some(&program.u_dithering), uniforms.dithering);
+                self.gl
+                    .uniform_1_f32(Some(&program.u_gamma), uniforms.gamma);
+                self.gl.uniform_matrix_4_f32_slice(
+                    Some(&program.u_color_matrix),
+                    false,
+                    uniforms.color_matrix.as_flattened(),
+                );
+                self.gl
+                    .uniform_1_f32(Some(&program.u_linear_output), uniforms.linear_output);
+                program.uploaded_uniforms.set(Some(uniforms));
+            }
+            // Callbacks may have bound their own program:
+            self.gl.use_program(Some(self.main_program().program));
+            self.using_external_oes_program = false;
+            self.gl.active_texture(glow::TEXTURE0);
+            self.bound_texture = None;
+
+            self.vao.bind(&self.gl);
+            // The VAO (or the context, when emulating VAOs) keeps this binding while painting:
+            self.gl.bind_buffer(
+                glow::ELEMENT_ARRAY_BUFFER,
+                Some(self.element_array_buffer.buffer),
+            );
+            self.bound_element_array_buffer = Some(self.element_array_buffer.buffer);
+        }
+
+        check_for_gl_error!(&self.gl, "prepare_painting");
+    }
+
+    /// See [`clear`].
+    pub fn clear(&self, screen_size_in_pixels: [u32; 2], clear_color: [f32; 4]) {
+        clear(&self.gl, screen_size_in_pixels, clear_color);
+    }
+
+    /// See [`clear_rgb`].
+    pub fn clear_rgb(&self, screen_size_in_pixels: [u32; 2], rgb: [f32; 3]) {
+        clear_rgb(&self.gl, screen_size_in_pixels, rgb);
+    }
+
+    /// See [`clear_all`].
+    pub fn clear_all(
+        &self,
+        screen_size_in_pixels: [u32; 2],
+        clear_color: [f32; 4],
+        depth: Option<f32>,
+        stencil: Option<i32>,
+    ) {
+        clear_all(&self.gl, screen_size_in_pixels, clear_color, depth, stencil);
+    }
+
+    /// Like [`Self::clear`], but only clears the region egui is painted into, see [`Self::set_viewport`].
+    pub fn clear_viewport(&self, screen_size_in_pixels: [u32; 2], clear_color: [f32; 4]) {
+        profiling::function_scope!();
+        let ViewportInPixels {
+            left_px,
+            from_bottom_px,
+            width_px,
+            height_px,
+            ..
+        } = self.viewport_in_pixels(screen_size_in_pixels);
+        let [r, g, b, a] = clear_color;
+        unsafe {
+            self.gl.enable(glow::SCISSOR_TEST);
+            self.gl
+                .scissor(left_px, from_bottom_px, width_px, height_px);
+            self.gl
+                .viewport(left_px, from_bottom_px, width_px, height_px);
+            self.gl.clear_color(r, g, b, a);
+            self.gl.clear(glow::COLOR_BUFFER_BIT);
+            self.gl.disable(glow::SCISSOR_TEST);
+        }
+    }
+
+    /// You are expected to have cleared the color buffer before calling this.
+    pub fn paint_and_update_textures(
+        &mut self,
+        screen_size_px: [u32; 2],
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+        textures_delta: &egui::TexturesDelta,
+    ) {
+        profiling::function_scope!();
+
+        self.set_textures(&textures_delta.set);
+
+        self.paint_primitives(screen_size_px, pixels_per_point, clipped_primitives);
+        self.stats.texture_uploads = textures_delta.set.len();
+
+        self.free_textures(&textures_delta.free);
+    }
+
+    /// Main entry-point for painting a frame.
+    ///
+    /// You should call `target.clear_color(..)` before
+    /// and `target.finish()` after this.
+    ///
     /// The following OpenGL features will be set:
     /// - Scissor test will be enabled
-    /// - Cull face will be disabled
-    /// - Blend will be enabled
+    /// - Blend will be enabled (unless the [`BlendMode`] is [`BlendMode::Opaque`])
+    ///
+    /// Cull face, depth test and stencil test are disabled while painting, but restored afterwards.
+    ///
+    /// The scissor area and blend parameters will be changed.
+    ///
+    /// As well as this, the following objects will be unset:
+    /// - Vertex Buffer
+    /// - Element Buffer
+    /// - Texture (and active texture will be set to 0)
+    /// - Sampler object of texture unit 0
+    /// - Program
+    ///
+    /// Please be mindful of these effects when integrating into your program, and also be mindful
+    /// of the effects your program might have on this code. Look at the source if in doubt.
+    /// Alternatively, use [`Self::set_preserve_gl_state`] to have the painter restore the state for you.
+    ///
+    /// Missing textures, GL errors and errors returned by callbacks (see [`CallbackFn::new_fallible`])
+    /// are logged. Use [`Self::try_paint_primitives`] to get them instead.
+    ///
+    /// Nothing is painted until the shader has compiled, see [`Self::is_ready`].
+    pub fn paint_primitives(
+        &mut self,
+        screen_size_px: [u32; 2],
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+    ) {
+        self.paint_primitives_impl(screen_size_px, pixels_per_point, clipped_primitives);
+        for err in self.paint_errors.drain(..) {
+            log::error!("{err}");
+        }
+    }
+
+    /// Like [`Self::paint_primitives`], but returns what went wrong instead of logging it,
+    /// and the [`PaintStats`] of the frame if nothing did.
+    ///
+    /// The whole frame is painted either way, skipping only the meshes whose texture is missing.
+    ///
+    /// # Errors
+    /// - If a mesh uses a texture that isn't registered with the painter, with [`MissingTexturePolicy::Error`].
+    ///   The error names the [`egui::TextureId`].
+    /// - If the frame raised a GL error. In debug builds GL errors are also checked (and logged) after each draw call,
+    ///   so only the ones raised later are returned.
+    /// - If any callback made with [`CallbackFn::new_fallible`] or [`CallbackFnMut::new_fallible`] failed.
+    /// - If the egui shader failed to compile in parallel (see [`Self::is_ready`]), in which case nothing is painted.
+    ///   This is returned every time, until [`Self::reload_shaders`] succeeds.
+    ///
+    /// Several errors are combined into one.
+    pub fn try_paint_primitives(
+        &mut self,
+        screen_size_px: [u32; 2],
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+    ) -> Result<PaintStats, PainterError> {
+        self.paint_primitives_impl(screen_size_px, pixels_per_point, clipped_primitives);
+        self.take_paint_errors()
+    }
+
+    /// The errors of the frame painted last, combined into one, or its [`PaintStats`] if there were none.
+    fn take_paint_errors(&mut self) -> Result<PaintStats, PainterError> {
+        let mut errors = std::mem::take(&mut self.paint_errors);
+        match errors.len() {
+            0 => Ok(self.stats),
+            1 => Err(errors.remove(0)),
+            num_errors => Err(PainterError::Other(format!(
+                "{num_errors} errors while painting: {}",
+                errors
+                    .iter()
+                    .map(|err| err.to_string())
+                    .collect::<Vec<_>>()
+                    .join("; ")
+            ))),
+        }
+    }
+
+    /// Like [`Self::paint_primitives`], but only repaints the damaged parts of the screen,
+    /// e.g. to save power with a mostly static UI on a battery-powered device.
+    ///
+    /// Everything is clipped to the union of the `damage_rects`, and primitives entirely outside of it
+    /// (including paint callbacks) are skipped. The rest of the framebuffer is left as it is,
+    /// so clear it with [`Self::clear_damaged`] instead of [`Self::clear`], and make sure it keeps its content
+    /// between frames (e.g. a buffer that isn't swapped, or `EGL_BUFFER_PRESERVED`).
+    ///
+    /// The damage rects are in points, like clip rects, and the host is responsible for them covering everything
+    /// that changed since the last frame. A rect covering the whole screen repaints everything.
+    pub fn paint_primitives_damaged(
+        &mut self,
+        screen_size_px: [u32; 2],
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+        damage_rects: &[Rect],
+    ) {
+        self.damage_rect = Some(union_rect(damage_rects));
+        self.paint_primitives(screen_size_px, pixels_per_point, clipped_primitives);
+        self.damage_rect = None;
+    }
+
+    /// Like [`Self::clear`], but only clears the union of the `damage_rects`, see [`Self::paint_primitives_damaged`].
+    pub fn clear_damaged(
+        &self,
+        screen_size_px: [u32; 2],
+        pixels_per_point: f32,
+        clear_color: [f32; 4],
+        damage_rects: &[Rect],
+    ) {
+        profiling::function_scope!();
+        let viewport_px = self.viewport_in_pixels(screen_size_px);
+        let [x, y, width, height] = scissor_rect(
+            [viewport_px.width_px, viewport_px.height_px].map(|side| side.max(0) as u32),
+            pixels_per_point,
+            transform_rect(&self.transform, union_rect(damage_rects)),
+            self.clip_rounding,
+        );
+        let [r, g, b, a] = clear_color;
+        unsafe {
+            self.gl.enable(glow::SCISSOR_TEST);
+            self.gl.scissor(
+                x + viewport_px.left_px,
+                y + viewport_px.from_bottom_px,
+                width,
+                height,
+            );
+            self.gl.clear_color(r, g, b, a);
+            self.gl.clear(glow::COLOR_BUFFER_BIT);
+            self.gl.disable(glow::SCISSOR_TEST);
+        }
+    }
+
+    fn paint_primitives_impl(
+        &mut self,
+        screen_size_px: [u32; 2],
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+    ) {
+        profiling::function_scope!();
+        if !self.finish_program(false) {
+            // Still compiling (see `is_ready`), or failed to:
+            self.stats = PaintStats::default();
+            return;
+        }
+        self.begin_frame(screen_size_px, pixels_per_point);
+        self.paint_frame_primitives(clipped_primitives);
+        self.end_frame_impl();
+    }
+
+    /// Start painting a frame by hand, for interleaving GL calls of your own with the primitives of egui
+    /// without paint callbacks. [`Self::paint_primitives`] is the same as:
+    ///
+    /// ``` no_run
+    /// # fn paint(painter: &mut egui_glow::Painter, clipped_primitives: &[egui::ClippedPrimitive]) {
+    /// painter.begin_frame([1920, 1080], 2.0);
+    /// painter.paint_frame_primitives(clipped_primitives);
+    /// painter.end_frame().ok();
+    /// # }
+    /// ```
+    ///
+    /// This sets up the OpenGL state described in [`Self::paint_primitives`] (binding the intermediate framebuffer
+    /// when anti-aliasing, supersampling or using a color LUT), and remembers what [`Self::end_frame`] needs to restore.
+    /// Between the two, call [`Self::paint_frame_primitives`] any number of times.
+    /// GL calls of your own in between may change any state, as long as [`Self::restore_frame_state`]
+    /// is called before painting egui again.
+    ///
+    /// A frame that is already begun is ended first, with a warning.
+    ///
+    /// This waits for the shader to finish compiling, see [`Self::is_ready`].
+    /// If it failed to, no frame is begun, and [`Self::end_frame`] returns the error.
+    pub fn begin_frame(&mut self, screen_size_px: [u32; 2], pixels_per_point: f32) {
+        profiling::function_scope!();
+        self.assert_not_destroyed();
+
+        if self.paint_frame.is_some() {
+            log::warn!("Painter::begin_frame was called without ending the previous frame");
+            self.end_frame_impl();
+        }
+        if !self.finish_program(true) {
+            return;
+        }
+
+        let saved_gl_state = self
+            .preserve_gl_state
+            .then(|| unsafe { self.save_gl_state() });
+
+        if let Some(gpu_timer) = &mut self.gpu_timer
+            && let Err(err) = unsafe { gpu_timer.begin(&self.gl) }
+        {
+            self.paint_errors.push(err);
+        }
+
+        let uses_intermediate_framebuffer = self.uses_intermediate_framebuffer();
+        let host_framebuffer = (self.render_target.is_some() || uses_intermediate_framebuffer)
+            .then(|| unsafe { self.gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING) });
+        let destination = self.render_target.or_else(|| host_framebuffer.flatten());
+
+        // egui's screen is the region of the framebuffer set with `set_viewport`:
+        let viewport_px = self.viewport_in_pixels(screen_size_px);
+        let screen_size_px =
+            [viewport_px.width_px, viewport_px.height_px].map(|side| side.max(0) as u32);
+
+        // What egui is actually painted at, which differs from the screen when supersampling:
+        let (paint_size_px, paint_pixels_per_point, intermediate) = if uses_intermediate_framebuffer
+        {
+            let factor = self.supersample_factor.min(
+                self.max_texture_side as f32
+                    / screen_size_px[0].max(screen_size_px[1]).max(1) as f32,
+            );
+            let paint_size_px = screen_size_px.map(|side| (side as f32 * factor).round() as u32);
+            if unsafe { self.begin_intermediate_framebuffer(paint_size_px, screen_size_px) } {
+                (paint_size_px, factor * pixels_per_point, true)
+            } else {
+                (screen_size_px, pixels_per_point, false)
+            }
+        } else {
+            (screen_size_px, pixels_per_point, false)
+        };
+
+        // The intermediate framebuffer or render target that `prepare_painting` binds,
+        // or else whatever the host had bound:
+        let framebuffer = self.intermediate_fbo().or_else(|| {
+            host_framebuffer.unwrap_or_else(|| unsafe {
+                self.gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING)
+            })
+        });
+
+        // All of the intermediate framebuffer, or the region of the destination:
+        let paint_frame = PaintFrame {
+            size_px: paint_size_px,
+            pixels_per_point: paint_pixels_per_point,
+            origin_px: if intermediate {
+                [0, 0, 0]
+            } else {
+                [
+                    viewport_px.left_px,
+                    viewport_px.top_px,
+                    viewport_px.from_bottom_px,
+                ]
+            },
+            framebuffer,
+        };
+        self.paint_frame = Some(paint_frame);
+
+        // egui needs these off, but a 3D scene painted before it is likely to rely on them staying on:
+        let host_capabilities = [glow::CULL_FACE, glow::DEPTH_TEST, glow::STENCIL_TEST]
+            .map(|capability| (capability, unsafe { self.gl.is_enabled(capability) }));
+
+        self.host_state = Some(HostState {
+            saved_gl_state,
+            host_capabilities,
+            host_framebuffer: host_framebuffer.flatten(),
+            rebind_host_framebuffer: host_framebuffer.is_some(),
+            destination,
+            intermediate: intermediate.then_some((viewport_px, pixels_per_point)),
+        });
+
+        unsafe {
+            self.prepare_painting(&paint_frame.viewport_px(), paint_pixels_per_point);
+            self.gl
+                .bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo.buffer));
+            self.vbo.begin_frame(&self.gl);
+            self.element_array_buffer.begin_frame(&self.gl);
+        }
+        self.stats = PaintStats::default();
+    }
+
+    /// Set up the OpenGL state for painting egui again, after GL calls of your own in a frame
+    /// started with [`Self::begin_frame`].
+    ///
+    /// Outside of a frame, this logs a warning and does nothing.
+    pub fn restore_frame_state(&mut self) {
+        let Some(paint_frame) = self.paint_frame else {
+            log::warn!(
+                "Painter::restore_frame_state can only be called between begin_frame and end_frame"
+            );
+            return;
+        };
+        unsafe {
+            self.prepare_painting(&paint_frame.viewport_px(), paint_frame.pixels_per_point);
+            self.gl
+                .bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo.buffer));
+        }
+    }
+
+    /// Paint some of the primitives of a frame started with [`Self::begin_frame`].
+    ///
+    /// Meshes of your own can be painted with the shader of egui like this too,
+    /// as [`egui::ClippedPrimitive`]s (unlike [`Self::paint_user_mesh`], which is for paint callbacks).
+    ///
+    /// Outside of a frame, this logs a warning and does nothing.
+    pub fn paint_frame_primitives(&mut self, clipped_primitives: &[egui::ClippedPrimitive]) {
+        profiling::function_scope!();
+        let Some(paint_frame) = self.paint_frame else {
+            log::warn!(
+                "Painter::paint_frame_primitives can only be called between begin_frame and end_frame"
+            );
+            return;
+        };
+        let paint_viewport_px = paint_frame.viewport_px();
+
+        let mut merged_mesh = std::mem::take(&mut self.merged_mesh);
+        let mut clipped_primitives = clipped_primitives.iter().peekable();
+        while let Some(egui::ClippedPrimitive {
+            clip_rect,
+            primitive,
+        }) = clipped_primitives.next()
+        {
+            let transformed_clip_rect = self.painted_clip_rect(*clip_rect);
+            let [x, y, width, height] = self.scissor_box(&paint_frame, transformed_clip_rect);
+            let is_skippable =
+                matches!(primitive, Primitive::Mesh(_)) || self.damage_rect.is_some();
+            if is_skippable && (width <= 0 || height <= 0) {
+                // Off-screen, collapsed or undamaged, so don't bother uploading it:
+                self.stats.skipped_primitives += 1;
+                continue;
+            }
+            unsafe { self.gl.scissor(x, y, width, height) };
+
+            match primitive {
+                Primitive::Mesh(mesh) => {
+                    // Paint the following meshes with the same clip rect and texture in the same draw call:
+                    let can_merge = |next: &&egui::ClippedPrimitive| match &next.primitive {
+                        Primitive::Mesh(next_mesh) => {
+                            next.clip_rect == *clip_rect && next_mesh.texture_id == mesh.texture_id
+                        }
+                        Primitive::Callback(_) => false,
+                    };
+                    while let Some(egui::ClippedPrimitive {
+                        primitive: Primitive::Mesh(next_mesh),
+                        ..
+                    }) = clipped_primitives.next_if(can_merge)
+                    {
+                        if merged_mesh.is_empty() {
+                            merged_mesh.append_ref(mesh);
+                        }
+                        merged_mesh.append_ref(next_mesh);
+                        self.stats.merged_meshes += 1;
+                    }
+
+                    if merged_mesh.is_empty() {
+                        self.paint_mesh(mesh);
+                    } else {
+                        self.paint_mesh(&merged_mesh);
+                        merged_mesh.clear();
+                    }
+                }
+                Primitive::Callback(callback) => {
+                    if callback.rect.is_positive() {
+                        profiling::scope!("callback");
+
+                        let info = egui::PaintCallbackInfo {
+                            viewport: transform_rect(&self.transform, callback.rect),
+                            clip_rect: transformed_clip_rect,
+                            pixels_per_point: paint_frame.pixels_per_point,
+                            screen_size_px: paint_frame.size_px,
+                        };
+
+                        let target = CallbackTarget {
+                            framebuffer: paint_frame.framebuffer,
+                            viewport_px: offset_viewport(
+                                &info.viewport_in_pixels(),
+                                &paint_viewport_px,
+                            ),
+                            clip_rect_px: offset_viewport(
+                                &info.clip_rect_in_pixels(),
+                                &paint_viewport_px,
+                            ),
+                        };
+                        unsafe {
+                            if self.supports_samplers {
+                                // Don't override how the callback samples its own textures:
+                                self.gl.bind_sampler(0, None);
+                            }
+                            self.bound_texture = None;
+                        }
+                        target.restore(&self.gl);
+
+                        self.callback_commands.replace(Some(Vec::new()));
+                        let callback = &callback.callback;
+                        let result = if let Some(callback) = callback.downcast_ref::<CallbackFn>() {
+                            Some((callback.f)(info, &target, self))
+                        } else if let Some(callback) = callback.downcast_ref::<CallbackFnMut>() {
+                            Some((callback.f.lock())(info, &target, self))
+                        } else if let Some(RegisteredCallback(id)) =
+                            callback.downcast_ref::<RegisteredCallback>()
+                        {
+                            Some(self.paint_callback_renderer(*id, info, &target))
+                        } else {
+                            None
+                        };
+                        if let Some(result) = result {
+                            self.stats.callbacks += 1;
+                            if let Err(err) = result {
+                                self.paint_errors.push(err);
+                            }
+                        } else {
+                            log::warn!(
+                                "Warning: Unsupported render callback. Expected egui_glow::CallbackFn, egui_glow::CallbackFnMut or egui_glow::RegisteredCallback"
+                            );
+                        }
+
+                        self.check_for_paint_error("painting a callback");
+
+                        // Restore state:
+                        unsafe {
+                            self.prepare_painting(&paint_viewport_px, paint_frame.pixels_per_point);
+                        }
+
+                        let commands = self.callback_commands.take();
+                        if let Some(commands) = commands.filter(|commands| !commands.is_empty()) {
+                            unsafe { self.gl.scissor(x, y, width, height) };
+                            for command in commands {
+                                match command {
+                                    CallbackCommand::PaintMesh(mesh, tint, blend_mode) => {
+                                        self.paint_user_mesh_now(&mesh, tint, blend_mode);
+                                    }
+                                    CallbackCommand::PushClipMask(mask) => {
+                                        self.apply_push_clip_mask(mask);
+                                    }
+                                    CallbackCommand::PopClipMask => self.apply_pop_clip_mask(),
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        self.merged_mesh = merged_mesh;
+    }
+
+    /// Finish a frame started with [`Self::begin_frame`]: paint the intermediate framebuffer if any,
+    /// and restore the OpenGL state described in [`Self::paint_primitives`].
+    ///
+    /// # Errors
+    /// Like [`Self::try_paint_primitives`], what went wrong painting the frame.
+    pub fn end_frame(&mut self) -> Result<PaintStats, PainterError> {
+        self.end_frame_impl();
+        self.take_paint_errors()
+    }
+
+    fn end_frame_impl(&mut self) {
+        profiling::function_scope!();
+        let Some(HostState {
+            saved_gl_state,
+            host_capabilities,
+            host_framebuffer,
+            rebind_host_framebuffer,
+            destination,
+            intermediate,
+        }) = self.host_state.take()
+        else {
+            log::warn!("Painter::end_frame was called without begin_frame");
+            return;
+        };
+
+        if !self.clip_masks.is_empty() || self.ignored_clip_masks != 0 {
+            log::warn!(
+                "{} clip masks were pushed with Painter::push_clip_mask but never popped",
+                self.clip_masks.len() + self.ignored_clip_masks
+            );
+            self.clip_masks.clear();
+            self.ignored_clip_masks = 0;
+            unsafe { self.gl.disable(glow::STENCIL_TEST) };
+        }
+
+        if let Some((viewport_px, pixels_per_point)) = intermediate {
+            unsafe {
+                self.end_intermediate_framebuffer(destination, &viewport_px, pixels_per_point);
+            }
+        }
+
+        unsafe {
+            self.vao.unbind(&self.gl);
+            self.gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, None);
+            if self.supports_samplers {
+                self.gl.bind_sampler(0, None);
+            }
+
+            self.gl.disable(glow::SCISSOR_TEST);
+
+            if let Some(gpu_timer) = &mut self.gpu_timer {
+                gpu_timer.end(&self.gl);
+            }
+            self.check_for_paint_error("painting");
+
+            if let Some(saved_gl_state) = saved_gl_state {
+                saved_gl_state.restore(&self.gl);
+            } else {
+                for (capability, enabled) in host_capabilities {
+                    if enabled {
+                        self.gl.enable(capability);
+                    }
+                }
+            }
+            if rebind_host_framebuffer {
+                self.gl
+                    .bind_framebuffer(glow::FRAMEBUFFER, host_framebuffer);
+            }
+        }
+        self.paint_frame = None;
+        if let Some(mesh_cache) = &mut self.mesh_cache {
+            unsafe { mesh_cache.end_frame(&self.gl) };
+        }
+
+        // The replaced textures may have been used by the meshes above, but are no longer needed:
+        self.flush_deleted_textures();
+    }
+
+    /// Make the driver finish preparing the egui shader by drawing with it once,
+    /// to avoid a hitch on the first frame, e.g. while a loading screen is shown.
+    ///
+    /// Some drivers only finish compiling a program when it's first drawn with.
+    /// This draws a triangle with no area, so nothing is painted,
+    /// with a placeholder texture, so it works before any textures exist.
+    /// Only the program for ordinary textures is warmed up, after waiting for it to compile (see [`Self::is_ready`]).
+    ///
+    /// This changes the OpenGL state like [`Self::paint_primitives`] does.
+    /// Between [`Self::begin_frame`] and [`Self::end_frame`], this logs a warning and does nothing.
+    pub fn warm_up(&mut self) {
+        profiling::function_scope!();
+        self.assert_not_destroyed();
+
+        if self.paint_frame.is_some() {
+            log::warn!("Painter::warm_up can't be called between begin_frame and end_frame");
+            return;
+        }
+        if !self.finish_program(true) {
+            for err in self.paint_errors.drain(..) {
+                log::error!("{err}");
+            }
+            return;
+        }
+
+        let saved_gl_state = self
+            .preserve_gl_state
+            .then(|| unsafe { self.save_gl_state() });
+        // Painting binds the intermediate framebuffer, if any:
+        let host_framebuffer = self
+            .intermediate_fbo()
+            .map(|_| unsafe { self.gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING) });
+
+        let texture = match self.fallback_texture {
+            Some(texture) => Ok(texture),
+            None => unsafe { self.create_fallback_texture() },
+        };
+        match texture {
+            Ok(texture) => unsafe {
+                let viewport_px = ViewportInPixels {
+                    left_px: 0,
+                    top_px: 0,
+                    from_bottom_px: 0,
+                    width_px: 1,
+                    height_px: 1,
+                };
+                self.prepare_painting(&viewport_px, 1.0);
+                self.gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+                if self.supports_samplers {
+                    self.gl.bind_sampler(0, None);
+                }
+                self.bound_texture = Some((texture, None));
+
+                let vertex = Vertex {
+                    pos: egui::Pos2::ZERO,
+                    uv: egui::Pos2::ZERO,
+                    color: egui::Color32::TRANSPARENT,
+                };
+                let mesh = Mesh {
+                    indices: vec![0, 1, 2],
+                    vertices: vec![vertex; 3],
+                    texture_id: egui::TextureId::default(),
+                };
+                let (index_type, index_offset) = self.upload_mesh(&mesh);
+                self.gl
+                    .draw_elements(glow::TRIANGLES, 3, index_type, index_offset as i32);
+
+                self.vao.unbind(&self.gl);
+                self.gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, None);
+                self.gl.disable(glow::SCISSOR_TEST);
+                check_for_gl_error!(&self.gl, "warm_up");
+            },
+            Err(err) => log::error!("Failed to warm up the painter: {err}"),
+        }
+
+        unsafe {
+            if let Some(saved_gl_state) = saved_gl_state {
+                saved_gl_state.restore(&self.gl);
+            }
+            if let Some(host_framebuffer) = host_framebuffer {
+                self.gl
+                    .bind_framebuffer(glow::FRAMEBUFFER, host_framebuffer);
+            }
+        }
+    }
+
+    /// Bind and clear the intermediate framebuffer, (re)creating it for the size egui is painted at if needed.
+    ///
+    /// Returns `false` if painting directly into the destination this frame.
+    unsafe fn begin_intermediate_framebuffer(
+        &mut self,
+        paint_size_px: [u32; 2],
+        screen_size_px: [u32; 2],
+    ) -> bool {
+        if paint_size_px.contains(&0) {
+            return false;
+        }
+
+        if self
+            .intermediate_framebuffer
+            .as_ref()
+            .is_none_or(|framebuffer| {
+                framebuffer.size != paint_size_px || framebuffer.samples != self.msaa_samples
+            })
+        {
+            if let Some(framebuffer) = self.intermediate_framebuffer.take() {
+                unsafe { framebuffer.destroy(&self.gl) };
+            }
+            let depth_stencil_format = if self.is_webgl_1 {
+                glow::DEPTH_STENCIL
+            } else {
+                glow::DEPTH24_STENCIL8
+            };
+            // Downsampled when supersampling, and painted pixel for pixel otherwise:
+            let filter = if paint_size_px == screen_size_px {
+                glow::NEAREST
+            } else {
+                glow::LINEAR
+            };
+            match unsafe {
+                IntermediateFramebuffer::new(
+                    &self.gl,
+                    self.msaa_samples,
+                    paint_size_px,
+                    depth_stencil_format,
+                    filter,
+                )
+            } {
+                Ok(framebuffer) => {
+                    let texture_id = *self
+                        .intermediate_texture_id
+                        .get_or_insert_with(|| self.textures.next_native_tex_id());
+                    let texture = PainterTexture::native(
+                        framebuffer.texture,
+                        glow::TEXTURE_2D,
+                        Some(paint_size_px.map(|side| side as usize)),
+                    );
+                    if let Some(old_texture) = self.textures.insert(texture_id, texture) {
+                        unsafe { self.gl.delete_texture(old_texture.texture) };
+                    }
+                    self.intermediate_framebuffer = Some(framebuffer);
+                }
+                Err(err) => {
+                    log::warn!(
+                        "{err}. Painting without anti-aliasing, supersampling or color LUT."
+                    );
+                    self.msaa_samples = 0;
+                    self.supersample_factor = 1.0;
+                    self.color_lut = None;
+                    self.destroy_intermediate_framebuffer();
+                    return false;
+                }
+            }
+        }
+
+        if let Some(framebuffer) = &self.intermediate_framebuffer {
+            unsafe { framebuffer.bind_and_clear(&self.gl) };
+        }
+        true
+    }
+
+    /// Resolve the intermediate framebuffer, and paint it onto `destination`, downsampling it if supersampled.
+    unsafe fn end_intermediate_framebuffer(
+        &mut self,
+        destination: Option<glow::Framebuffer>,
+        viewport_px: &ViewportInPixels,
+        pixels_per_point: f32,
+    ) {
+        let (Some(framebuffer), Some(texture_id)) =
+            (&self.intermediate_framebuffer, self.intermediate_texture_id)
+        else {
+            return;
+        };
+
+        let ViewportInPixels {
+            left_px,
+            from_bottom_px,
+            width_px,
+            height_px,
+            ..
+        } = *viewport_px;
+        unsafe {
+            framebuffer.resolve(&self.gl);
+            // Back to the viewport of the screen:
+            self.prepare_painting(viewport_px, pixels_per_point);
+            let program = match (self.color_lut, &self.color_lut_program) {
+                (Some(color_lut), Some(program)) => {
+                    self.gl.active_texture(glow::TEXTURE1);
+                    self.gl.bind_texture(glow::TEXTURE_3D, Some(color_lut));
+                    if self.supports_samplers {
+                        self.gl.bind_sampler(1, None);
+                    }
+                    self.gl.active_texture(glow::TEXTURE0);
+                    self.gl.use_program(Some(program.program));
+                    self.gl.uniform_1_i32(program.u_color_lut.as_ref(), 1);
+                    program
+                }
+                _ => self.main_program(),
+            };
+            // Already applied when painting into the intermediate framebuffer:
+            program.uploaded_uniforms.set(None);
+            self.gl.uniform_1_f32(Some(&program.u_dithering), 0.0);
+            self.gl.uniform_1_f32(Some(&program.u_gamma), 1.0);
+            self.gl.uniform_1_f32(program.u_postprocess.as_ref(), 0.0);
+            self.gl.uniform_matrix_4_f32_slice(
+                Some(&program.u_color_matrix),
+                false,
+                IDENTITY_COLOR_MATRIX.as_flattened(),
+            );
+            self.gl.uniform_matrix_3_f32_slice(
+                Some(&program.u_transform),
+                false,
+                transform_columns(&IDENTITY_TRANSFORM).as_flattened(),
+            );
+            self.gl.uniform_1_f32(
+                Some(&program.u_linear_output),
+                self.srgb_framebuffer as i32 as f32,
+            );
+            self.gl.bind_framebuffer(glow::FRAMEBUFFER, destination);
+            self.gl
+                .scissor(left_px, from_bottom_px, width_px, height_px);
+        }
+
+        // Painted with the blend mode egui was painted with, onto what was already there:
+        let mut mesh = Mesh::with_texture(texture_id);
+        mesh.add_rect_with_uv(
+            Rect::from_min_size(
+                egui::Pos2::ZERO,
+                egui::vec2(width_px as f32, height_px as f32) / pixels_per_point,
+            ),
+            // The framebuffer is upside down:
+            Rect::from_min_max(egui::pos2(0.0, 1.0), egui::pos2(1.0, 0.0)),
+            egui::Color32::WHITE,
+        );
+        self.paint_mesh(&mesh);
+
+        if self.color_lut.is_some() {
+            unsafe {
+                self.gl.active_texture(glow::TEXTURE1);
+                self.gl.bind_texture(glow::TEXTURE_3D, None);
+                self.gl.active_texture(glow::TEXTURE0);
+            }
+        }
+    }
+
+    /// Delete the intermediate framebuffer and its texture, when no longer needed.
+    fn destroy_intermediate_framebuffer(&mut self) {
+        if let Some(framebuffer) = self.intermediate_framebuffer.take() {
+            unsafe { framebuffer.destroy(&self.gl) };
+        }
+        if let Some(texture_id) = self.intermediate_texture_id.take() {
+            self.free_texture(texture_id);
+        }
+    }
+
+    unsafe fn save_gl_state(&self) -> GlState {
+        let mut capabilities = vec![
+            glow::SCISSOR_TEST,
+            glow::CULL_FACE,
+            glow::DEPTH_TEST,
+            glow::STENCIL_TEST,
+            glow::BLEND,
+        ];
+        if self.supports_srgb_framebuffer {
+            capabilities.push(glow::FRAMEBUFFER_SRGB);
+        }
+        let supports_vertex_array_query =
+            !self.is_webgl_1 && is_gl_version_at_least(&self.gl, [3, 0], [3, 0]);
+
+        unsafe {
+            GlState::save(
+                &self.gl,
+                &capabilities,
+                self.supports_samplers,
+                supports_vertex_array_query,
+            )
+        }
+    }
+
+    /// Upload a mesh to the streaming buffers,
+    /// and return the type of its indices and their offset in the element array buffer.
+    unsafe fn upload_mesh(&mut self, mesh: &Mesh) -> (u32, usize) {
+        // All indices fit in a u16 if there are at most 2^16 vertices, halving the upload:
+        let use_u16_indices = MIN_INDICES_FOR_U16 <= mesh.indices.len()
+            && mesh.vertices.len() <= u16::MAX as usize + 1;
+
+        unsafe {
+            self.gl
+                .bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo.buffer));
+            let vertex_offset = self
+                .vbo
+                .upload(&self.gl, bytemuck::cast_slice(&mesh.vertices));
+            // Cached meshes point the attributes at their own buffers:
+            if self.vbo.is_streaming() || self.mesh_cache.is_some() {
+                self.vao
+                    .set_vertex_buffer(&self.gl, self.vbo.buffer, vertex_offset as i32);
+            }
+
+            let indices = if use_u16_indices {
+                self.indices_u16.clear();
+                self.indices_u16
+                    .extend(mesh.indices.iter().map(|&index| index as u16));
+                bytemuck::cast_slice(&self.indices_u16)
+            } else {
+                bytemuck::cast_slice(&mesh.indices)
+            };
+            if self.bound_element_array_buffer != Some(self.element_array_buffer.buffer) {
+                self.gl.bind_buffer(
+                    glow::ELEMENT_ARRAY_BUFFER,
+                    Some(self.element_array_buffer.buffer),
+                );
+                self.bound_element_array_buffer = Some(self.element_array_buffer.buffer);
+            }
+            let index_offset = self.element_array_buffer.upload(&self.gl, indices);
+
+            if use_u16_indices {
+                (glow::UNSIGNED_SHORT, index_offset)
+            } else {
+                (glow::UNSIGNED_INT, index_offset)
+            }
+        }
+    }
+
+    /// Like [`Self::paint_primitives`], but also returns statistics about what was painted.
+    pub fn paint_primitives_with_stats(
+        &mut self,
+        screen_size_px: [u32; 2],
+        pixels_per_point: f32,
+        clipped_primitives: &[egui::ClippedPrimitive],
+    ) -> PaintStats {
+        self.paint_primitives(screen_size_px, pixels_per_point, clipped_primitives);
+        self.stats
+    }
+
+    fn paint_mesh(&mut self, mesh: &Mesh) {
+        self.paint_mesh_tinted(mesh, egui::Color32::WHITE);
+    }
+
+    /// Paint `mesh` with its colors multiplied by `tint`.
+    #[inline(never)] // Easier profiling
+    fn paint_mesh_tinted(&mut self, mesh: &Mesh, tint: egui::Color32) {
+        debug_assert!(mesh.is_valid(), "Mesh is not valid");
+        let texture = self
+            .textures
+            .get(&mesh.texture_id)
+            .or_else(|| self.missing_texture(mesh.texture_id));
+        if let Some(texture) = texture {
+            let sampler = if self.supports_samplers {
+                // Native textures are sampled with their own parameters:
+                texture.options.and_then(|options| {
+                    let sampler_options = self
+                        .sampler_options
+                        .get(&mesh.texture_id)
+                        .copied()
+                        .unwrap_or_default();
+                    self.sampler(options, sampler_options)
+                })
+            } else {
+                None
+            };
+
+            let cached = self.mesh_cache.as_mut().and_then(|mesh_cache| unsafe {
+                mesh_cache
+                    .get_or_upload(&self.gl, mesh, &mut self.bound_element_array_buffer)
+                    .map(|cached| (cached.vbo, cached.element_array_buffer))
+            });
+
+            let (index_type, index_offset) = unsafe {
+                let (index_type, index_offset) = if let Some((vbo, element_array_buffer)) = cached {
+                    self.vao.set_vertex_buffer(&self.gl, vbo, 0);
+                    self.gl
+                        .bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(element_array_buffer));
+                    self.bound_element_array_buffer = Some(element_array_buffer);
+                    self.stats.cached_meshes += 1;
+                    (glow::UNSIGNED_INT, 0)
+                } else {
+                    self.upload_mesh(mesh)
+                };
+
+                let is_external_oes = texture.target == TEXTURE_EXTERNAL_OES;
+                if is_external_oes != self.using_external_oes_program {
+                    let program = if is_external_oes {
+                        self.external_oes_program.as_ref()
+                    } else {
+                        Some(self.main_program())
+                    };
+                    self.gl.use_program(program.map(|program| program.program));
+                    self.using_external_oes_program = is_external_oes;
+                }
+                if tint != egui::Color32::WHITE {
+                    self.set_tint(tint.to_normalized_gamma_f32());
+                }
+
+                // Runs of text share the font atlas, so this is often already bound:
+                if self.bound_texture != Some((texture.texture, sampler)) {
+                    self.gl.bind_texture(texture.target, Some(texture.texture));
+                    if self.supports_samplers {
+                        self.gl.bind_sampler(0, sampler);
+                        if sampler.is_none() && texture.options.is_some() {
+                            // Failed to create the sampler object, so sample like without them:
+                            self.set_sampling_params(
+                                ParameterTarget::BoundTexture,
+                                texture.options,
+                                self.sampler_options.get(&mesh.texture_id),
+                            );
+                        }
+                    }
+                    self.bound_texture = Some((texture.texture, sampler));
+                }
+
+                (index_type, index_offset)
+            };
+
+            let index_size = if index_type == glow::UNSIGNED_SHORT {
+                std::mem::size_of::<u16>()
+            } else {
+                std::mem::size_of::<u32>()
+            };
+            for (first, count) in draw_ranges(mesh.indices.len(), self.max_indices_per_draw) {
+                unsafe {
+                    self.gl.draw_elements(
+                        glow::TRIANGLES,
+                        count as i32,
+                        index_type,
+                        (index_offset + first * index_size) as i32,
+                    );
+                }
+                self.stats.draw_calls += 1;
+            }
+            if tint != egui::Color32::WHITE {
+                // Every other mesh is painted without a tint:
+                unsafe { self.set_tint(WHITE_TINT) };
+            }
+            self.stats.vertices += mesh.vertices.len();
+            self.stats.indices += mesh.indices.len();
+
+            check_for_gl_error!(&self.gl, "paint_mesh");
+        }
+    }
+
+    /// Set the tint of the program [`Self::paint_mesh_tinted`] paints with.
+    unsafe fn set_tint(&self, tint: [f32; 4]) {
+        let program = if self.using_external_oes_program {
+            self.external_oes_program.as_ref()
+        } else {
+            Some(self.main_program())
+        };
+        if let Some(program) = program {
+            unsafe { self.gl.uniform_4_f32_slice(Some(&program.u_tint), &tint) };
+        }
+    }
+
+    /// What to paint a mesh with instead of the missing texture `tex_id`, if anything.
+    /// See [`MissingTexturePolicy`].
+    fn missing_texture(&mut self, tex_id: egui::TextureId) -> Option<PainterTexture> {
+        match self.missing_texture_policy {
+            MissingTexturePolicy::Skip => {
+                log::warn!("Failed to find texture {tex_id:?}");
+                None
+            }
+            MissingTexturePolicy::Error => {
+                self.paint_errors.push(PainterError::Other(format!(
+                    "Failed to find texture {tex_id:?}"
+                )));
+                None
+            }
+            MissingTexturePolicy::Fallback => {
+                let texture = match self.fallback_texture {
+                    Some(texture) => texture,
+                    None => match unsafe { self.create_fallback_texture() } {
+                        Ok(texture) => texture,
+                        Err(err) => {
+                            self.paint_errors.push(err);
+                            return None;
+                        }
+                    },
+                };
+                Some(PainterTexture {
+                    texture,
+                    target: glow::TEXTURE_2D,
+                    storage: None,
+                    options: Some(egui::TextureOptions::NEAREST),
+                    is_native: false,
+                    size: Some([2, 2]),
+                    format: TextureFormat::Rgba8,
+                })
+            }
+        }
+    }
+
+    /// Create the 2x2 magenta checkerboard of [`MissingTexturePolicy::Fallback`].
+    unsafe fn create_fallback_texture(&mut self) -> Result<glow::Texture, PainterError> {
+        let magenta = [255, 0, 255, 255];
+        let black = [0, 0, 0, 255];
+        let texels = [magenta, black, black, magenta].concat();
+
+        unsafe {
+            let texture = self.gl.create_texture()?;
+            self.gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+            self.bound_texture = None;
+            self.gl.tex_image_2d(
+                glow::TEXTURE_2D,
+                0,
+                glow::RGBA as i32,
+                2,
+                2,
+                0,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelUnpackData::Slice(Some(&texels)),
+            );
+            self.set_sampling_params(
+                ParameterTarget::BoundTexture,
+                Some(egui::TextureOptions::NEAREST),
+                None,
+            );
+            check_for_gl_error!(&self.gl, "create_fallback_texture");
+            self.fallback_texture = Some(texture);
+            Ok(texture)
+        }
+    }
+
+    /// Record the GL error raised since the last check, if any, as an error of the frame being painted.
+    fn check_for_paint_error(&mut self, context: &str) {
+        let error_code = unsafe { self.gl.get_error() };
+        if error_code != glow::NO_ERROR {
+            self.paint_errors.push(PainterError::Other(format!(
+                "GL error while {context}: {} (0x{error_code:X})",
+                crate::gl_error_name(error_code)
+            )));
+        }
+    }
+
+    /// Paint a mesh with the same shader, blending and textures as the rest of egui,
+    /// from inside a paint callback, e.g. for a 2D overlay on top of a 3D scene.
+    ///
+    /// The mesh is in points, like the rest of egui, and is clipped to the clip rect of the callback.
+    /// Since the callback may change any OpenGL state, the mesh is painted right after the callback
+    /// returns, once the state egui paints with is restored, and on top of whatever the callback painted.
+    ///
+    /// Outside of a callback, this logs a warning and does nothing.
+    pub fn paint_user_mesh(&self, mesh: Mesh) {
+        self.queue_callback_command(
+            CallbackCommand::PaintMesh(mesh, egui::Color32::WHITE, self.blend_mode),
+            "paint_user_mesh",
+        );
+    }
+
+    /// Like [`Self::paint_user_mesh`], but with the colors of the mesh multiplied by `tint` on the GPU,
+    /// e.g. to highlight or fade a mesh without recoloring its vertices.
+    ///
+    /// Like the colors of egui, the tint is premultiplied, so a translucent tint makes the mesh translucent.
+    /// [`egui::Color32::WHITE`] leaves the mesh as it is.
+    pub fn paint_user_mesh_tinted(&self, mesh: Mesh, tint: egui::Color32) {
+        self.queue_callback_command(
+            CallbackCommand::PaintMesh(mesh, tint, self.blend_mode),
+            "paint_user_mesh_tinted",
+        );
+    }
+
+    /// Like [`Self::paint_user_mesh`], but blended with `blend_mode` instead of [`Self::blend_mode`],
+    /// e.g. [`BlendMode::Additive`] for a glow or [`BlendMode::Multiply`] for a shadow.
+    pub fn paint_user_mesh_with_blend_mode(&self, mesh: Mesh, blend_mode: BlendMode) {
+        self.queue_callback_command(
+            CallbackCommand::PaintMesh(mesh, egui::Color32::WHITE, blend_mode),
+            "paint_user_mesh_with_blend_mode",
+        );
+    }
+
+    /// Paint a mesh queued by a paint callback.
+    fn paint_user_mesh_now(&mut self, mesh: &Mesh, tint: egui::Color32, blend_mode: BlendMode) {
+        if blend_mode == self.blend_mode {
+            self.paint_mesh_tinted(mesh, tint);
+        } else {
+            unsafe { self.apply_blend_mode(blend_mode) };
+            self.paint_mesh_tinted(mesh, tint);
+            unsafe { self.apply_blend_mode(self.blend_mode) };
+        }
+    }
+
+    /// Clip everything painted after this callback to the triangles of `mask`, from inside a paint callback,
+    /// e.g. to clip a window to its rounded corners, or to any other shape.
+    ///
+    /// The mask is in points, like the rest of egui. Every pixel its triangles cover is inside it, whatever their color,
+    /// and it is not clipped to the clip rect of the callback.
+    /// Masks nest: only what is inside all the masks pushed (and not yet popped) is painted.
+    /// Pop the mask with [`Self::pop_clip_mask`] from a later callback of the same frame.
+    /// The masks left at the end of the frame are popped with a warning.
+    ///
+    /// The masks are kept in the stencil buffer, so this needs a framebuffer with one,
+    /// and callbacks shouldn't change the stencil buffer while masks are pushed.
+    /// Without a stencil buffer, this logs a warning and clips nothing.
+    ///
+    /// Like [`Self::paint_user_mesh`], this happens once the callback returns, and outside of a callback
+    /// this logs a warning and does nothing.
+    pub fn push_clip_mask(&self, mask: Mesh) {
+        self.queue_callback_command(CallbackCommand::PushClipMask(mask), "push_clip_mask");
+    }
+
+    /// Stop clipping to the mask pushed last with [`Self::push_clip_mask`], from inside a paint callback.
+    pub fn pop_clip_mask(&self) {
+        self.queue_callback_command(CallbackCommand::PopClipMask, "pop_clip_mask");
+    }
+
+    /// Set the scissor box to `clip_rect` the same way egui clips its own meshes, from inside a paint callback,
+    /// e.g. for a custom renderer that paints several sub-regions.
+    ///
+    /// `clip_rect` is in points, like [`PaintCallbackInfo::clip_rect`] (which it often lies within).
+    /// It is converted to pixels with the `pixels_per_point` and screen size of the frame being painted,
+    /// and goes through [`Self::set_transform`], [`Self::set_viewport`] and [`Self::set_clip_rounding`] like egui's clip rects.
+    ///
+    /// This only sets the box: enable `SCISSOR_TEST` if needed.
+    /// Outside of painting, this logs a warning and does nothing.
+    pub fn set_scissor(&self, clip_rect: Rect) {
+        let Some(paint_frame) = self.paint_frame else {
+            log::warn!(
+                "Painter::set_scissor can only be called while painting, e.g. from a paint callback"
+            );
+            return;
+        };
+        let [x, y, width, height] =
+            self.scissor_box(&paint_frame, self.painted_clip_rect(clip_rect));
+        unsafe { self.gl.scissor(x, y, width, height) };
+    }
+
+    /// Where the meshes clipped to `clip_rect` are painted, in points:
+    /// clipped to the damage, see [`Self::paint_primitives_damaged`], and transformed, see [`Self::set_transform`].
+    fn painted_clip_rect(&self, clip_rect: Rect) -> Rect {
+        let clip_rect = self
+            .damage_rect
+            .map_or(clip_rect, |damage_rect| clip_rect.intersect(damage_rect));
+        transform_rect(&self.transform, clip_rect)
+    }
+
+    /// The scissor box of a clip rect from [`Self::painted_clip_rect`], as `[x, y, width, height]` in the framebuffer.
+    fn scissor_box(&self, paint_frame: &PaintFrame, painted_clip_rect: Rect) -> [i32; 4] {
+        let [x, y, width, height] = scissor_rect(
+            paint_frame.size_px,
+            paint_frame.pixels_per_point,
+            painted_clip_rect,
+            self.clip_rounding,
+        );
+        let [left_px, _, from_bottom_px] = paint_frame.origin_px;
+        [x + left_px, y + from_bottom_px, width, height]
+    }
+
+    fn queue_callback_command(&self, command: CallbackCommand, method: &str) {
+        if let Some(commands) = self.callback_commands.borrow_mut().as_mut() {
+            commands.push(command);
+        } else {
+            log::warn!("Painter::{method} can only be called from inside a paint callback");
+        }
+    }
+
+    /// Add `mask` to the stencil buffer, see [`Self::push_clip_mask`].
+    fn apply_push_clip_mask(&mut self, mask: Mesh) {
+        if !self.has_buffer(glow::STENCIL) {
+            if self.ignored_clip_masks == 0 {
+                log::warn!(
+                    "Ignoring Painter::push_clip_mask: the framebuffer has no stencil buffer"
+                );
+            }
+            self.ignored_clip_masks += 1;
+            return;
+        }
+
+        let depth = self.clip_masks.len() as i32;
+        unsafe {
+            self.gl.disable(glow::SCISSOR_TEST);
+            self.gl.enable(glow::STENCIL_TEST);
+            self.gl.stencil_mask(0xFF);
+            if depth == 0 {
+                self.gl.clear_stencil(0);
+                self.gl.clear(glow::STENCIL_BUFFER_BIT);
+            }
+            // Inside all the masks below it, the stencil goes up by one where this mask covers it:
+            self.gl.stencil_func(glow::EQUAL, depth, 0xFF);
+            self.gl.stencil_op(glow::KEEP, glow::KEEP, glow::INCR);
+            self.gl.color_mask(false, false, false, false);
+        }
+        self.paint_mesh(&mask);
+        self.clip_masks.push(mask);
+        unsafe {
+            self.gl.color_mask(true, true, true, true);
+            self.gl.enable(glow::SCISSOR_TEST);
+            self.set_clip_mask_stencil();
+        }
+        check_for_gl_error!(&self.gl, "push_clip_mask");
+    }
+
+    /// Take the top mask back out of the stencil buffer, see [`Self::pop_clip_mask`].
+    fn apply_pop_clip_mask(&mut self) {
+        if self.ignored_clip_masks != 0 {
+            self.ignored_clip_masks -= 1;
+            return;
+        }
+        let Some(mask) = self.clip_masks.pop() else {
+            log::warn!("Painter::pop_clip_mask was called without a clip mask to pop");
+            return;
+        };
+
+        let depth = self.clip_masks.len() as i32 + 1;
+        unsafe {
+            self.gl.disable(glow::SCISSOR_TEST);
+            self.gl.stencil_func(glow::EQUAL, depth, 0xFF);
+            self.gl.stencil_op(glow::KEEP, glow::KEEP, glow::DECR);
+            self.gl.color_mask(false, false, false, false);
+        }
+        self.paint_mesh(&mask);
+        unsafe {
+            self.gl.color_mask(true, true, true, true);
+            self.gl.enable(glow::SCISSOR_TEST);
+            self.set_clip_mask_stencil();
+        }
+        check_for_gl_error!(&self.gl, "pop_clip_mask");
+    }
+
+    /// Only paint inside all the pushed clip masks, if any.
+    unsafe fn set_clip_mask_stencil(&self) {
+        unsafe {
+            if self.clip_masks.is_empty() {
+                self.gl.disable(glow::STENCIL_TEST);
+            } else {
+                self.gl.enable(glow::STENCIL_TEST);
+                self.gl
+                    .stencil_func(glow::EQUAL, self.clip_masks.len() as i32, 0xFF);
+                self.gl.stencil_op(glow::KEEP, glow::KEEP, glow::KEEP);
+            }
+        }
+    }
+
+    /// Does the bound framebuffer have a `glow::DEPTH` or `glow::STENCIL` buffer?
+    fn has_buffer(&self, buffer: u32) -> bool {
+        let (bits, attachment, size) = if buffer == glow::DEPTH {
+            (
+                glow::DEPTH_BITS,
+                glow::DEPTH_ATTACHMENT,
+                glow::FRAMEBUFFER_ATTACHMENT_DEPTH_SIZE,
+            )
+        } else {
+            (
+                glow::STENCIL_BITS,
+                glow::STENCIL_ATTACHMENT,
+                glow::FRAMEBUFFER_ATTACHMENT_STENCIL_SIZE,
+            )
+        };
+        let gl = &self.gl;
+        let has_buffer = unsafe {
+            if gl.version().is_embedded || !is_gl_version_at_least(gl, [3, 0], [3, 0]) {
+                0 < gl.get_parameter_i32(bits)
+            } else {
+                // `STENCIL_BITS` and `DEPTH_BITS` are gone from core profiles, so ask the attachment:
+                let attachment = if gl
+                    .get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING)
+                    .is_some()
+                {
+                    attachment
+                } else {
+                    buffer
+                };
+                let parameter = |parameter| {
+                    gl.get_framebuffer_attachment_parameter_i32(
+                        glow::FRAMEBUFFER,
+                        attachment,
+                        parameter,
+                    )
+                };
+                parameter(glow::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE) != glow::NONE as i32
+                    && 0 < parameter(size)
+            }
+        };
+        check_for_gl_error!(&self.gl, "has_buffer");
+        has_buffer
+    }
+
+    /// Register a renderer to be painted by [`RegisteredCallback`]s with this `id`,
+    /// replacing (and destroying) any renderer already registered with it.
+    pub fn register_callback_renderer(
+        &mut self,
+        id: egui::Id,
+        renderer: impl CallbackRenderer + 'static,
+    ) {
+        if let Some(mut old_renderer) = self.callback_renderers.insert(id, Box::new(renderer)) {
+            old_renderer.destroy(&self.gl);
+        }
+    }
+
+    /// Unregister and destroy the renderer registered with this `id`.
+    ///
+    /// Returns `false` if there was none.
+    pub fn unregister_callback_renderer(&mut self, id: egui::Id) -> bool {
+        if let Some(mut renderer) = self.callback_renderers.remove(&id) {
+            renderer.destroy(&self.gl);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn paint_callback_renderer(
+        &mut self,
+        id: egui::Id,
+        info: PaintCallbackInfo,
+        target: &CallbackTarget,
+    ) -> Result<(), PainterError> {
+        // Taken out while painting, so it can be given the painter:
+        let Some(mut renderer) = self.callback_renderers.remove(&id) else {
+            return Err(PainterError::Other(format!(
+                "No callback renderer is registered with {id:?}"
+            )));
+        };
+        let result = renderer.paint(info, target, self);
+        self.callback_renderers.insert(id, renderer);
+        result
+    }
+
+    /// Have [`Self::paint_primitives`] save the OpenGL state it changes, and restore it afterwards,
+    /// e.g. when painting egui on top of your own renderer. Off by default.
+    ///
+    /// This restores the enabled capabilities (blending, scissor test, face culling and depth test),
+    /// the blend equations and functions, the color mask, the viewport and scissor box,
+    /// the program, the active texture, the texture and sampler bound to texture unit 0,
+    /// the vertex buffer, and the vertex array object (where it can be queried).
+    ///
+    /// Querying all that stalls the pipeline, so only turn this on if you need it.
+    pub fn set_preserve_gl_state(&mut self, preserve_gl_state: bool) {
+        self.preserve_gl_state = preserve_gl_state;
+    }
+
+    /// Name the programs, buffers and textures of the painter with `KHR_debug` labels,
+    /// e.g. "egui texture Managed(0)", so they can be told apart in `RenderDoc` or `apitrace`.
+    ///
+    /// Objects that already exist are labeled right away, and new ones as they are created.
+    /// Turning this off again keeps the labels already set.
+    ///
+    /// Ignored (with a warning) on contexts without `KHR_debug`, which includes WebGL.
+    pub fn set_debug_labels(&mut self, debug_labels: bool) {
+        if debug_labels && !self.gl.supports_debug() {
+            log::warn!("This context doesn't support KHR_debug. Ignoring set_debug_labels.");
+            return;
+        }
+        self.debug_labels = debug_labels;
+
+        self.debug_label(
+            DebugObject::Program(self.program.program()),
+            format_args!("egui program"),
+        );
+        if let Some(program) = &self.external_oes_program {
+            self.debug_label(
+                DebugObject::Program(program.program),
+                format_args!("egui external OES program"),
+            );
+        }
+        if let Some(program) = &self.color_lut_program {
+            self.debug_label(
+                DebugObject::Program(program.program),
+                format_args!("egui color LUT program"),
+            );
+        }
+        self.debug_label(
+            DebugObject::Buffer(self.vbo.buffer),
+            format_args!("egui vertex buffer"),
+        );
+        self.debug_label(
+            DebugObject::Buffer(self.element_array_buffer.buffer),
+            format_args!("egui index buffer"),
+        );
+        if let Some(vertex_array) = self.vao.vertex_array() {
+            self.debug_label(
+                DebugObject::VertexArray(vertex_array),
+                format_args!("egui vertex array"),
+            );
+        }
+        for (tex_id, texture) in self.textures.all() {
+            // Native textures belong to the app, which may have named them already:
+            if !texture.is_native {
+                self.debug_label(
+                    DebugObject::Texture(texture.texture),
+                    format_args!("egui texture {tex_id:?}"),
+                );
+            }
+        }
+    }
+
+    pub fn debug_labels(&self) -> bool {
+        self.debug_labels
+    }
+
+    /// Log the `KHR_debug` messages of the driver that are at least `min_severity`,
+    /// see [`crate::enable_debug_output`].
+    ///
+    /// This needs the only reference to the glow context, so it does nothing but log a warning
+    /// if the context is shared (e.g. with the app). In that case, call [`crate::enable_debug_output`]
+    /// before creating the painter instead.
+    pub fn enable_debug_output(&mut self, min_severity: crate::DebugSeverity) {
+        if let Some(gl) = Arc::get_mut(&mut self.gl) {
+            crate::enable_debug_output(gl, min_severity);
+        } else {
+            log::warn!(
+                "The glow context is shared, so debug output can't be enabled by the painter. Call egui_glow::enable_debug_output before sharing it."
+            );
+        }
+    }
+
+    /// Label the object if [`Self::set_debug_labels`] is on.
+    fn debug_label(&self, object: DebugObject, label: std::fmt::Arguments<'_>) {
+        if self.debug_labels {
+            unsafe { set_debug_label(&self.gl, object, &label.to_string()) };
+        }
+    }
+
+    /// Enable and set up blending for `blend_mode`, or disable it.
+    unsafe fn apply_blend_mode(&self, blend_mode: BlendMode) {
+        unsafe {
+            if let Some(
+                [
+                    equation_rgb,
+                    equation_alpha,
+                    src_rgb,
+                    dst_rgb,
+                    src_alpha,
+                    dst_alpha,
+                ],
+            ) = blend_mode.gl_blend_state()
+            {
+                self.gl.enable(glow::BLEND);
+                self.gl
+                    .blend_equation_separate(equation_rgb, equation_alpha);
+                self.gl
+                    .blend_func_separate(src_rgb, dst_rgb, src_alpha, dst_alpha);
+            } else {
+                self.gl.disable(glow::BLEND);
+            }
+        }
+    }
+
+    /// Set how egui is blended with what's already in the framebuffer.
+    ///
+    /// This is restored after each [`egui::PaintCallback`], like the rest of the state egui paints with.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Dither the colors egui paints down to eight bits, to reduce banding in gradients.
+    ///
+    /// Starts out as the `dithering` passed to [`Self::new`], and takes effect from the next frame.
+    pub fn set_dithering(&mut self, dithering: bool) {
+        self.dithering = dithering;
+    }
+
+    pub fn dithering(&self) -> bool {
+        self.dithering
+    }
+
+    /// Brighten (above `1.0`) or darken (below `1.0`) everything egui paints,
+    /// by raising its colors to the power of `1.0 / gamma`.
+    ///
+    /// This is a workaround for displays where egui looks washed out or too dark
+    /// (see <https://github.com/emilk/egui/issues/794>), which can be exposed as a slider.
+    /// `1.0` (the default) leaves the colors alone. Takes effect from the next frame.
+    pub fn set_gamma(&mut self, gamma: f32) {
+        if !(gamma.is_finite() && 0.0 < gamma) {
+            log::warn!("Ignoring invalid gamma {gamma}");
+            return;
+        }
+        self.gamma = gamma;
+    }
+
+    pub fn gamma(&self) -> f32 {
+        self.gamma
+    }
+
+    /// Transform the colors of everything egui paints with a 4x4 matrix,
+    /// e.g. for a global sepia or high-contrast filter, or to fade to black.
+    ///
+    /// Each row gives one channel of the output `[r, g, b, a]` as a weighted sum of the input channels.
+    /// The matrix is applied to colors without premultiplied alpha (in gamma space), before blending,
+    /// so edges don't darken.
+    /// [`IDENTITY_COLOR_MATRIX`] (the default) leaves the colors alone. Takes effect from the next frame.
+    pub fn set_color_matrix(&mut self, color_matrix: [[f32; 4]; 4]) {
+        self.color_matrix = color_matrix;
+    }
+
+    pub fn color_matrix(&self) -> [[f32; 4]; 4] {
+        self.color_matrix
+    }
+
+    /// Transform everything egui paints with a 2D affine matrix, in points,
+    /// e.g. to zoom and pan the whole UI on a canvas without laying it out again.
+    ///
+    /// The two rows give the transformed `x` and `y` of a point `[x, y, 1]`.
+    /// The vertices are transformed on the GPU, and clip rectangles (and the viewports of paint callbacks)
+    /// are replaced by the bounding box of their transformed corners.
+    /// So with rotation or skew, meshes aren't clipped exactly at the edges of their clip rectangles.
+    ///
+    /// Note that egui doesn't know about the transform, so the app has to transform pointer input the opposite way.
+    /// [`IDENTITY_TRANSFORM`] (the default) leaves everything where it is. Takes effect from the next frame.
+    pub fn set_transform(&mut self, transform: [[f32; 3]; 2]) {
+        self.transform = transform;
+    }
+
+    pub fn transform(&self) -> [[f32; 3]; 2] {
+        self.transform
+    }
+
+    /// Keep meshes that are painted frame after frame on the GPU, up to `budget` bytes,
+    /// so they don't need to be uploaded every frame. `None` (the default) disables the cache.
+    ///
+    /// This is a win for mostly static UIs, such as dashboards,
+    /// at the cost of hashing every mesh, every frame.
+    /// Meshes are cached once they are painted in two frames in a row,
+    /// and the least recently painted ones are evicted when the cache grows past the budget.
+    pub fn set_mesh_cache_budget(&mut self, budget: Option<usize>) {
+        match (budget, &mut self.mesh_cache) {
+            (Some(budget), Some(mesh_cache)) => mesh_cache.set_budget(budget),
+            (Some(budget), None) => self.mesh_cache = Some(MeshCache::new(budget)),
+            (None, _) => {
+                if let Some(mesh_cache) = self.mesh_cache.take() {
+                    unsafe {
+                        mesh_cache.destroy(&self.gl);
+                        // Without streaming, the attributes are no longer pointed at the VBO before each mesh:
+                        self.vao.bind(&self.gl);
+                        self.vao.set_vertex_buffer(&self.gl, self.vbo.buffer, 0);
+                        self.vao.unbind(&self.gl);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Allocate the vertex and index buffers for this many vertices and indices per frame up front,
+    /// e.g. to avoid hitches while they grow to that size during the first frames.
+    ///
+    /// The buffers still grow if a frame needs more, and never shrink.
+    /// Without streaming (WebGL1), these are the sizes of the largest mesh instead.
+    pub fn reserve_buffers(&mut self, vertices: usize, indices: usize) {
+        self.assert_not_destroyed();
+        self.reserve_buffer_bytes(
+            vertices * std::mem::size_of::<Vertex>(),
+            indices * std::mem::size_of::<u32>(),
+        );
+    }
+
+    fn reserve_buffer_bytes(&mut self, vertex_bytes: usize, index_bytes: usize) {
+        unsafe {
+            self.gl
+                .bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo.buffer));
+            self.vbo.reserve(&self.gl, vertex_bytes);
+            self.gl.bind_buffer(glow::ARRAY_BUFFER, None);
+
+            // The binding of the element array buffer is part of the VAO:
+            self.vao.bind(&self.gl);
+            self.gl.bind_buffer(
+                glow::ELEMENT_ARRAY_BUFFER,
+                Some(self.element_array_buffer.buffer),
+            );
+            self.element_array_buffer.reserve(&self.gl, index_bytes);
+            self.vao.unbind(&self.gl);
+        }
+    }
+
+    /// Set the usage hint the vertex and index buffers are allocated with:
+    /// `glow::STREAM_DRAW` (the default), `glow::DYNAMIC_DRAW` or `glow::STATIC_DRAW`.
+    ///
+    /// Which one is fastest depends on the driver, so measure before changing it.
+    /// Takes effect from the next frame.
+    pub fn set_buffer_usage(&mut self, usage: u32) {
+        if !matches!(
+            usage,
+            glow::STREAM_DRAW | glow::DYNAMIC_DRAW | glow::STATIC_DRAW
+        ) {
+            log::warn!("Ignoring unsupported buffer usage {usage:#x}");
+            return;
+        }
+        self.vbo.set_usage(usage);
+        self.element_array_buffer.set_usage(usage);
+    }
+
+    /// Statistics about the last painted frame.
+    pub fn paint_stats(&self) -> PaintStats {
+        self.stats
+    }
+
+    /// Measure how long the GPU takes to paint each frame, see [`Self::last_gpu_frame_time`].
+    ///
+    /// Uses timer queries, which need OpenGL 3.3 (or `ARB_timer_query`),
+    /// or `EXT_disjoint_timer_query` on OpenGL ES and WebGL2.
+    /// Elsewhere, this does nothing but log a warning.
+    ///
+    /// Paint callbacks can't use `GL_TIME_ELAPSED` queries of their own while this is on,
+    /// since those can't be nested.
+    pub fn set_gpu_timing(&mut self, gpu_timing: bool) {
+        if gpu_timing && !self.supports_timer_queries {
+            log::warn!("This context doesn't support timer queries. Ignoring set_gpu_timing.");
+        } else if gpu_timing {
+            self.gpu_timer
+                .get_or_insert_with(|| GpuTimer::new(&self.gl));
+        } else if let Some(gpu_timer) = self.gpu_timer.take() {
+            unsafe { gpu_timer.destroy(&self.gl) };
+        }
+    }
+
+    pub fn gpu_timing(&self) -> bool {
+        self.gpu_timer.is_some()
+    }
+
+    /// How long the GPU took to paint a recent frame, if [`Self::set_gpu_timing`] is on.
+    ///
+    /// To not stall the pipeline, the time arrives a frame or two after the frame was painted,
+    /// so this is `None` for the first few frames.
+    pub fn last_gpu_frame_time(&self) -> Option<std::time::Duration> {
+        self.gpu_timer.as_ref()?.last_frame_time()
+    }
+
+    // ------------------------------------------------------------------------
+
+    /// Create or update the texture `tex_id`.
+    ///
+    /// # Panics
+    /// If the texture is larger than [`Self::max_texture_side`] (unless downscaled, see [`Self::set_oversize_policy`]),
+    /// if a partial update doesn't fit inside the texture, or if the texture can't be created.
+    /// Use [`Self::try_set_texture`] for images you don't control.
+    pub fn set_texture(&mut self, tex_id: egui::TextureId, delta: &egui::epaint::ImageDelta) {
+        if let Err(err) = self.try_set_texture(tex_id, delta) {
+            panic!("{err}");
+        }
+    }
+
+    /// [`Self::set_texture`] for many deltas at once, e.g. [`egui::TexturesDelta::set`].
+    ///
+    /// The deltas of each texture are uploaded together (in the order they are given),
+    /// so a texture with many partial updates, like the font atlas, is only bound once.
+    ///
+    /// # Panics
+    /// Like [`Self::set_texture`], on the first delta that can't be uploaded.
+    pub fn set_textures(&mut self, deltas: &[(egui::TextureId, egui::epaint::ImageDelta)]) {
+        profiling::function_scope!();
+
+        self.batching_uploads = true;
+        self.batch_bound_texture = None;
+        let result = group_by_texture(deltas.iter().map(|(id, _)| *id))
+            .into_iter()
+            .try_for_each(|index| {
+                let (id, delta) = &deltas[index];
+                self.try_set_texture(*id, delta)
+            });
+        // Before panicking, so that uploads after a caught panic don't skip binding their texture:
+        self.batching_uploads = false;
+        self.batch_bound_texture = None;
+        if let Err(err) = result {
+            panic!("{err}");
+        }
+    }
+
+    /// Like [`Self::set_texture`], but returns an error instead of panicking.
+    ///
+    /// # Errors
+    /// If the texture is larger than [`Self::max_texture_side`] (unless downscaled, see [`Self::set_oversize_policy`]),
+    /// if a partial update doesn't fit inside the texture, or if the texture can't be created.
+    /// In that case the texture is left untouched: an existing texture keeps its texels,
+    /// and a new one is not registered.
+    pub fn try_set_texture(
+        &mut self,
+        tex_id: egui::TextureId,
+        delta: &egui::epaint::ImageDelta,
+    ) -> Result<(), PainterError> {
+        // RGBA8 is supported everywhere:
+        self.set_texture_with_options(tex_id, delta, UploadOptions::default())
+    }
+
+    /// Like [`Self::set_texture`], but only stores the alpha channel of the image,
+    /// in a single-channel texture. See [`TextureFormat::R8`].
+    ///
+    /// This is useful for the font atlas and other alpha masks, where the color is always white.
+    ///
+    /// Partial updates (with [`egui::epaint::ImageDelta::pos`] set) of such a texture
+    /// must also go through this function.
+    ///
+    /// Falls back to [`Self::set_texture`] on contexts without texture swizzling (e.g. WebGL).
+    ///
+    /// # Panics
+    /// Like [`Self::set_texture`], and if a partial update is for a texture
+    /// that wasn't uploaded with this function.
+    /// Use [`Self::try_set_texture_r8`] for images you don't control.
+    pub fn set_texture_r8(&mut self, tex_id: egui::TextureId, delta: &egui::epaint::ImageDelta) {
+        if let Err(err) = self.try_set_texture_r8(tex_id, delta) {
+            panic!("{err}");
+        }
+    }
+
+    /// Like [`Self::set_texture_r8`], but returns an error instead of panicking.
+    ///
+    /// # Errors
+    /// Like [`Self::try_set_texture`], and if a partial update is for a texture
+    /// that wasn't uploaded with [`Self::set_texture_r8`] (or this function).
+    /// In that case the texture is left untouched.
+    pub fn try_set_texture_r8(
+        &mut self,
+        tex_id: egui::TextureId,
+        delta: &egui::epaint::ImageDelta,
+    ) -> Result<(), PainterError> {
+        let format = if self.supports_texture_swizzle {
+            TextureFormat::R8
+        } else {
+            TextureFormat::Rgba8
+        };
+        let upload = UploadOptions {
+            format,
+            ..Default::default()
+        };
+        self.set_texture_with_options(tex_id, delta, upload)
+    }
+
+    /// Like [`Self::set_texture`], but with control over how the image is uploaded.
+    ///
+    /// The image is converted on the CPU to [`UploadOptions::format`],
+    /// dropping the channels that the format does not store.
+    /// Partial updates of the texture must use the same format as the full upload.
+    ///
+    /// # Errors
+    /// Returns an error if the format is not supported by the OpenGL context,
+    /// or is a float format (use [`Self::set_texture_data`] for those),
+    /// if the image is larger than [`Self::max_texture_side`], if a partial update
+    /// doesn't fit inside the texture or is in another format than the texture is stored in,
+    /// or if the texture can't be created.
+    /// In that case the texture is left untouched.
+    pub fn set_texture_with_options(
+        &mut self,
+        tex_id: egui::TextureId,
+        delta: &egui::epaint::ImageDelta,
+        upload: UploadOptions,
+    ) -> Result<(), PainterError> {
+        profiling::function_scope!();
+
+        self.assert_not_destroyed();
+
+        let format = upload.format;
+        let gl_format = self.gl_texture_format(format)?;
+
+        if upload.row_length_px.is_some() || upload.skip_px != [0, 0] {
+            return Err(PainterError::Other(
+                "Uploading a region of a larger image is only supported by Painter::set_texture_data"
+                    .to_owned(),
+            ));
+        }
+
+        match &delta.image {
+            egui::ImageData::Color(image) => {
+                assert_eq!(
+                    image.width() * image.height(),
+                    image.pixels.len(),
+                    "Mismatch between texture size and texel count"
+                );
+
+                let colors: Cow<'_, [egui::Color32]> = if upload.premultiply_alpha {
+                    let mut colors = image.pixels.clone();
+                    premultiply_alpha(bytemuck::cast_slice_mut(&mut colors));
+                    Cow::Owned(colors)
+                } else {
+                    Cow::Borrowed(&image.pixels)
+                };
+
+                let (size, colors) = if self.oversize_policy == OversizePolicy::Downscale
+                    && delta.pos.is_none()
+                    && self.max_texture_side < image.width().max(image.height())
+                {
+                    profiling::scope!("downscale");
+                    let factor = image
+                        .width()
+                        .max(image.height())
+                        .div_ceil(self.max_texture_side);
+                    let (size, colors) = downscale(&colors, image.size, factor);
+                    log::warn!(
+                        "Downscaled a texture image of size {}x{} to {}x{}, to fit the maximum supported texture side of {}",
+                        image.width(),
+                        image.height(),
+                        size[0],
+                        size[1],
+                        self.max_texture_side
+                    );
+                    (size, Cow::Owned(colors))
+                } else {
+                    (image.size, colors)
+                };
+
+                let data = format.texels_from_colors(&colors).ok_or_else(|| {
+                    PainterError::Other(format!(
+                        "egui images can't be uploaded as {format:?}. Use Painter::set_texture_data instead"
+                    ))
+                })?;
+
+                self.check_texture_size(size)?;
+                self.check_texture_region(tex_id, delta.pos, size, format)?;
+                let pos = self.bind_texture_for_upload(
+                    tex_id,
+                    delta.pos,
+                    size,
+                    delta.options,
+                    format,
+                    gl_format,
+                )?;
+                let options = self.set_texture_params(tex_id, delta.options);
+                self.upload_texture_srgb(
+                    pos,
+                    size,
+                    options,
+                    UploadOptions {
+                        premultiply_alpha: false, // already done
+                        ..upload
+                    },
+                    gl_format,
+                    data,
+                );
+                self.retain_texture(tex_id, delta.pos, image, delta.options, upload);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Keep a copy of each texture managed by egui on the CPU,
+    /// so that [`Self::on_context_restored`] can upload them again by itself.
+    ///
+    /// Otherwise the app has to get egui to send all its textures again,
+    /// and shows a blank screen until it does.
+    ///
+    /// This doubles the memory used by textures, and costs a copy of the image
+    /// on each partial update (like those of the font atlas), so it is off by default.
+    /// Only images set with [`Self::set_texture`] and [`Self::set_texture_with_options`] are kept,
+    /// not the raw texels of [`Self::set_texture_data`].
+    ///
+    /// Turning this on only keeps the textures set from then on.
+    pub fn set_retain_texture_data(&mut self, retain_texture_data: bool) {
+        if !retain_texture_data {
+            self.retained_textures = None;
+        } else if self.retained_textures.is_none() {
+            self.retained_textures = Some(HashMap::default());
+        }
+    }
+
+    pub fn retain_texture_data(&self) -> bool {
+        self.retained_textures.is_some()
+    }
+
+    /// Keep a copy of an image uploaded to a texture managed by egui, see [`Self::set_retain_texture_data`].
+    fn retain_texture(
+        &mut self,
+        tex_id: egui::TextureId,
+        pos: Option<[usize; 2]>,
+        image: &Arc<egui::ColorImage>,
+        options: egui::TextureOptions,
+        upload: UploadOptions,
+    ) {
+        if self.is_native_texture(tex_id) {
+            return;
+        }
+        let Some(retained_textures) = &mut self.retained_textures else {
+            return;
+        };
+
+        let Some([x, y]) = pos else {
+            retained_textures.insert(
+                tex_id,
+                RetainedTexture {
+                    image: Arc::clone(image),
+                    options,
+                    upload,
+                },
+            );
+            return;
+        };
+
+        let Some(retained) = retained_textures.get_mut(&tex_id) else {
+            return; // only the region is known
+        };
+        let [w, h] = image.size;
+        let [retained_w, retained_h] = retained.image.size;
+        if retained_w < x + w || retained_h < y + h {
+            // E.g. a partial update of a downscaled texture, which we can't follow:
+            retained_textures.remove(&tex_id);
+            return;
+        }
+        let retained_image = Arc::make_mut(&mut retained.image);
+        for row in 0..h {
+            let start = (y + row) * retained_w + x;
+            retained_image.pixels[start..start + w]
+                .copy_from_slice(&image.pixels[row * w..(row + 1) * w]);
+        }
+        retained.options = options;
+    }
+
+    /// Set the textures of a [`egui::TexturesDelta`] (and free those it frees),
+    /// without painting anything.
+    ///
+    /// This is how to get the textures managed by egui back after [`Self::on_context_restored`],
+    /// from a delta with all of them in it, unless [`Self::set_retain_texture_data`] is on.
+    ///
+    /// # Errors
+    /// See [`Self::try_set_texture`]. Stops at the first texture that fails.
+    pub fn reupload_textures(
+        &mut self,
+        textures_delta: &egui::TexturesDelta,
+    ) -> Result<(), PainterError> {
+        for (id, image_delta) in &textures_delta.set {
+            self.try_set_texture(*id, image_delta)?;
+        }
+        self.free_textures(&textures_delta.free);
+        Ok(())
+    }
+
+    /// Upload the copies kept by [`Self::set_retain_texture_data`] again.
+    fn reupload_retained_textures(&mut self) {
+        let Some(retained_textures) = self.retained_textures.take() else {
+            return;
+        };
+        self.retained_textures = Some(HashMap::default());
+
+        #[expect(clippy::iter_over_hash_type)]
+        for (tex_id, retained) in retained_textures {
+            let delta = egui::epaint::ImageDelta::full(
+                egui::ImageData::Color(retained.image),
+                retained.options,
+            );
+            if let Err(err) = self.set_texture_with_options(tex_id, &delta, retained.upload) {
+                log::warn!("Failed to upload texture {tex_id:?} again: {err}");
+            }
+        }
+    }
+
+    /// Upload raw texel data in any [`TextureFormat`], e.g. HDR content as [`TextureFormat::Rgba16F`].
+    ///
+    /// `data` must be tightly packed, i.e. `size[0] * size[1] * format.bytes_per_texel()` bytes,
+    /// in the native byte order.
+    ///
+    /// If `pos` is `Some`, only that region of an existing texture (of the same format) is updated.
+    ///
+    /// `data` may also be a larger image that the texels are picked from,
+    /// see [`UploadOptions::row_length_px`].
+    ///
+    /// # Errors
+    /// Returns an error if the format is not supported by the OpenGL context,
+    /// e.g. float textures on WebGL1 without the `OES_texture_float` extension,
+    /// if [`UploadOptions::premultiply_alpha`] is set for a format it doesn't support,
+    /// if `data` is not as long as `size` needs (or doesn't hold the region given by
+    /// [`UploadOptions::row_length_px`] and [`UploadOptions::skip_px`]),
+    /// if `size` is larger than [`Self::max_texture_side`], if the region at `pos`
+    /// doesn't fit inside the texture, or if the texture can't be created.
+    /// In that case the texture is left untouched.
+    pub fn set_texture_data(
+        &mut self,
+        tex_id: egui::TextureId,
+        pos: Option<[usize; 2]>,
+        size: [usize; 2],
+        options: egui::TextureOptions,
+        upload: UploadOptions,
+        data: &[u8],
+    ) -> Result<(), PainterError> {
+        profiling::function_scope!();
+
+        self.assert_not_destroyed();
+
+        let format = upload.format;
+        let gl_format = self.gl_texture_format(format)?;
+
+        if upload.premultiply_alpha
+            && !matches!(format, TextureFormat::Rgba8 | TextureFormat::Srgba8)
+        {
+            return Err(PainterError::Other(format!(
+                "Premultiplying alpha is not supported for {format:?} textures"
+            )));
+        }
+
+        self.check_texture_size(size)?;
+        self.check_texture_region(tex_id, pos, size, format)?;
+        upload.check_data_len(size, data.len())?;
+        let pos = self.bind_texture_for_upload(tex_id, pos, size, options, format, gl_format)?;
+        let options = self.set_texture_params(tex_id, options);
+        self.upload_texture_srgb(pos, size, options, upload, gl_format, Cow::Borrowed(data));
+        Ok(())
+    }
+
+    /// Fails (or panics, see [`OversizePolicy::Panic`]) if a texture of this size can't be uploaded.
+    fn check_texture_size(&self, [w, h]: [usize; 2]) -> Result<(), PainterError> {
+        if w <= self.max_texture_side && h <= self.max_texture_side {
+            return Ok(());
+        }
+
+        let err = PainterError::Other(format!(
+            "Got a texture image of size {w}x{h}, but the maximum supported texture side is only {}",
+            self.max_texture_side
+        ));
+        assert!(self.oversize_policy != OversizePolicy::Panic, "{err}");
+        Err(err)
+    }
+
+    /// Fails if a partial update at `pos` doesn't fit inside the texture it updates.
+    ///
+    /// Native textures are only checked for existence, since their size is just a hint.
+    fn check_texture_region(
+        &self,
+        tex_id: egui::TextureId,
+        pos: Option<[usize; 2]>,
+        [w, h]: [usize; 2],
+        format: TextureFormat,
+    ) -> Result<(), PainterError> {
+        let Some([x, y]) = pos else {
+            return Ok(());
+        };
+        let Some(texture) = self.textures.get(&tex_id) else {
+            return Err(PainterError::Other(format!(
+                "Can't update a region of texture {tex_id:?}, which hasn't been uploaded yet"
+            )));
+        };
+        if let Some([texture_w, texture_h]) = texture.size
+            && !texture.is_native
+            && (texture_w < x + w || texture_h < y + h)
+        {
+            return Err(PainterError::Other(format!(
+                "Region of size {w}x{h} at [{x}, {y}] is out of bounds of texture {tex_id:?}, which is {texture_w}x{texture_h}"
+            )));
+        }
+        if !texture.is_native && texture.format != format {
+            return Err(PainterError::Other(format!(
+                "Can't update a region of texture {tex_id:?} as {format:?}, since it is stored as {:?}",
+                texture.format
+            )));
+        }
+        Ok(())
+    }
+
+    /// Get (or create) the texture for `tex_id`, and bind it.
+    ///
+    /// For full uploads (`pos == None`) this also prepares the storage of the texture.
+    /// Where available, that is immutable storage allocated with `tex_storage_2d`,
+    /// which is only reallocated (as a new texture object) when the size or format changes.
+    ///
+    /// Returns the position to upload the texels to. This is `Some([0, 0])` for full uploads
+    /// to immutable storage, since those can only be written with `tex_sub_image_2d`.
+    ///
+    /// If the texture can't be created, the existing one (if any) is left as it was.
+    fn bind_texture_for_upload(
+        &mut self,
+        tex_id: egui::TextureId,
+        pos: Option<[usize; 2]>,
+        [w, h]: [usize; 2],
+        options: egui::TextureOptions,
+        format: TextureFormat,
+        gl_format: GlTextureFormat,
+    ) -> Result<Option<[usize; 2]>, PainterError> {
+        let is_sized_format = !matches!(
+            gl_format.internal_format,
+            glow::RGBA | glow::RGB | glow::SRGB_ALPHA
+        );
+        let storage =
+            (pos.is_none() && self.supports_texture_storage && is_sized_format && 0 < w && 0 < h)
+                .then(|| TextureStorage {
+                    size: [w, h],
+                    internal_format: gl_format.internal_format,
+                    levels: if options.mipmap_mode.is_some() {
+                        (usize::BITS - w.max(h).leading_zeros()) as i32
+                    } else {
+                        1
+                    },
+                });
+
+        // Partial updates and textures with mutable storage (including native ones) are updated in-place.
+        // Immutable storage can't be reallocated, so then we need a new texture.
+        let existing = self.textures.get(&tex_id);
+        if let Some(existing) = existing
+            && (pos.is_some() || existing.storage.is_none() || existing.storage == storage)
+        {
+            if !(self.batching_uploads && self.batch_bound_texture == Some(existing.texture)) {
+                self.bind_texture_2d(existing.texture);
+                if self.batching_uploads {
+                    self.batch_bound_texture = Some(existing.texture);
+                }
+            }
+            if pos.is_none() {
+                self.set_texture_swizzle(format);
+                self.textures.update(&tex_id, |texture| {
+                    texture.size = Some([w, h]);
+                    texture.format = format;
+                });
+            }
+            return Ok(if existing.storage.is_some() {
+                pos.or(Some([0, 0]))
+            } else {
+                pos
+            });
+        }
+
+        let texture = unsafe { self.gl.create_texture()? };
+        if let Some(existing) = existing {
+            unsafe { self.gl.delete_texture(existing.texture) };
+        }
+        self.textures.insert(
+            tex_id,
+            PainterTexture {
+                texture,
+                target: glow::TEXTURE_2D,
+                storage,
+                options: None,
+                is_native: false,
+                size: pos.is_none().then_some([w, h]),
+                format,
+            },
+        );
+        self.bind_texture_2d(texture);
+        if self.batching_uploads {
+            self.batch_bound_texture = Some(texture);
+        }
+        self.debug_label(
+            DebugObject::Texture(texture),
+            format_args!("egui texture {tex_id:?}"),
+        );
+
+        if let Some(storage) = storage {
+            unsafe {
+                self.gl.tex_storage_2d(
+                    glow::TEXTURE_2D,
+                    storage.levels,
+                    storage.internal_format,
+                    w as _,
+                    h as _,
+                );
+            }
+            check_for_gl_error!(&self.gl, "tex_storage_2d");
+        }
+        if pos.is_none() {
+            self.set_texture_swizzle(format);
+        }
+
+        Ok(if storage.is_some() { Some([0, 0]) } else { pos })
+    }
+
+    /// Set the swizzle of the bound texture, which is needed to sample [`TextureFormat::R8`] as RGBA.
+    fn set_texture_swizzle(&self, format: TextureFormat) {
+        if self.supports_texture_swizzle {
+            // The swizzle sticks with the texture, so we need to reset it when going back to RGBA.
+            let swizzle = match format {
+                TextureFormat::R8 => [glow::RED; 4], // premultiplied white
+                TextureFormat::Rgba8
+                | TextureFormat::Srgba8
+                | TextureFormat::Rgb8
+                | TextureFormat::Rg8
+                | TextureFormat::Rgba16F
+                | TextureFormat::Rgba32F => [glow::RED, glow::GREEN, glow::BLUE, glow::ALPHA],
+            };
+            unsafe {
+                self.gl.tex_parameter_i32_slice(
+                    glow::TEXTURE_2D,
+                    glow::TEXTURE_SWIZZLE_RGBA,
+                    &swizzle.map(|channel| channel as i32),
+                );
+            }
+            check_for_gl_error!(&self.gl, "TEXTURE_SWIZZLE_RGBA");
+        }
+    }
+
+    /// Remember the options `tex_id` was uploaded with, and set how the bound texture is sampled.
+    ///
+    /// The parameters are only set if the options differ from the ones the texture is sampled with,
+    /// so e.g. partial updates of the font atlas don't set them again every time.
+    ///
+    /// Returns the options actually used, see [`Self::supported_texture_options`].
+    fn set_texture_params(
+        &mut self,
+        tex_id: egui::TextureId,
+        options: egui::TextureOptions,
+    ) -> egui::TextureOptions {
+        let texture = self.textures.get(&tex_id);
+        let size = texture.and_then(|texture| texture.size);
+        let format = texture.map(|texture| texture.format);
+        let options = self.supported_texture_options(size, format, options);
+        if texture.and_then(|texture| texture.options) == Some(options) {
+            // E.g. a partial update, of a texture that is already sampled like that:
+            return options;
+        }
+        self.textures.update(&tex_id, |texture| {
+            texture.options = Some(options);
+        });
+        self.apply_texture_params(tex_id);
+        options
+    }
+
+    /// WebGL1 can't repeat or mipmap textures whose sides aren't powers of two:
+    /// they are sampled as black instead. So such textures are clamped to the edge, without mipmaps.
+    ///
+    /// Likewise, [`TextureFormat::Rgba32F`] textures are sampled as black when filtered linearly
+    /// on OpenGL ES and WebGL without `OES_texture_float_linear`, so those are filtered with `NEAREST`.
+    fn supported_texture_options(
+        &mut self,
+        size: Option<[usize; 2]>,
+        format: Option<TextureFormat>,
+        mut options: egui::TextureOptions,
+    ) -> egui::TextureOptions {
+        let is_linear = options.magnification == egui::TextureFilter::Linear
+            || options.minification == egui::TextureFilter::Linear
+            || options.mipmap_mode == Some(egui::TextureFilter::Linear);
+        if format == Some(TextureFormat::Rgba32F) && !self.supports_float_linear && is_linear {
+            if !std::mem::replace(&mut self.warned_about_float_filtering, true) {
+                log::warn!(
+                    "This context lacks OES_texture_float_linear, so Rgba32F textures are filtered with NEAREST instead (this warning is only shown once)"
+                );
+            }
+            options.magnification = egui::TextureFilter::Nearest;
+            options.minification = egui::TextureFilter::Nearest;
+            options.mipmap_mode = options.mipmap_mode.map(|_| egui::TextureFilter::Nearest);
+        }
+
+        let Some([w, h]) = size else {
+            return options;
+        };
+        let is_npot = !w.is_power_of_two() || !h.is_power_of_two();
+        let needs_pot = options.wrap_mode != egui::TextureWrapMode::ClampToEdge
+            || options.mipmap_mode.is_some();
+        if self.is_webgl_1 && is_npot && needs_pot {
+            if !std::mem::replace(&mut self.warned_about_npot_textures, true) {
+                log::warn!(
+                    "WebGL1 only supports repeating and mipmapping textures whose sides are powers of two. Clamping a {w}x{h} texture to the edge, without mipmaps (this warning is only shown once)"
+                );
+            }
+            options.wrap_mode = egui::TextureWrapMode::ClampToEdge;
+            options.mipmap_mode = None;
+        }
+        options
+    }
+
+    /// Set how the bound texture `tex_id` is sampled, from the [`egui::TextureOptions`]
+    /// it was uploaded with and any [`SamplerOptions`] set for it.
+    ///
+    /// With sampler objects, only the mipmap levels are set here, and the rest per draw call.
+    /// For native textures, only the parts of the [`SamplerOptions`] that are set are applied.
+    fn apply_texture_params(&self, tex_id: egui::TextureId) {
+        let options = self
+            .textures
+            .get(&tex_id)
+            .and_then(|texture| texture.options);
+        let sampler_options = self.sampler_options.get(&tex_id);
+
+        if !self.supports_samplers || options.is_none() {
+            self.set_sampling_params(ParameterTarget::BoundTexture, options, sampler_options);
+        }
+
+        if self.supports_mipmap_levels {
+            // Not part of `egui::TextureOptions`, so reset to the defaults unless set:
+            let is_native = options.is_none();
+            let base_level = sampler_options
+                .and_then(|sampler_options| sampler_options.mipmap_base_level)
+                .map(i32::from)
+                .or_else(|| (!is_native).then_some(0));
+            let max_level = sampler_options
+                .and_then(|sampler_options| sampler_options.mipmap_max_level)
+                .map(i32::from)
+                .or_else(|| (!is_native).then_some(1000));
+            unsafe {
+                if let Some(base_level) = base_level {
+                    self.gl.tex_parameter_i32(
+                        glow::TEXTURE_2D,
+                        glow::TEXTURE_BASE_LEVEL,
+                        base_level,
+                    );
+                }
+                if let Some(max_level) = max_level {
+                    self.gl
+                        .tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAX_LEVEL, max_level);
+                }
+            }
+            check_for_gl_error!(&self.gl, "mipmap levels");
+        }
+    }
+
+    /// Set the filtering and wrapping of the bound texture, or of a sampler object.
+    ///
+    /// If `options` is `None`, only the parts of the [`SamplerOptions`] that are set are applied.
+    fn set_sampling_params(
+        &self,
+        target: ParameterTarget,
+        options: Option<egui::TextureOptions>,
+        sampler_options: Option<&SamplerOptions>,
+    ) {
+        let gl = &self.gl;
+
+        if let Some(options) = options {
+            unsafe {
+                target.set_i32(
+                    gl,
+                    glow::TEXTURE_MAG_FILTER,
+                    options.magnification.glow_code(None) as i32,
+                );
+                target.set_i32(
+                    gl,
+                    glow::TEXTURE_MIN_FILTER,
+                    options.minification.glow_code(options.mipmap_mode) as i32,
+                );
+
+                target.set_i32(
+                    gl,
+                    glow::TEXTURE_WRAP_S,
+                    options.wrap_mode.glow_code() as i32,
+                );
+                target.set_i32(
+                    gl,
+                    glow::TEXTURE_WRAP_T,
+                    options.wrap_mode.glow_code() as i32,
+                );
+
+                // Not part of `egui::TextureOptions`, so reset to the defaults:
+                if self.max_anisotropy.is_some() {
+                    target.set_f32(gl, glow::TEXTURE_MAX_ANISOTROPY_EXT, 1.0);
+                }
+                if self.supports_lod_bias {
+                    target.set_f32(gl, glow::TEXTURE_LOD_BIAS, 0.0);
+                }
+            }
+            check_for_gl_error!(&self.gl, "tex_parameter");
+        }
+
+        let Some(sampler_options) = sampler_options else {
+            return;
+        };
+        let SamplerOptions {
+            wrap_mode_s,
+            wrap_mode_t,
+            border_color,
+            anisotropy,
+            mipmap_base_level: _, // not a sampler parameter
+            mipmap_max_level: _,  // not a sampler parameter
+            lod_bias,
+        } = *sampler_options;
+
+        let wrap_code = |wrap_mode: WrapMode| {
+            if wrap_mode == WrapMode::ClampToBorder && !self.supports_clamp_to_border {
+                WrapMode::ClampToEdge.glow_code()
+            } else {
+                wrap_mode.glow_code()
+            }
+        };
+
+        unsafe {
+            if let Some(wrap_mode_s) = wrap_mode_s {
+                target.set_i32(gl, glow::TEXTURE_WRAP_S, wrap_code(wrap_mode_s) as i32);
+            }
+            if let Some(wrap_mode_t) = wrap_mode_t {
+                target.set_i32(gl, glow::TEXTURE_WRAP_T, wrap_code(wrap_mode_t) as i32);
+            }
+            if sampler_options.uses_border() && self.supports_clamp_to_border {
+                target.set_f32_slice(
+                    gl,
+                    glow::TEXTURE_BORDER_COLOR,
+                    &border_color.to_normalized_gamma_f32(),
+                );
+            }
+            if let Some(anisotropy) = anisotropy {
+                if let Some(max_anisotropy) = self.max_anisotropy {
+                    target.set_f32(
+                        gl,
+                        glow::TEXTURE_MAX_ANISOTROPY_EXT,
+                        f32::from(anisotropy).clamp(1.0, max_anisotropy),
+                    );
+                } else {
+                    log::debug!(
+                        "Ignoring anisotropy: this context lacks EXT_texture_filter_anisotropic"
+                    );
+                }
+            }
+            if let Some(lod_bias) = lod_bias
+                && self.supports_lod_bias
+            {
+                target.set_f32(gl, glow::TEXTURE_LOD_BIAS, lod_bias);
+            }
+        }
+        check_for_gl_error!(&self.gl, "sampler options");
+    }
+
+    /// Bind `texture` to `TEXTURE_2D`, e.g. to upload to it or set its parameters.
+    ///
+    /// This may be between [`Self::begin_frame`] and [`Self::end_frame`],
+    /// so the next mesh can't assume its texture is still bound.
+    fn bind_texture_2d(&mut self, texture: glow::Texture) {
+        unsafe { self.gl.bind_texture(glow::TEXTURE_2D, Some(texture)) };
+        self.bound_texture = None;
+    }
+
+    /// The sampler object for sampling a texture with the given options, created on first use.
+    ///
+    /// `None` if it can't be created, e.g. because the context was lost,
+    /// in which case the parameters have to be set on the texture instead.
+    fn sampler(
+        &mut self,
+        options: egui::TextureOptions,
+        sampler_options: SamplerOptions,
+    ) -> Option<glow::Sampler> {
+        if let Some(&sampler) = self.samplers.get(&(options, sampler_options)) {
+            return Some(sampler);
+        }
+
+        let sampler = match unsafe { self.gl.create_sampler() } {
+            Ok(sampler) => sampler,
+            Err(err) => {
+                log::warn!("Failed to create a sampler object: {err}");
+                return None;
+            }
+        };
+        self.set_sampling_params(
+            ParameterTarget::Sampler(sampler),
+            Some(options),
+            Some(&sampler_options),
+        );
+        self.samplers.insert((options, sampler_options), sampler);
+        Some(sampler)
+    }
+
+    /// Which OpenGL formats to use for uploading textures of the given [`TextureFormat`].
+    fn gl_texture_format(&self, format: TextureFormat) -> Result<GlTextureFormat, PainterError> {
+        let has_extension = |name: &str| {
+            self.gl
+                .supported_extensions()
+                .iter()
+                .any(|extension| extension.ends_with(name))
+        };
+
+        match format {
+            TextureFormat::Rgba8 => {
+                let (internal_format, src_format) = if self.is_webgl_1 {
+                    let format = if self.srgb_textures {
+                        glow::SRGB_ALPHA
+                    } else {
+                        glow::RGBA
+                    };
+                    (format, format)
+                } else if self.srgb_textures {
+                    (glow::SRGB8_ALPHA8, glow::RGBA)
+                } else {
+                    (glow::RGBA8, glow::RGBA)
+                };
+                Ok(GlTextureFormat {
+                    internal_format,
+                    src_format,
+                    pixel_type: glow::UNSIGNED_BYTE,
+                })
+            }
+
+            TextureFormat::Srgba8 => {
+                if self.is_webgl_1 && has_extension("EXT_sRGB") {
+                    // Unsized formats only:
+                    Ok(GlTextureFormat {
+                        internal_format: glow::SRGB_ALPHA,
+                        src_format: glow::SRGB_ALPHA,
+                        pixel_type: glow::UNSIGNED_BYTE,
+                    })
+                } else if !self.is_webgl_1 && is_gl_version_at_least(&self.gl, [2, 1], [3, 0]) {
+                    Ok(GlTextureFormat {
+                        internal_format: glow::SRGB8_ALPHA8,
+                        src_format: glow::RGBA,
+                        pixel_type: glow::UNSIGNED_BYTE,
+                    })
+                } else {
+                    Err(PainterError::Other(
+                        "sRGB textures are not supported by this context (missing EXT_sRGB)"
+                            .to_owned(),
+                    ))
+                }
+            }
+
+            TextureFormat::Rgb8 => Ok(GlTextureFormat {
+                internal_format: if self.is_webgl_1 {
+                    glow::RGB
+                } else {
+                    glow::RGB8
+                },
+                src_format: glow::RGB,
+                pixel_type: glow::UNSIGNED_BYTE,
+            }),
+
+            TextureFormat::Rg8 => {
+                if !self.is_webgl_1
+                    && (is_gl_version_at_least(&self.gl, [3, 0], [3, 0])
+                        || has_extension("ARB_texture_rg"))
+                {
+                    Ok(GlTextureFormat {
+                        internal_format: glow::RG8,
+                        src_format: glow::RG,
+                        pixel_type: glow::UNSIGNED_BYTE,
+                    })
+                } else {
+                    Err(PainterError::Other(
+                        "RG8 textures are not supported by this context (missing ARB_texture_rg)"
+                            .to_owned(),
+                    ))
+                }
+            }
+
+            TextureFormat::R8 => {
+                if self.supports_texture_swizzle {
+                    Ok(GlTextureFormat {
+                        internal_format: glow::R8,
+                        src_format: glow::RED,
+                        pixel_type: glow::UNSIGNED_BYTE,
+                    })
+                } else {
+                    Err(PainterError::Other(
+                        "R8 textures require texture swizzling, which this context lacks"
+                            .to_owned(),
+                    ))
+                }
+            }
+
+            TextureFormat::Rgba16F | TextureFormat::Rgba32F => {
+                let (sized_format, pixel_type, extension) = if format == TextureFormat::Rgba16F {
+                    (glow::RGBA16F, glow::HALF_FLOAT, "OES_texture_half_float")
+                } else {
+                    (glow::RGBA32F, glow::FLOAT, "OES_texture_float")
+                };
+
+                if is_gl_version_at_least(&self.gl, [3, 0], [3, 0])
+                    || (!self.gl.version().is_embedded && has_extension("ARB_texture_float"))
+                {
+                    Ok(GlTextureFormat {
+                        internal_format: sized_format,
+                        src_format: glow::RGBA,
+                        pixel_type,
+                    })
+                } else if has_extension(extension) {
+                    // OpenGL ES 2.0 / WebGL1 only has unsized formats.
+                    let pixel_type = if pixel_type == glow::HALF_FLOAT {
+                        glow::HALF_FLOAT_OES
+                    } else {
+                        pixel_type
+                    };
+                    Ok(GlTextureFormat {
+                        internal_format: glow::RGBA,
+                        src_format: glow::RGBA,
+                        pixel_type,
+                    })
+                } else {
+                    Err(PainterError::Other(format!(
+                        "{format:?} textures are not supported by this context (missing {extension})"
+                    )))
+                }
+            }
+        }
+    }
+
+    fn upload_texture_srgb(
+        &mut self,
+        pos: Option<[usize; 2]>,
+        [w, h]: [usize; 2],
+        options: egui::TextureOptions,
+        upload: UploadOptions,
+        gl_format: GlTextureFormat,
+        data: Cow<'_, [u8]>,
+    ) {
+        profiling::function_scope!();
+        let format = upload.format;
+        let bytes_per_texel = format.bytes_per_texel();
+        let mut row_length = upload.row_length_px.unwrap_or(w);
+        let mut skip_px = upload.skip_px;
+        assert!(
+            w <= row_length,
+            "The row length ({row_length}) must be at least the width ({w})"
+        );
+        if upload.is_strided(w) {
+            let [x, y] = skip_px;
+            let required_len = if h == 0 {
+                0
+            } else {
+                ((y + h - 1) * row_length + x + w) * bytes_per_texel
+            };
+            assert!(
+                required_len <= data.len(),
+                "Region of size {w}x{h} at [{x}, {y}] is out of bounds of the data"
+            );
+        } else {
+            assert_eq!(
+                data.len(),
+                w * h * bytes_per_texel,
+                "Mismatch between texture size ({w}x{h}) and texel count"
+            );
+        }
+        debug_assert!(
+            w <= self.max_texture_side && h <= self.max_texture_side,
+            "Texture sizes are checked before upload"
+        );
+
+        let needs_cpu_copy = upload.premultiply_alpha || upload.flip_y;
+        let data = if upload.is_strided(w) && (needs_cpu_copy || !self.supports_unpack_row_length) {
+            profiling::scope!("pack_rows");
+            let packed = pack_rows(&data, [w, h], row_length, skip_px, bytes_per_texel);
+            row_length = w;
+            skip_px = [0, 0];
+            Cow::Owned(packed)
+        } else {
+            data
+        };
+
+        let data = if needs_cpu_copy {
+            let mut data = data.into_owned();
+            if upload.premultiply_alpha {
+                profiling::scope!("premultiply_alpha");
+                premultiply_alpha(&mut data);
+            }
+            if upload.flip_y {
+                profiling::scope!("flip_y");
+                flip_rows(&mut data, w * bytes_per_texel);
+            }
+            Cow::Owned(data)
+        } else {
+            data
+        };
+
+        unsafe {
+            let GlTextureFormat {
+                internal_format,
+                src_format,
+                pixel_type,
+            } = gl_format;
+
+            self.gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+
+            let is_strided = row_length != w || skip_px != [0, 0];
+            if is_strided {
+                self.gl
+                    .pixel_store_i32(glow::UNPACK_ROW_LENGTH, row_length as i32);
+                self.gl
+                    .pixel_store_i32(glow::UNPACK_SKIP_PIXELS, skip_px[0] as i32);
+                self.gl
+                    .pixel_store_i32(glow::UNPACK_SKIP_ROWS, skip_px[1] as i32);
+            }
+
+            let level = 0;
+            if let Some([x, y]) = pos {
+                profiling::scope!("gl.tex_sub_image_2d");
+                self.gl.tex_sub_image_2d(
+                    glow::TEXTURE_2D,
+                    level,
+                    x as _,
+                    y as _,
+                    w as _,
+                    h as _,
+                    src_format,
+                    pixel_type,
+                    glow::PixelUnpackData::Slice(Some(&data)),
+                );
+                check_for_gl_error!(&self.gl, "tex_sub_image_2d");
+            } else {
+                let border = 0;
+                profiling::scope!("gl.tex_image_2d");
+                self.gl.tex_image_2d(
+                    glow::TEXTURE_2D,
+                    level,
+                    internal_format as _,
+                    w as _,
+                    h as _,
+                    border,
+                    src_format,
+                    pixel_type,
+                    glow::PixelUnpackData::Slice(Some(&data)),
+                );
+                check_for_gl_error!(&self.gl, "tex_image_2d");
+            }
+
+            if is_strided {
+                // Reset, so we don't mess up later uploads (ours or anyone else's):
+                self.gl.pixel_store_i32(glow::UNPACK_ROW_LENGTH, 0);
+                self.gl.pixel_store_i32(glow::UNPACK_SKIP_PIXELS, 0);
+                self.gl.pixel_store_i32(glow::UNPACK_SKIP_ROWS, 0);
+            }
+
+            if options.mipmap_mode.is_some() {
+                self.gl.generate_mipmap(glow::TEXTURE_2D);
+                check_for_gl_error!(&self.gl, "generate_mipmap");
+            }
+        }
+    }
+
+    /// Change how a texture is filtered and wrapped, without re-uploading its texels.
     ///
-    /// The scissor area and blend parameters will be changed.
+    /// This is useful for e.g. toggling between pixel-perfect and smooth scaling of an image at runtime.
+    /// Turning on [`egui::TextureOptions::mipmap_mode`] generates the mipmaps from the current texels,
+    /// but only for textures that have room for them. Where immutable texture storage is available
+    /// (OpenGL 4.2, OpenGL ES 3.0 and WebGL2), a texture uploaded without mipmaps has a single level,
+    /// so it stays without mipmaps (and a warning is logged). Choose the mipmap mode when uploading it instead,
+    /// or upload it again.
     ///
-    /// As well as this, the following objects will be unset:
-    /// - Vertex Buffer
-    /// - Element Buffer
-    /// - Texture (and active texture will be set to 0)
-    /// - Program
+    /// Does nothing (except log a warning) if the texture is unknown.
+    pub fn set_texture_options(&mut self, tex_id: egui::TextureId, options: egui::TextureOptions) {
+        self.assert_not_destroyed();
+
+        let Some(texture) = self.textures.get(&tex_id) else {
+            log::warn!("Failed to set the options of unknown texture {tex_id:?}");
+            return;
+        };
+        if texture.target != glow::TEXTURE_2D {
+            log::warn!("Can't set the options of external texture {tex_id:?}");
+            return;
+        }
+
+        let mut supported_options = options;
+        if options.mipmap_mode.is_some()
+            && let Some(storage) = texture.storage
+            && storage.levels == 1
+            && storage.size != [1, 1]
+        {
+            log::warn!(
+                "Texture {tex_id:?} was uploaded without mipmaps, and its immutable storage has no room for them. Upload it again to mipmap it"
+            );
+            supported_options.mipmap_mode = None;
+        }
+
+        self.bind_texture_2d(texture.texture);
+        let applied_options = self.set_texture_params(tex_id, supported_options);
+        if let Some(retained) = self
+            .retained_textures
+            .as_mut()
+            .and_then(|retained_textures| retained_textures.get_mut(&tex_id))
+        {
+            retained.options = options;
+        }
+
+        let had_mipmaps = texture
+            .options
+            .is_some_and(|options| options.mipmap_mode.is_some());
+        if applied_options.mipmap_mode.is_some() && !had_mipmaps {
+            unsafe { self.gl.generate_mipmap(glow::TEXTURE_2D) };
+            check_for_gl_error!(&self.gl, "generate_mipmap");
+        }
+    }
+
+    /// Set OpenGL-specific sampling options for a texture, e.g. a different wrap mode per axis.
     ///
-    /// Please be mindful of these effects when integrating into your program, and also be mindful
-    /// of the effects your program might have on this code. Look at the source if in doubt.
-    pub fn paint_primitives(
-        &mut self,
-        screen_size_px: [u32; 2],
-        pixels_per_point: f32,
-        clipped_primitives: &[egui::ClippedPrimitive],
-    ) {
-        profiling::function_scope!();
+    /// These stick with the [`egui::TextureId`] across uploads, until the texture is freed.
+    /// They may be set before the texture is first uploaded, and also work for native textures.
+    ///
+    /// If the texture exists, the options take effect immediately.
+    /// For native textures, options that are `None` leave the texture's own parameters untouched.
+    pub fn set_sampler_options(&mut self, tex_id: egui::TextureId, options: SamplerOptions) {
         self.assert_not_destroyed();
 
-        unsafe { self.prepare_painting(screen_size_px, pixels_per_point) };
+        if options.uses_border() && !self.supports_clamp_to_border {
+            log::warn!(
+                "WrapMode::ClampToBorder is not supported by this context; falling back to ClampToEdge for {tex_id:?}"
+            );
+        }
+
+        self.sampler_options.insert(tex_id, options);
 
-        for egui::ClippedPrimitive {
-            clip_rect,
-            primitive,
-        } in clipped_primitives
+        if let Some(texture) = self.textures.get(&tex_id)
+            && texture.target == glow::TEXTURE_2D
         {
-            set_clip_rect(&self.gl, screen_size_px, pixels_per_point, *clip_rect);
+            self.bind_texture_2d(texture.texture);
+            self.apply_texture_params(tex_id);
+        }
+    }
 
-            match primitive {
-                Primitive::Mesh(mesh) => {
-                    self.paint_mesh(mesh);
-                }
-                Primitive::Callback(callback) => {
-                    if callback.rect.is_positive() {
-                        profiling::scope!("callback");
+    /// The [`SamplerOptions`] set for a texture with [`Self::set_sampler_options`], if any.
+    pub fn sampler_options(&self, tex_id: egui::TextureId) -> Option<SamplerOptions> {
+        self.sampler_options.get(&tex_id).copied()
+    }
 
-                        let info = egui::PaintCallbackInfo {
-                            viewport: callback.rect,
-                            clip_rect: *clip_rect,
-                            pixels_per_point,
-                            screen_size_px,
-                        };
+    /// Delete the native textures replaced with [`Self::replace_native_texture`].
+    ///
+    /// Their deletion is deferred, since they may still be in use by the primitives being painted.
+    /// This is called at the end of [`Self::paint_primitives`],
+    /// so you only need to call it yourself when replacing textures outside of painting.
+    ///
+    /// Like all other methods of the painter, this must be called on the thread owning the GL context.
+    pub fn flush_deleted_textures(&mut self) {
+        for texture in self.textures_to_destroy.drain(..) {
+            unsafe { self.gl.delete_texture(texture) };
+        }
+    }
 
-                        let viewport_px = info.viewport_in_pixels();
-                        unsafe {
-                            self.gl.viewport(
-                                viewport_px.left_px,
-                                viewport_px.from_bottom_px,
-                                viewport_px.width_px,
-                                viewport_px.height_px,
-                            );
-                        }
+    /// Free a texture, and delete its [`glow::Texture`].
+    ///
+    /// This also deletes native textures, which the caller may still be using elsewhere.
+    /// Use [`Self::free_managed_texture`] to only free textures managed by egui.
+    pub fn free_texture(&mut self, tex_id: egui::TextureId) {
+        self.free_textures(&[tex_id]);
+    }
 
-                        if let Some(callback) = callback.callback.downcast_ref::<CallbackFn>() {
-                            (callback.f)(info, self);
-                        } else {
-                            log::warn!(
-                                "Warning: Unsupported render callback. Expected egui_glow::CallbackFn"
-                            );
-                        }
+    /// Free a texture managed by egui, but leave native textures (see [`Self::is_native_texture`]) alone.
+    ///
+    /// Returns `false` (and logs a warning) if `tex_id` is native, and thus wasn't freed.
+    pub fn free_managed_texture(&mut self, tex_id: egui::TextureId) -> bool {
+        if self.is_native_texture(tex_id) {
+            log::warn!("Refusing to free native texture {tex_id:?}");
+            false
+        } else {
+            self.free_texture(tex_id);
+            true
+        }
+    }
 
-                        check_for_gl_error!(&self.gl, "callback");
+    /// Is this the id of a texture the app registered with [`Self::register_native_texture`],
+    /// rather than one the painter created?
+    ///
+    /// The textures of [`Self::allocate_texture`], [`Self::create_render_target`]
+    /// and [`Self::register_native_texture_array_layer`] are [`egui::TextureId::User`] too,
+    /// but belong to the painter, so they are not native.
+    /// `false` for unknown ids.
+    pub fn is_native_texture(&self, tex_id: egui::TextureId) -> bool {
+        self.textures
+            .get(&tex_id)
+            .is_some_and(|texture| texture.is_native)
+    }
 
-                        // Restore state:
-                        unsafe { self.prepare_painting(screen_size_px, pixels_per_point) };
-                    }
+    /// Free many textures at once, e.g. all the images of a closed document.
+    ///
+    /// Unknown ids are skipped.
+    pub fn free_textures(&mut self, tex_ids: &[egui::TextureId]) {
+        let old_textures: Vec<glow::Texture> = tex_ids
+            .iter()
+            .filter_map(|tex_id| {
+                self.sampler_options.remove(tex_id);
+                self.array_layers.remove(tex_id);
+                if let Some(retained_textures) = &mut self.retained_textures {
+                    retained_textures.remove(tex_id);
                 }
-            }
+                self.textures.remove(tex_id)
+            })
+            .map(|old_tex| old_tex.texture)
+            .collect();
+        for old_tex in old_textures {
+            unsafe { self.gl.delete_texture(old_tex) };
         }
+    }
 
-        unsafe {
-            self.vao.unbind(&self.gl);
-            self.gl.bind_buffer(glow::ELEMENT_ARRAY_BUFFER, None);
+    /// Get the [`glow::Texture`] bound to a [`egui::TextureId`].
+    ///
+    /// Where sampler objects are supported (OpenGL 3.3, OpenGL ES 3.0 / WebGL2), egui samples its own
+    /// textures through those, and leaves the filtering and wrapping of the texture itself at the OpenGL defaults.
+    pub fn texture(&self, texture_id: egui::TextureId) -> Option<glow::Texture> {
+        self.textures
+            .get(&texture_id)
+            .map(|texture| texture.texture)
+    }
 
-            self.gl.disable(glow::SCISSOR_TEST);
+    /// All textures known to the painter, both managed by egui and native, in no particular order.
+    pub fn textures(&self) -> impl Iterator<Item = (egui::TextureId, glow::Texture)> + '_ {
+        self.textures
+            .all()
+            .into_iter()
+            .map(|(id, texture)| (id, texture.texture))
+    }
+
+    /// The number of textures known to the painter, see [`Self::textures`].
+    pub fn texture_count(&self) -> usize {
+        self.textures.len()
+    }
+
+    /// A handle to the textures of this painter, to create painters for other contexts
+    /// that share objects with this one with [`PainterBuilder::shared_textures`].
+    ///
+    /// [`Self::set_texture`], [`Self::free_texture`] and the native textures of any of these painters
+    /// are seen by all of them. Sampling options (see [`Self::set_sampler_options`]) are still per painter.
+    /// The textures are deleted by [`Self::destroy`] once the last of them is destroyed.
+    pub fn shared_textures(&self) -> SharedTextures {
+        self.textures.clone()
+    }
+
+    pub(crate) fn use_shared_textures(&mut self, textures: SharedTextures) {
+        self.textures = textures;
+    }
+
+    /// Register a native `TEXTURE_2D` texture, so that egui can paint it.
+    ///
+    /// The painter can't query the size of a texture on OpenGL ES and WebGL,
+    /// so pass it as `size_hint` if you know it. It is used for [`Self::texture_memory_bytes`],
+    /// assuming four bytes per texel, and for [`Self::read_texture_rgba`].
+    #[expect(clippy::needless_pass_by_ref_mut)] // Intentionally hide interiority of mutability
+    pub fn register_native_texture(
+        &mut self,
+        native: glow::Texture,
+        size_hint: Option<[usize; 2]>,
+    ) -> egui::TextureId {
+        self.assert_not_destroyed();
+        let id = self.textures.next_native_tex_id();
+        self.textures.insert(
+            id,
+            PainterTexture::native(native, glow::TEXTURE_2D, size_hint),
+        );
+        id
+    }
+
+    /// Like [`Self::register_native_texture`], but egui samples the texture with `options`,
+    /// like the textures it manages, instead of with whatever parameters it was created with.
+    ///
+    /// With [`egui::TextureOptions::mipmap_mode`], the texture needs mipmaps, which are up to you,
+    /// e.g. with `glGenerateMipmap` after rendering into it.
+    /// See also [`Self::set_texture_options`].
+    pub fn register_native_texture_with_options(
+        &mut self,
+        native: glow::Texture,
+        size_hint: Option<[usize; 2]>,
+        options: egui::TextureOptions,
+    ) -> egui::TextureId {
+        let id = self.register_native_texture(native, size_hint);
+        self.bind_texture_2d(native);
+        self.set_texture_params(id, options);
+        id
+    }
 
-            check_for_gl_error!(&self.gl, "painting");
+    /// Replace the native texture behind `id`, e.g. after resizing it.
+    ///
+    /// The new texture is sampled with its own parameters,
+    /// even if the old one was registered with options (see [`Self::replace_native_texture_with_options`]).
+    /// The old texture is deleted by the next [`Self::flush_deleted_textures`].
+    ///
+    /// See [`Self::register_native_texture`] for `size_hint`.
+    pub fn replace_native_texture(
+        &mut self,
+        id: egui::TextureId,
+        replacing: glow::Texture,
+        size_hint: Option<[usize; 2]>,
+    ) {
+        let target = self
+            .textures
+            .get(&id)
+            .map_or(glow::TEXTURE_2D, |texture| texture.target);
+        let replacing = PainterTexture::native(replacing, target, size_hint);
+        if let Some(old_tex) = self.textures.insert(id, replacing) {
+            self.textures_to_destroy.push(old_tex.texture);
         }
     }
 
-    #[inline(never)] // Easier profiling
-    fn paint_mesh(&mut self, mesh: &Mesh) {
-        debug_assert!(mesh.is_valid(), "Mesh is not valid");
-        if let Some(texture) = self.texture(mesh.texture_id) {
-            unsafe {
-                self.gl.bind_buffer(glow::ARRAY_BUFFER, Some(self.vbo));
-                self.gl.buffer_data_u8_slice(
-                    glow::ARRAY_BUFFER,
-                    bytemuck::cast_slice(&mesh.vertices),
-                    glow::STREAM_DRAW,
-                );
+    /// Like [`Self::replace_native_texture`], but egui samples the new texture with `options`,
+    /// see [`Self::register_native_texture_with_options`].
+    pub fn replace_native_texture_with_options(
+        &mut self,
+        id: egui::TextureId,
+        replacing: glow::Texture,
+        size_hint: Option<[usize; 2]>,
+        options: egui::TextureOptions,
+    ) {
+        self.replace_native_texture(id, replacing, size_hint);
+        if self
+            .textures
+            .get(&id)
+            .is_some_and(|texture| texture.target != glow::TEXTURE_2D)
+        {
+            log::warn!("Can't set the options of external texture {id:?}");
+            return;
+        }
+        self.bind_texture_2d(replacing);
+        self.set_texture_params(id, options);
+    }
 
-                self.gl
-                    .bind_buffer(glow::ELEMENT_ARRAY_BUFFER, Some(self.element_array_buffer));
-                self.gl.buffer_data_u8_slice(
-                    glow::ELEMENT_ARRAY_BUFFER,
-                    bytemuck::cast_slice(&mesh.indices),
-                    glow::STREAM_DRAW,
-                );
+    /// The estimated GPU memory used by all textures known to the painter, in bytes.
+    ///
+    /// This is the size of each texture times the bytes per texel of its [`TextureFormat`].
+    /// It excludes mipmaps and any padding added by the driver,
+    /// and native textures registered without a size hint count as zero.
+    /// See [`Self::texture_memory_breakdown`] for the usage of each texture.
+    pub fn texture_memory_bytes(&self) -> usize {
+        self.textures
+            .all()
+            .iter()
+            .map(|(_, texture)| texture.memory_bytes())
+            .sum()
+    }
 
-                self.gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+    /// The estimated GPU memory used by each texture known to the painter, in bytes,
+    /// e.g. to find what is behind a growing [`Self::texture_memory_bytes`].
+    pub fn texture_memory_breakdown(&self) -> impl Iterator<Item = (egui::TextureId, usize)> + '_ {
+        self.textures
+            .all()
+            .into_iter()
+            .map(|(id, texture)| (id, texture.memory_bytes()))
+    }
+
+    /// Register a native `GL_TEXTURE_EXTERNAL_OES` texture, e.g. a camera or video frame on Android,
+    /// so that egui can paint it like any other image.
+    ///
+    /// These textures can only be sampled with a `samplerExternalOES`, so they are painted with a
+    /// variant of the egui shader, which is compiled the first time this is called.
+    /// Painting them costs a program switch.
+    ///
+    /// The texture keeps its own sampling parameters, so it can't be used with
+    /// [`Self::set_texture_options`] nor [`Self::set_sampler_options`].
+    ///
+    /// # Errors
+    /// If the context lacks `OES_EGL_image_external` (`OES_EGL_image_external_essl3` for GLSL ES 3.00),
+    /// or the shader variant fails to compile.
+    pub fn register_native_texture_external_oes(
+        &mut self,
+        native: glow::Texture,
+    ) -> Result<egui::TextureId, PainterError> {
+        self.assert_not_destroyed();
+
+        if self.external_oes_program.is_none() {
+            let extension = if self.shader_source.version.is_new_shader_interface() {
+                "OES_EGL_image_external_essl3"
+            } else {
+                "OES_EGL_image_external"
+            };
+            let has_extension = self.shader_source.version.is_embedded()
+                && self
+                    .gl
+                    .supported_extensions()
+                    .iter()
+                    .any(|supported| supported.ends_with(extension));
+            if !has_extension {
+                return Err(PainterError::Other(format!(
+                    "External textures are not supported by this context (missing {extension})"
+                )));
+            }
+
+            let program = unsafe {
+                ShaderProgram::new(&self.gl, &self.shader_source, ShaderVariant::ExternalOes)?
+            };
+            self.debug_label(
+                DebugObject::Program(program.program),
+                format_args!("egui external OES program"),
+            );
+            self.external_oes_program = Some(program);
+        }
+
+        let id = self.textures.next_native_tex_id();
+        self.textures.insert(
+            id,
+            PainterTexture::native(native, TEXTURE_EXTERNAL_OES, None),
+        );
+        Ok(id)
+    }
+
+    /// Create an empty texture of the given size, e.g. to render into with a framebuffer,
+    /// which egui can paint like any other image.
+    ///
+    /// The texture is owned by the painter and stored as [`TextureFormat::Rgba8`]; its texels start out undefined.
+    /// Get the [`glow::Texture`] to attach to your framebuffer with [`Self::texture`],
+    /// and free it with [`Self::free_texture`] when done with it.
+    /// With [`egui::TextureOptions::mipmap_mode`], generating the mipmaps after rendering into it is up to you.
+    ///
+    /// # Errors
+    /// If the size is larger than [`Self::max_texture_side`], or the texture can't be created.
+    pub fn allocate_texture(
+        &mut self,
+        size: [usize; 2],
+        options: egui::TextureOptions,
+    ) -> Result<egui::TextureId, PainterError> {
+        self.assert_not_destroyed();
+        self.check_texture_size(size)?;
+
+        let id = self.textures.next_native_tex_id();
+        self.allocate_empty_texture(id, size, options)?;
+        Ok(id)
+    }
+
+    /// Create an empty texture (see [`Self::allocate_texture`]) with a framebuffer to render into it.
+    ///
+    /// # Errors
+    /// If the texture can't be created, or the framebuffer is incomplete,
+    /// e.g. because the context can't render into the texture format.
+    pub fn create_render_target(
+        &mut self,
+        size: [usize; 2],
+        options: egui::TextureOptions,
+    ) -> Result<RenderTarget, PainterError> {
+        let texture_id = self.allocate_texture(size, options)?;
+        let texture = self
+            .textures
+            .get(&texture_id)
+            .map(|texture| texture.texture);
+
+        let framebuffer = unsafe {
+            let framebuffer = self.gl.create_framebuffer()?;
+            let previous = self.gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING);
+            self.gl
+                .bind_framebuffer(glow::FRAMEBUFFER, Some(framebuffer));
+            self.gl.framebuffer_texture_2d(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::TEXTURE_2D,
+                texture,
+                0,
+            );
+            let status = self.gl.check_framebuffer_status(glow::FRAMEBUFFER);
+            self.gl.bind_framebuffer(glow::FRAMEBUFFER, previous);
+            check_for_gl_error!(&self.gl, "create_render_target");
+
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                self.gl.delete_framebuffer(framebuffer);
+                self.free_texture(texture_id);
+                return Err(PainterError::Other(format!(
+                    "The framebuffer of a render target is incomplete (status 0x{status:X})"
+                )));
             }
+            framebuffer
+        };
+        self.debug_label(
+            DebugObject::Framebuffer(framebuffer),
+            format_args!("egui render target {texture_id:?}"),
+        );
+
+        Ok(RenderTarget {
+            texture_id,
+            framebuffer,
+            size,
+        })
+    }
+
+    /// Delete the framebuffer and texture of a [`RenderTarget`] made with [`Self::create_render_target`].
+    pub fn destroy_render_target(&mut self, render_target: RenderTarget) {
+        unsafe { self.gl.delete_framebuffer(render_target.framebuffer) };
+        self.free_texture(render_target.texture_id);
+    }
 
+    /// Create an empty RGBA8 texture for `tex_id`, and leave it bound.
+    fn allocate_empty_texture(
+        &mut self,
+        tex_id: egui::TextureId,
+        size: [usize; 2],
+        options: egui::TextureOptions,
+    ) -> Result<(), PainterError> {
+        let format = TextureFormat::Rgba8;
+        let gl_format = self.gl_texture_format(format)?;
+        let pos = self.bind_texture_for_upload(tex_id, None, size, options, format, gl_format)?;
+        self.set_texture_params(tex_id, options);
+        if pos.is_none() {
+            // Mutable storage, which we need to allocate ourselves:
             unsafe {
-                self.gl.draw_elements(
-                    glow::TRIANGLES,
-                    mesh.indices.len() as i32,
-                    glow::UNSIGNED_INT,
+                self.gl.tex_image_2d(
+                    glow::TEXTURE_2D,
+                    0,
+                    gl_format.internal_format as _,
+                    size[0] as _,
+                    size[1] as _,
                     0,
+                    gl_format.src_format,
+                    gl_format.pixel_type,
+                    glow::PixelUnpackData::Slice(None),
                 );
             }
-
-            check_for_gl_error!(&self.gl, "paint_mesh");
-        } else {
-            log::warn!("Failed to find texture {:?}", mesh.texture_id);
+            check_for_gl_error!(&self.gl, "tex_image_2d");
         }
+        Ok(())
     }
 
-    // ------------------------------------------------------------------------
-
-    pub fn set_texture(&mut self, tex_id: egui::TextureId, delta: &egui::epaint::ImageDelta) {
-        profiling::function_scope!();
-
+    /// Register a layer of a native `TEXTURE_2D_ARRAY` texture of the given size,
+    /// so that egui can paint it like any other image, e.g. a video frame or a sprite.
+    ///
+    /// egui only samples `TEXTURE_2D` textures, so the layer is copied into a new 2D texture owned by the painter.
+    /// This costs the memory of one extra layer, plus a GPU copy whenever the layer changes:
+    /// call [`Self::update_native_texture_array_layer`] after rendering new content into it.
+    /// A shader variant that samples the array directly would avoid the copy,
+    /// but would need a program switch for every mesh using it.
+    ///
+    /// The layer is copied through a framebuffer, so the array must be in a color-renderable format,
+    /// and is stored as [`TextureFormat::Rgba8`].
+    /// Free the copy with [`Self::free_texture`] when done with it.
+    ///
+    /// # Errors
+    /// Texture arrays require OpenGL 3.0 or OpenGL ES 3.0 / WebGL2.
+    pub fn register_native_texture_array_layer(
+        &mut self,
+        array: glow::Texture,
+        layer: u32,
+        size: [usize; 2],
+        options: egui::TextureOptions,
+    ) -> Result<egui::TextureId, PainterError> {
         self.assert_not_destroyed();
 
-        let glow_texture = *self
-            .textures
-            .entry(tex_id)
-            .or_insert_with(|| unsafe { self.gl.create_texture().unwrap() });
-        unsafe {
-            self.gl.bind_texture(glow::TEXTURE_2D, Some(glow_texture));
+        if self.is_webgl_1 || !is_gl_version_at_least(&self.gl, [3, 0], [3, 0]) {
+            return Err(PainterError::Other(
+                "Texture arrays require OpenGL 3.0 or OpenGL ES 3.0".to_owned(),
+            ));
         }
 
-        match &delta.image {
-            egui::ImageData::Color(image) => {
-                assert_eq!(
-                    image.width() * image.height(),
-                    image.pixels.len(),
-                    "Mismatch between texture size and texel count"
-                );
+        let id = self.textures.next_native_tex_id();
+        self.allocate_empty_texture(id, size, options)?;
+        self.array_layers
+            .insert(id, ArrayLayer { array, layer, size });
+        self.update_native_texture_array_layer(id)?;
+        Ok(id)
+    }
+
+    /// Copy the current content of a texture array layer
+    /// registered with [`Self::register_native_texture_array_layer`] into its egui texture.
+    ///
+    /// # Errors
+    /// If `tex_id` is not a registered array layer,
+    /// or the array is not in a color-renderable format.
+    pub fn update_native_texture_array_layer(
+        &mut self,
+        tex_id: egui::TextureId,
+    ) -> Result<(), PainterError> {
+        profiling::function_scope!();
+        self.assert_not_destroyed();
 
-                let data: &[u8] = bytemuck::cast_slice(image.pixels.as_ref());
+        let (Some(texture), Some(array_layer)) = (
+            self.textures.get(&tex_id),
+            self.array_layers.get(&tex_id).copied(),
+        ) else {
+            return Err(PainterError::Other(format!(
+                "{tex_id:?} is not a registered texture array layer"
+            )));
+        };
+        let ArrayLayer {
+            array,
+            layer,
+            size: [w, h],
+        } = array_layer;
 
-                self.upload_texture_srgb(delta.pos, image.size, delta.options, data);
+        let is_complete = unsafe {
+            let previous_fbo = self
+                .gl
+                .get_parameter_framebuffer(glow::READ_FRAMEBUFFER_BINDING);
+            let fbo = self.gl.create_framebuffer()?;
+            self.gl.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(fbo));
+            self.gl.framebuffer_texture_layer(
+                glow::READ_FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                Some(array),
+                0,
+                layer as i32,
+            );
+            let is_complete = self.gl.check_framebuffer_status(glow::READ_FRAMEBUFFER)
+                == glow::FRAMEBUFFER_COMPLETE;
+            if is_complete {
+                self.gl
+                    .bind_texture(glow::TEXTURE_2D, Some(texture.texture));
+                self.bound_texture = None;
+                self.gl
+                    .copy_tex_sub_image_2d(glow::TEXTURE_2D, 0, 0, 0, 0, 0, w as _, h as _);
+                if texture
+                    .options
+                    .is_some_and(|options| options.mipmap_mode.is_some())
+                {
+                    self.gl.generate_mipmap(glow::TEXTURE_2D);
+                }
             }
+            self.gl
+                .bind_framebuffer(glow::READ_FRAMEBUFFER, previous_fbo);
+            self.gl.delete_framebuffer(fbo);
+            is_complete
+        };
+        check_for_gl_error!(&self.gl, "update_native_texture_array_layer");
+
+        if is_complete {
+            Ok(())
+        } else {
+            Err(PainterError::Other(
+                "Failed to copy texture array layer: its format is not color-renderable".to_owned(),
+            ))
         }
     }
 
-    fn upload_texture_srgb(
-        &mut self,
-        pos: Option<[usize; 2]>,
-        [w, h]: [usize; 2],
-        options: egui::TextureOptions,
-        data: &[u8],
-    ) {
+    /// Read back the texels of a texture, e.g. to inspect the font atlas or export a generated image.
+    ///
+    /// Returns `None` if the texture is unknown, external (see [`Self::register_native_texture_external_oes`]),
+    /// or if its size is unknown (native textures registered without a size hint on OpenGL ES and WebGL).
+    /// On desktop OpenGL the whole texture is read, whatever size hint a native texture was registered with.
+    ///
+    /// On desktop OpenGL this uses `glGetTexImage`.
+    /// That is not available on OpenGL ES and WebGL, so there the texture is instead attached
+    /// to a temporary framebuffer and read with `glReadPixels`.
+    /// That requires the format of the texture to be color-renderable, which float textures often aren't.
+    pub fn read_texture_rgba(&self, tex_id: egui::TextureId) -> Option<egui::ColorImage> {
         profiling::function_scope!();
-        assert_eq!(
-            data.len(),
-            w * h * 4,
-            "Mismatch between texture size and texel count, by {}",
-            data.len() % (w * h * 4)
-        );
-        assert!(
-            w <= self.max_texture_side && h <= self.max_texture_side,
-            "Got a texture image of size {}x{}, but the maximum supported texture side is only {}",
-            w,
-            h,
-            self.max_texture_side
-        );
+        self.assert_not_destroyed();
 
-        unsafe {
-            self.gl.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_MAG_FILTER,
-                options.magnification.glow_code(None) as i32,
-            );
-            self.gl.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_MIN_FILTER,
-                options.minification.glow_code(options.mipmap_mode) as i32,
-            );
+        // Painting may be in the middle of a frame, with the texture of the next mesh still bound:
+        let previous = unsafe { self.gl.get_parameter_texture(glow::TEXTURE_BINDING_2D) };
+        let image = self.read_texture_rgba_impl(tex_id);
+        unsafe { self.gl.bind_texture(glow::TEXTURE_2D, previous) };
+        image
+    }
 
-            self.gl.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_WRAP_S,
-                options.wrap_mode.glow_code() as i32,
-            );
-            self.gl.tex_parameter_i32(
-                glow::TEXTURE_2D,
-                glow::TEXTURE_WRAP_T,
-                options.wrap_mode.glow_code() as i32,
-            );
-            check_for_gl_error!(&self.gl, "tex_parameter");
+    fn read_texture_rgba_impl(&self, tex_id: egui::TextureId) -> Option<egui::ColorImage> {
+        let texture = self
+            .textures
+            .get(&tex_id)
+            .filter(|texture| texture.target == glow::TEXTURE_2D)?;
+        let has_get_tex_image = !cfg!(target_arch = "wasm32") && !self.gl.version().is_embedded;
 
-            let (internal_format, src_format) = if self.is_webgl_1 {
-                let format = if self.srgb_textures {
-                    glow::SRGB_ALPHA
-                } else {
-                    glow::RGBA
-                };
-                (format, format)
-            } else if self.srgb_textures {
-                (glow::SRGB8_ALPHA8, glow::RGBA)
-            } else {
-                (glow::RGBA8, glow::RGBA)
-            };
+        unsafe {
+            self.gl
+                .bind_texture(glow::TEXTURE_2D, Some(texture.texture));
+        }
 
-            self.gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+        let [w, h] = if has_get_tex_image {
+            // `glGetTexImage` writes the whole texture, so this must be its real size,
+            // not the size hint a native texture was registered with:
+            unsafe {
+                [glow::TEXTURE_WIDTH, glow::TEXTURE_HEIGHT].map(|parameter| {
+                    self.gl
+                        .get_tex_level_parameter_i32(glow::TEXTURE_2D, 0, parameter)
+                        as usize
+                })
+            }
+        } else {
+            // `glReadPixels` writes no more than the `w` x `h` it is asked for:
+            texture.size?
+        };
 
-            let level = 0;
-            if let Some([x, y]) = pos {
-                profiling::scope!("gl.tex_sub_image_2d");
-                self.gl.tex_sub_image_2d(
+        let mut pixels = vec![0_u8; w.checked_mul(h)?.checked_mul(4)?];
+        unsafe {
+            if has_get_tex_image {
+                self.gl.get_tex_image(
                     glow::TEXTURE_2D,
-                    level,
-                    x as _,
-                    y as _,
-                    w as _,
-                    h as _,
-                    src_format,
+                    0,
+                    glow::RGBA,
                     glow::UNSIGNED_BYTE,
-                    glow::PixelUnpackData::Slice(Some(data)),
+                    glow::PixelPackData::Slice(Some(&mut pixels)),
                 );
-                check_for_gl_error!(&self.gl, "tex_sub_image_2d");
+                check_for_gl_error!(&self.gl, "get_tex_image");
             } else {
-                let border = 0;
-                profiling::scope!("gl.tex_image_2d");
-                self.gl.tex_image_2d(
+                let previous_fbo = self.gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING);
+                let fbo = self.gl.create_framebuffer().ok()?;
+                self.gl.bind_framebuffer(glow::FRAMEBUFFER, Some(fbo));
+                self.gl.framebuffer_texture_2d(
+                    glow::FRAMEBUFFER,
+                    glow::COLOR_ATTACHMENT0,
                     glow::TEXTURE_2D,
-                    level,
-                    internal_format as _,
-                    w as _,
-                    h as _,
-                    border,
-                    src_format,
-                    glow::UNSIGNED_BYTE,
-                    glow::PixelUnpackData::Slice(Some(data)),
+                    Some(texture.texture),
+                    0,
                 );
-                check_for_gl_error!(&self.gl, "tex_image_2d");
+                let is_complete = self.gl.check_framebuffer_status(glow::FRAMEBUFFER)
+                    == glow::FRAMEBUFFER_COMPLETE;
+                if is_complete {
+                    self.gl.read_pixels(
+                        0,
+                        0,
+                        w as _,
+                        h as _,
+                        glow::RGBA,
+                        glow::UNSIGNED_BYTE,
+                        glow::PixelPackData::Slice(Some(&mut pixels)),
+                    );
+                }
+                self.gl.bind_framebuffer(glow::FRAMEBUFFER, previous_fbo);
+                self.gl.delete_framebuffer(fbo);
+                check_for_gl_error!(&self.gl, "read_texture_rgba");
+                if !is_complete {
+                    log::warn!(
+                        "Can't read back {tex_id:?}: {:?} textures are not color-renderable on this context",
+                        texture.format
+                    );
+                    return None;
+                }
             }
+        }
 
-            if options.mipmap_mode.is_some() {
-                self.gl.generate_mipmap(glow::TEXTURE_2D);
-                check_for_gl_error!(&self.gl, "generate_mipmap");
+        if texture.format == TextureFormat::R8 {
+            // Undo the swizzle, which doesn't apply when reading back:
+            for texel in pixels.chunks_exact_mut(4) {
+                texel.fill(texel[0]);
             }
         }
+
+        let pixels = pixels
+            .chunks_exact(4)
+            .map(|texel| {
+                egui::Color32::from_rgba_premultiplied(texel[0], texel[1], texel[2], texel[3])
+            })
+            .collect();
+        Some(egui::ColorImage::new([w, h], pixels))
     }
 
-    pub fn free_texture(&mut self, tex_id: egui::TextureId) {
-        if let Some(old_tex) = self.textures.remove(&tex_id) {
-            unsafe { self.gl.delete_texture(old_tex) };
-        }
+    /// Read back a texture as straight-alpha RGBA8 bytes from the top left, with its size,
+    /// ready to save as an image file, e.g. to see what is actually in the font atlas.
+    ///
+    /// Returns `None` where [`Self::read_texture_rgba`] does, e.g. for unknown ids.
+    /// See [`Self::read_screen_rgba_unmultiplied`] for the precision of the unmultiplied colors.
+    pub fn debug_dump_texture(&self, tex_id: egui::TextureId) -> Option<(Vec<u8>, [usize; 2])> {
+        let mut image = self.read_texture_rgba(tex_id)?;
+        unmultiply_alpha(image.as_raw_mut());
+        Some((image.as_raw().to_vec(), image.size))
     }
 
-    /// Get the [`glow::Texture`] bound to a [`egui::TextureId`].
-    pub fn texture(&self, texture_id: egui::TextureId) -> Option<glow::Texture> {
-        self.textures.get(&texture_id).copied()
+    /// Read the pixels of the framebuffer egui was painted into, see [`Self::set_render_target`].
+    ///
+    /// When supersampling (see [`Self::set_supersample_factor`]), this is the downsampled result, at the screen size.
+    pub fn read_screen_rgba(&self, screen_size_px: [u32; 2]) -> egui::ColorImage {
+        profiling::function_scope!();
+        let [w, h] = screen_size_px;
+        self.read_rgba_image([0, 0, w as i32, h as i32])
     }
 
-    pub fn register_native_texture(&mut self, native: glow::Texture) -> egui::TextureId {
-        self.assert_not_destroyed();
-        let id = egui::TextureId::User(self.next_native_tex_id);
-        self.next_native_tex_id += 1;
-        self.textures.insert(id, native);
-        id
+    /// Like [`Self::read_screen_rgba`], but with straight (unmultiplied) alpha, as image files store it,
+    /// e.g. to save a screenshot of a transparent window as a PNG of [`egui::ColorImage::as_raw`].
+    ///
+    /// The [`egui::Color32`]s of the image hold the unmultiplied values, so don't paint it with egui.
+    /// Dividing by alpha loses precision where it is low, e.g. at anti-aliased edges,
+    /// and fully transparent pixels become transparent black.
+    pub fn read_screen_rgba_unmultiplied(&self, screen_size_px: [u32; 2]) -> egui::ColorImage {
+        let mut image = self.read_screen_rgba(screen_size_px);
+        unmultiply_alpha(image.as_raw_mut());
+        image
     }
 
-    pub fn replace_native_texture(&mut self, id: egui::TextureId, replacing: glow::Texture) {
-        if let Some(old_tex) = self.textures.insert(id, replacing) {
-            self.textures_to_destroy.push(old_tex);
-        }
+    /// Like [`Self::read_screen_rgba`], but into a buffer of yours, as rows of RGBA bytes from the top,
+    /// e.g. to capture a video without allocating every frame.
+    ///
+    /// The buffer is resized to fit, which only allocates if it is too small.
+    ///
+    /// # Panics
+    /// If the size is larger than any framebuffer, with more bytes than fit an `i32`.
+    pub fn read_screen_rgba_into(&self, [w, h]: [u32; 2], pixels: &mut Vec<u8>) {
+        profiling::function_scope!();
+        pixels.resize(read_pixels_len([w, h], 4), 0);
+        self.read_rgba([0, 0, w as i32, h as i32], pixels);
     }
 
-    pub fn read_screen_rgba(&self, [w, h]: [u32; 2]) -> egui::ColorImage {
+    /// Read a rectangle of the framebuffer egui was painted into, given in physical pixels
+    /// from the top left, like the rest of egui. See [`Self::read_screen_rgba`].
+    ///
+    /// The rectangle is rounded outwards to whole pixels, and clamped to the screen.
+    /// If nothing is left of it, the image is empty.
+    pub fn read_screen_rect(
+        &self,
+        screen_size_px: [u32; 2],
+        rect_in_pixels: Rect,
+    ) -> egui::ColorImage {
         profiling::function_scope!();
 
-        let mut pixels = vec![0_u8; (w * h * 4) as usize];
-        unsafe {
+        let [screen_width, screen_height] = screen_size_px.map(|side| side as f32);
+        let min_x = rect_in_pixels.min.x.floor().clamp(0.0, screen_width);
+        let min_y = rect_in_pixels.min.y.floor().clamp(0.0, screen_height);
+        let max_x = rect_in_pixels.max.x.ceil().clamp(0.0, screen_width);
+        let max_y = rect_in_pixels.max.y.ceil().clamp(0.0, screen_height);
+        if max_x <= min_x || max_y <= min_y {
+            return egui::ColorImage::new([0, 0], Vec::new());
+        }
+
+        // OpenGL counts rows from the bottom:
+        self.read_rgba_image([
+            min_x as i32,
+            (screen_height - max_y) as i32,
+            (max_x - min_x) as i32,
+            (max_y - min_y) as i32,
+        ])
+    }
+
+    /// Read the color of a single pixel of the framebuffer egui was painted into,
+    /// e.g. for an eyedropper. `x` and `y` are in physical pixels from the top left,
+    /// and are clamped to the screen. See [`Self::read_screen_rgba`].
+    ///
+    /// This waits for the GPU to finish painting, so don't call it every frame.
+    pub fn read_pixel(&self, [width, height]: [u32; 2], x: u32, y: u32) -> egui::Color32 {
+        if width == 0 || height == 0 {
+            return egui::Color32::TRANSPARENT;
+        }
+        let [x, y] = [x.min(width - 1), y.min(height - 1)];
+
+        let mut pixel = [0; 4];
+        // OpenGL counts rows from the bottom:
+        self.read_rgba([x as i32, (height - 1 - y) as i32, 1, 1], &mut pixel);
+        egui::Color32::from_rgba_premultiplied(pixel[0], pixel[1], pixel[2], pixel[3])
+    }
+
+    /// Like [`Self::read_rgba`], straight into the pixels of an image, without a copy in between.
+    fn read_rgba_image(&self, rect: [i32; 4]) -> egui::ColorImage {
+        let [_, _, w, h] = rect;
+        let size = [w, h].map(|side| u32::try_from(side).unwrap_or(0));
+        let mut pixels = vec![egui::Color32::TRANSPARENT; read_pixels_len(size, 4) / 4];
+        self.read_rgba(rect, bytemuck::cast_slice_mut(&mut pixels));
+        egui::ColorImage::new(size.map(|side| side as usize), pixels)
+    }
+
+    /// Read `[x, y, width, height]` of the framebuffer, counted from the bottom left as in OpenGL,
+    /// as rows of RGBA bytes from the top. `pixels` must fit them exactly.
+    ///
+    /// The rows are flipped in-place.
+    fn read_rgba(&self, [x, y, w, h]: [i32; 4], pixels: &mut [u8]) {
+        self.read_render_target(|| unsafe {
             self.gl.read_pixels(
-                0,
-                0,
-                w as _,
-                h as _,
+                x,
+                y,
+                w,
+                h,
                 glow::RGBA,
                 glow::UNSIGNED_BYTE,
-                glow::PixelPackData::Slice(Some(&mut pixels)),
+                glow::PixelPackData::Slice(Some(pixels)),
             );
+        });
+        flip_rows(pixels, (w * 4) as usize);
+    }
+
+    /// Read the depth buffer of the framebuffer egui was painted into, as rows of depths from the top,
+    /// e.g. to debug a 3D scene egui is painted over, or to pick in it.
+    ///
+    /// The depths are window coordinates, i.e. in the `0..=1` of `glDepthRange`.
+    ///
+    /// # Errors
+    /// If the framebuffer has no depth buffer,
+    /// or on OpenGL ES and WebGL, which can't read depth buffers.
+    pub fn read_depth(&self, [w, h]: [u32; 2]) -> Result<Vec<f32>, PainterError> {
+        profiling::function_scope!();
+
+        if self.gl.version().is_embedded {
+            return Err(PainterError::Other(
+                "Reading the depth buffer is not supported by OpenGL ES and WebGL".to_owned(),
+            ));
         }
-        let mut flipped = Vec::with_capacity((w * h * 4) as usize);
-        for row in pixels.chunks_exact((w * 4) as usize).rev() {
-            flipped.extend_from_slice(bytemuck::cast_slice(row));
-        }
-        egui::ColorImage::new([w as usize, h as usize], flipped)
+
+        let mut depths = vec![0.0_f32; (w * h) as usize];
+        self.read_render_target(|| {
+            if !self.has_buffer(glow::DEPTH) {
+                return Err(PainterError::Other(
+                    "The framebuffer has no depth buffer".to_owned(),
+                ));
+            }
+            unsafe {
+                self.gl.read_pixels(
+                    0,
+                    0,
+                    w as _,
+                    h as _,
+                    glow::DEPTH_COMPONENT,
+                    glow::FLOAT,
+                    glow::PixelPackData::Slice(Some(bytemuck::cast_slice_mut(&mut depths))),
+                );
+            }
+            check_for_gl_error!(&self.gl, "read_depth");
+            Ok(())
+        })?;
+        // OpenGL counts rows from the bottom:
+        flip_rows(
+            bytemuck::cast_slice_mut(&mut depths),
+            w as usize * std::mem::size_of::<f32>(),
+        );
+        Ok(depths)
     }
 
-    pub fn read_screen_rgb(&self, [w, h]: [u32; 2]) -> Vec<u8> {
+    pub fn read_screen_rgb(&self, screen_size_px: [u32; 2]) -> Vec<u8> {
+        let mut pixels = Vec::new();
+        self.read_screen_rgb_into(screen_size_px, &mut pixels);
+        pixels
+    }
+
+    /// Like [`Self::read_screen_rgb`], but into a buffer of yours,
+    /// which is resized to fit, and thus only allocates if it is too small.
+    ///
+    /// # Panics
+    /// If the size is larger than any framebuffer, with more bytes than fit an `i32`.
+    pub fn read_screen_rgb_into(&self, [w, h]: [u32; 2], pixels: &mut Vec<u8>) {
         profiling::function_scope!();
-        let mut pixels = vec![0_u8; (w * h * 3) as usize];
-        unsafe {
+
+        pixels.resize(read_pixels_len([w, h], 3), 0);
+        self.read_render_target(|| unsafe {
+            // The rows are tightly packed, rather than padded to the default alignment of 4 bytes:
+            let alignment = self.gl.get_parameter_i32(glow::PACK_ALIGNMENT);
+            self.gl.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
             self.gl.read_pixels(
                 0,
                 0,
@@ -689,24 +5641,142 @@ impl Painter {
                 h as _,
                 glow::RGB,
                 glow::UNSIGNED_BYTE,
-                glow::PixelPackData::Slice(Some(&mut pixels)),
+                glow::PixelPackData::Slice(Some(pixels)),
             );
+            self.gl.pixel_store_i32(glow::PACK_ALIGNMENT, alignment);
+        });
+    }
+
+    /// Start reading the framebuffer egui was painted into without waiting for the GPU,
+    /// e.g. to record a video. See [`Self::read_screen_rgba`].
+    ///
+    /// The pixels are copied into a pixel buffer object in the background.
+    /// Poll [`Self::try_finish_screen_read`] with the returned id, e.g. next frame, to get them.
+    ///
+    /// # Errors
+    /// Without OpenGL 3.2 or OpenGL ES 3.0 (WebGL2), which this needs for fences.
+    /// Use [`Self::read_screen_rgba`] instead there.
+    pub fn begin_screen_read(
+        &mut self,
+        screen_size_px: [u32; 2],
+    ) -> Result<ScreenReadId, PainterError> {
+        profiling::function_scope!();
+
+        if !self.supports_async_screen_read {
+            return Err(PainterError::Other(
+                "Asynchronous screen reads require OpenGL 3.2 or OpenGL ES 3.0".to_owned(),
+            ));
+        }
+
+        let read = self
+            .read_render_target(|| unsafe { PendingScreenRead::new(&self.gl, screen_size_px) })?;
+        let id = ScreenReadId(self.next_screen_read_id);
+        self.next_screen_read_id += 1;
+        self.screen_reads.insert(id, read);
+        Ok(id)
+    }
+
+    /// The pixels of a screen read started with [`Self::begin_screen_read`],
+    /// or `None` if they haven't arrived yet.
+    ///
+    /// Once this returns the image (or an error), the id is forgotten, and `None` is returned for it from then on.
+    ///
+    /// # Errors
+    /// If the pixel buffer the pixels arrived in can't be mapped to read them.
+    pub fn try_finish_screen_read(
+        &mut self,
+        id: ScreenReadId,
+    ) -> Result<Option<egui::ColorImage>, PainterError> {
+        let Some(read) = self.screen_reads.get(&id) else {
+            return Ok(None);
+        };
+        if !unsafe { read.is_ready(&self.gl) } {
+            return Ok(None);
+        }
+        let Some(read) = self.screen_reads.remove(&id) else {
+            return Ok(None);
+        };
+        unsafe { read.finish(&self.gl) }.map(Some)
+    }
+
+    /// Call `read` with the render target (see [`Self::set_render_target`]) bound, if any.
+    fn read_render_target<R>(&self, read: impl FnOnce() -> R) -> R {
+        if let Some(render_target) = self.render_target {
+            unsafe {
+                let previous = self.gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING);
+                self.gl
+                    .bind_framebuffer(glow::FRAMEBUFFER, Some(render_target));
+                let result = read();
+                self.gl.bind_framebuffer(glow::FRAMEBUFFER, previous);
+                result
+            }
+        } else {
+            read()
+        }
+    }
+
+    /// Delete the egui shader and its variants.
+    unsafe fn delete_programs(&self) {
+        unsafe {
+            if let MainProgram::Compiling(pending) = &self.program {
+                for shader in pending.shaders {
+                    self.gl.delete_shader(shader);
+                }
+            }
+            self.gl.delete_program(self.program.program());
+            for program in self
+                .external_oes_program
+                .iter()
+                .chain(&self.color_lut_program)
+            {
+                self.gl.delete_program(program.program);
+            }
         }
-        pixels
     }
 
-    unsafe fn destroy_gl(&self) {
+    unsafe fn destroy_gl(&mut self) {
+        // Let go of the shared textures, so the last painter to be destroyed sees that it is the last,
+        // even if the others are only dropped later:
+        let textures = std::mem::take(&mut self.textures);
         unsafe {
-            self.gl.delete_program(self.program);
+            self.delete_programs();
+            if textures.is_unshared() {
+                for (_, tex) in textures.all() {
+                    self.gl.delete_texture(tex.texture);
+                }
+            } else if let Some(tex) = self
+                .intermediate_texture_id
+                .and_then(|tex_id| textures.remove(&tex_id))
+            {
+                // The other painters keep using the rest:
+                self.gl.delete_texture(tex.texture);
+            }
+            self.gl.delete_buffer(self.vbo.buffer);
+            self.gl.delete_buffer(self.element_array_buffer.buffer);
+            if let Some(mesh_cache) = &self.mesh_cache {
+                mesh_cache.destroy(&self.gl);
+            }
             #[expect(clippy::iter_over_hash_type)]
-            for tex in self.textures.values() {
-                self.gl.delete_texture(*tex);
+            for read in self.screen_reads.values() {
+                read.destroy(&self.gl);
+            }
+            if let Some(framebuffer) = &self.intermediate_framebuffer {
+                framebuffer.destroy(&self.gl);
             }
-            self.gl.delete_buffer(self.vbo);
-            self.gl.delete_buffer(self.element_array_buffer);
-            for t in &self.textures_to_destroy {
+            if let Some(gpu_timer) = &self.gpu_timer {
+                gpu_timer.destroy(&self.gl);
+            }
+            for t in self
+                .textures_to_destroy
+                .iter()
+                .chain(&self.fallback_texture)
+            {
                 self.gl.delete_texture(*t);
             }
+            #[expect(clippy::iter_over_hash_type)]
+            for sampler in self.samplers.values() {
+                self.gl.delete_sampler(*sampler);
+            }
         }
     }
 
@@ -714,6 +5784,10 @@ impl Painter {
     /// that should be deleted.
     pub fn destroy(&mut self) {
         if !self.destroyed {
+            #[expect(clippy::iter_over_hash_type)]
+            for (_, mut renderer) in self.callback_renderers.drain() {
+                renderer.destroy(&self.gl);
+            }
             unsafe {
                 self.destroy_gl();
             }
@@ -721,12 +5795,161 @@ impl Painter {
         }
     }
 
+    /// Call [`Self::destroy`] when the painter is dropped, if it hasn't been already.
+    ///
+    /// # Safety
+    /// The OpenGL context must still be alive, and current on the dropping thread, whenever the
+    /// painter is dropped. Deleting objects of a context that is gone is undefined behavior,
+    /// which is why this is off by default and [`Self::destroy`] is usually called explicitly
+    /// (e.g. by eframe, while the context is known to be alive).
+    ///
+    /// This is safe in the common desktop case where the painter is dropped before the context.
+    pub unsafe fn set_destroy_on_drop(&mut self, destroy_on_drop: bool) {
+        self.destroy_on_drop = destroy_on_drop;
+    }
+
+    pub fn destroy_on_drop(&self) -> bool {
+        self.destroy_on_drop
+    }
+
+    /// Start over with a new OpenGL context after the old one was lost,
+    /// e.g. on the web when the tab was in the background or the GPU was reset.
+    ///
+    /// Everything the painter created in the old context is recreated in `gl`,
+    /// and the settings of the painter are kept. Nothing of the old context is deleted,
+    /// since that died with it.
+    ///
+    /// What the app created can't be recreated, so these are forgotten:
+    /// - The native textures (see [`Self::is_native_texture`]).
+    ///   Their ids are returned, so they can be registered again.
+    /// - The textures the painter made for the app, with [`Self::allocate_texture`], [`Self::create_render_target`]
+    ///   and [`Self::register_native_texture_array_layer`]. Their ids aren't returned, so make them again.
+    /// - The [`Self::set_render_target`] and [`Self::set_color_lut`].
+    /// - Whatever [`CallbackRenderer`]s created. They are kept registered, but have to recreate it themselves.
+    ///
+    /// The textures managed by egui are uploaded again if [`Self::set_retain_texture_data`] is on.
+    /// Otherwise they are gone too, and need to be set again, e.g. with [`Self::reupload_textures`].
+    /// Either way the painter stops sharing textures (see [`Self::shared_textures`]) and has textures of its own.
+    ///
+    /// # Errors
+    /// Same as [`Self::new`], and if the sources set with [`Self::reload_shaders`] fail to compile in `gl`.
+    /// In that case the painter is left as it was.
+    pub fn on_context_restored(
+        &mut self,
+        gl: Arc<glow::Context>,
+    ) -> Result<Vec<egui::TextureId>, PainterError> {
+        self.assert_not_destroyed();
+
+        let mut painter = Self::new_impl(
+            gl,
+            &self.shader_source.prefix,
+            Some(self.shader_source.version),
+            self.shader_source.postprocess.as_deref(),
+            self.dithering,
+        )?;
+        let ShaderSource { vert, frag, .. } = &self.shader_source;
+        if (vert.is_some() || frag.is_some())
+            && let Err(err) = painter.reload_shaders(vert.as_deref(), frag.as_deref())
+        {
+            // Otherwise it would leak its objects in the new context, and warn about it when dropped:
+            painter.destroy();
+            return Err(err);
+        }
+
+        painter.init_msaa(u8::try_from(self.msaa_samples).unwrap_or(u8::MAX));
+        painter.supersample_factor = self.supersample_factor;
+        painter.gamma = self.gamma;
+        painter.color_matrix = self.color_matrix;
+        painter.transform = self.transform;
+        painter.viewport = self.viewport;
+        if self.srgb_framebuffer {
+            painter.set_srgb_framebuffer(true);
+        }
+        painter.blend_mode = self.blend_mode;
+        painter.preserve_gl_state = self.preserve_gl_state;
+        painter.oversize_policy = self.oversize_policy;
+        painter.missing_texture_policy = self.missing_texture_policy;
+        painter.clip_rounding = self.clip_rounding;
+        painter.max_indices_per_draw = self.max_indices_per_draw;
+        if let Some(mesh_cache) = &self.mesh_cache {
+            painter.set_mesh_cache_budget(Some(mesh_cache.budget()));
+        }
+        painter.set_buffer_usage(self.vbo.usage());
+        painter.reserve_buffer_bytes(self.vbo.capacity(), self.element_array_buffer.capacity());
+        if self.debug_labels {
+            painter.set_debug_labels(true);
+        }
+        if self.gpu_timer.is_some() {
+            painter.set_gpu_timing(true);
+        }
+        painter.sampler_options = std::mem::take(&mut self.sampler_options);
+        painter.callback_renderers = std::mem::take(&mut self.callback_renderers);
+        // The textures died with the context, so even shared ones aren't shared anymore:
+        painter.textures.0.lock().next_native_tex_id = self.textures.0.lock().next_native_tex_id;
+        painter.next_screen_read_id = self.next_screen_read_id;
+        painter.destroy_on_drop = self.destroy_on_drop;
+        painter.retained_textures = self.retained_textures.take();
+
+        let mut lost_textures: Vec<egui::TextureId> = self
+            .textures
+            .all()
+            .into_iter()
+            .filter(|&(id, texture)| texture.is_native && Some(id) != self.intermediate_texture_id)
+            .map(|(id, _)| id)
+            .collect();
+        lost_textures.sort();
+
+        let mut lost = std::mem::replace(self, painter);
+        lost.destroy_on_drop = false;
+        lost.destroyed = true; // so dropping it doesn't touch the new context
+        drop(lost);
+
+        self.reupload_retained_textures();
+        Ok(lost_textures)
+    }
+
+    /// Has [`Self::destroy`] been called?
+    ///
+    /// Most methods panic after that, so check this first if a late paint request
+    /// can arrive during shutdown.
+    pub fn is_destroyed(&self) -> bool {
+        self.destroyed
+    }
+
     fn assert_not_destroyed(&self) {
         assert!(!self.destroyed, "the egui glow has already been destroyed!");
     }
 }
 
+/// Clear the whole framebuffer to `clear_color`, which is in gamma space without premultiplied alpha.
+///
+/// For a transparent window, clear to a transparent color (e.g. `[0.0; 4]`).
+/// With [`crate::BlendMode::Normal`], egui then leaves premultiplied colors with the correct alpha
+/// in the framebuffer, ready for the compositor to blend over whatever is behind the window.
+/// To keep the alpha that is already in the framebuffer instead, use [`clear_rgb`].
 pub fn clear(gl: &glow::Context, screen_size_in_pixels: [u32; 2], clear_color: [f32; 4]) {
+    clear_all(gl, screen_size_in_pixels, clear_color, None, None);
+}
+
+/// Like [`clear`], but only clears the color channels, and leaves the alpha of the framebuffer as it is,
+/// e.g. when the alpha was set up for compositing by someone else.
+pub fn clear_rgb(gl: &glow::Context, screen_size_in_pixels: [u32; 2], [r, g, b]: [f32; 3]) {
+    unsafe { gl.color_mask(true, true, true, false) };
+    clear_all(gl, screen_size_in_pixels, [r, g, b, 0.0], None, None);
+    unsafe { gl.color_mask(true, true, true, true) };
+}
+
+/// Like [`clear`], but also clears the depth and stencil buffers to the given values, if any,
+/// e.g. when a framebuffer is shared with a 3D scene.
+///
+/// The depth and stencil write masks are turned on to clear them.
+pub fn clear_all(
+    gl: &glow::Context,
+    screen_size_in_pixels: [u32; 2],
+    clear_color: [f32; 4],
+    depth: Option<f32>,
+    stencil: Option<i32>,
+) {
     profiling::function_scope!();
     unsafe {
         gl.disable(glow::SCISSOR_TEST);
@@ -743,13 +5966,26 @@ pub fn clear(gl: &glow::Context, screen_size_in_pixels: [u32; 2], clear_color: [
             clear_color[2],
             clear_color[3],
         );
-        gl.clear(glow::COLOR_BUFFER_BIT);
+        let mut mask = glow::COLOR_BUFFER_BIT;
+        if let Some(depth) = depth {
+            gl.depth_mask(true);
+            gl.clear_depth_f32(depth);
+            mask |= glow::DEPTH_BUFFER_BIT;
+        }
+        if let Some(stencil) = stencil {
+            gl.stencil_mask(0xFF);
+            gl.clear_stencil(stencil);
+            mask |= glow::STENCIL_BUFFER_BIT;
+        }
+        gl.clear(mask);
     }
 }
 
 impl Drop for Painter {
     fn drop(&mut self) {
-        if !self.destroyed {
+        if self.destroy_on_drop {
+            self.destroy();
+        } else if !self.destroyed {
             log::warn!(
                 "You forgot to call destroy() on the egui glow painter. Resources will leak!"
             );
@@ -757,23 +5993,134 @@ impl Drop for Painter {
     }
 }
 
-fn set_clip_rect(
+/// The number of bytes `glReadPixels` writes for `[w, h]` pixels of `bytes_per_pixel`, tightly packed.
+///
+/// # Panics
+/// If that is more than `glReadPixels` can be asked for, which no framebuffer is large enough for.
+fn read_pixels_len([w, h]: [u32; 2], bytes_per_pixel: usize) -> usize {
+    let len = (w as usize)
+        .checked_mul(h as usize)
+        .and_then(|len| len.checked_mul(bytes_per_pixel))
+        .filter(|&len| i32::try_from(len).is_ok());
+    let Some(len) = len else {
+        panic!("Can't read {w}x{h} pixels: that is larger than any framebuffer");
+    };
+    len
+}
+
+/// Is the context at least OpenGL `major.minor`, or OpenGL ES `es_major.es_minor`?
+///
+/// WebGL1 counts as OpenGL ES 2.0 and WebGL2 as OpenGL ES 3.0.
+fn is_gl_version_at_least(
     gl: &glow::Context,
+    [major, minor]: [u32; 2],
+    [es_major, es_minor]: [u32; 2],
+) -> bool {
+    let version = gl.version();
+    if version.is_embedded {
+        [version.major, version.minor] >= [es_major, es_minor]
+    } else {
+        [version.major, version.minor] >= [major, minor]
+    }
+}
+
+/// Transpose a matrix given as rows into the columns OpenGL wants.
+fn column_major(rows: &[[f32; 4]; 4]) -> [[f32; 4]; 4] {
+    std::array::from_fn(|column| std::array::from_fn(|row| rows[row][column]))
+}
+
+/// The `(first, count)` of the indices of each draw of a mesh with `index_count` indices,
+/// with at most `max_indices_per_draw` (rounded down to whole triangles) in each.
+fn draw_ranges(
+    index_count: usize,
+    max_indices_per_draw: Option<usize>,
+) -> impl Iterator<Item = (usize, usize)> {
+    let max_count = max_indices_per_draw.map_or(index_count, |max| (max - max % 3).max(3));
+    (0..index_count)
+        .step_by(max_count.max(1))
+        .map(move |first| (first, max_count.min(index_count - first)))
+}
+
+/// The order to apply texture deltas with these ids in, so the deltas of each texture come together,
+/// in the order they were given. The textures come in the order they first appear.
+fn group_by_texture(ids: impl Iterator<Item = egui::TextureId>) -> Vec<usize> {
+    let mut first_index = HashMap::new();
+    let mut order: Vec<(usize, usize)> = ids
+        .enumerate()
+        .map(|(index, id)| (*first_index.entry(id).or_insert(index), index))
+        .collect();
+    order.sort_unstable();
+    order.into_iter().map(|(_, index)| index).collect()
+}
+
+/// The smallest rectangle containing all of `rects`, or [`Rect::NOTHING`] if there are none.
+fn union_rect(rects: &[Rect]) -> Rect {
+    rects
+        .iter()
+        .fold(Rect::NOTHING, |union, rect| union.union(*rect))
+}
+
+/// The columns OpenGL wants for a `mat3` of the affine `transform`.
+fn transform_columns(transform: &[[f32; 3]; 2]) -> [[f32; 3]; 3] {
+    let [[a, b, c], [d, e, f]] = *transform;
+    [[a, d, 0.0], [b, e, 0.0], [c, f, 1.0]]
+}
+
+/// The bounding box of a rectangle transformed by the affine `transform`.
+///
+/// Works for infinite rectangles like [`Rect::EVERYTHING`] too.
+fn transform_rect(transform: &[[f32; 3]; 2], rect: Rect) -> Rect {
+    if *transform == IDENTITY_TRANSFORM {
+        return rect;
+    }
+
+    // The range of `factor * value` for `value` in `min..=max`, where `0 * infinity` is `0`:
+    let scaled = |factor: f32, min: f32, max: f32| {
+        if factor == 0.0 {
+            [0.0, 0.0]
+        } else {
+            let (a, b) = (factor * min, factor * max);
+            [a.min(b), a.max(b)]
+        }
+    };
+    let [x_range, y_range] = transform.map(|[x_factor, y_factor, offset]| {
+        let [x_min, x_max] = scaled(x_factor, rect.min.x, rect.max.x);
+        let [y_min, y_max] = scaled(y_factor, rect.min.y, rect.max.y);
+        [x_min + y_min + offset, x_max + y_max + offset]
+    });
+    Rect::from_min_max(
+        egui::pos2(x_range[0], y_range[0]),
+        egui::pos2(x_range[1], y_range[1]),
+    )
+}
+
+/// Offset a viewport relative to the region egui is painted into, so it is relative to the framebuffer.
+fn offset_viewport(viewport: &ViewportInPixels, region: &ViewportInPixels) -> ViewportInPixels {
+    ViewportInPixels {
+        left_px: viewport.left_px + region.left_px,
+        top_px: viewport.top_px + region.top_px,
+        from_bottom_px: viewport.from_bottom_px + region.from_bottom_px,
+        width_px: viewport.width_px,
+        height_px: viewport.height_px,
+    }
+}
+
+/// The scissor rectangle for a clip rectangle, as `[x, y, width, height]` in physical pixels.
+fn scissor_rect(
     [width_px, height_px]: [u32; 2],
     pixels_per_point: f32,
     clip_rect: Rect,
-) {
+    rounding: ClipRounding,
+) -> [i32; 4] {
     // Transform clip rect to physical pixels:
     let clip_min_x = pixels_per_point * clip_rect.min.x;
     let clip_min_y = pixels_per_point * clip_rect.min.y;
     let clip_max_x = pixels_per_point * clip_rect.max.x;
     let clip_max_y = pixels_per_point * clip_rect.max.y;
 
-    // Round to integer:
-    let clip_min_x = clip_min_x.round() as i32;
-    let clip_min_y = clip_min_y.round() as i32;
-    let clip_max_x = clip_max_x.round() as i32;
-    let clip_max_y = clip_max_y.round() as i32;
+    // Snap to integer:
+    let [clip_min_x, clip_max_x] = rounding.snap(clip_min_x, clip_max_x);
+    let [clip_min_y, clip_max_y] = rounding.snap(clip_min_y, clip_max_y);
 
     // Clamp:
     let clip_min_x = clip_min_x.clamp(0, width_px as i32);
@@ -781,12 +6128,144 @@ fn set_clip_rect(
     let clip_max_x = clip_max_x.clamp(clip_min_x, width_px as i32);
     let clip_max_y = clip_max_y.clamp(clip_min_y, height_px as i32);
 
-    unsafe {
-        gl.scissor(
-            clip_min_x,
-            height_px as i32 - clip_max_y,
-            clip_max_x - clip_min_x,
-            clip_max_y - clip_min_y,
-        );
-    }
+    [
+        clip_min_x,
+        height_px as i32 - clip_max_y,
+        clip_max_x - clip_min_x,
+        clip_max_y - clip_min_y,
+    ]
+}
+
+#[test]
+fn test_scissor_rect() {
+    let screen_size_px = [800, 600];
+    let rect = |min: [f32; 2], max: [f32; 2]| Rect::from_min_max(min.into(), max.into());
+
+    let round = ClipRounding::Round;
+
+    // The y axis is flipped:
+    assert_eq!(
+        scissor_rect(
+            screen_size_px,
+            2.0,
+            rect([10.0, 20.0], [110.0, 70.0]),
+            round
+        ),
+        [20, 460, 200, 100]
+    );
+
+    // Clipped away entirely, so empty:
+    let [_, _, width, height] = scissor_rect(
+        screen_size_px,
+        1.0,
+        rect([900.0, 20.0], [1000.0, 70.0]),
+        round,
+    );
+    assert_eq!(width, 0);
+    assert!(0 < height);
+    let [_, _, width, height] =
+        scissor_rect(screen_size_px, 1.0, rect([10.0, 20.0], [5.0, 70.0]), round);
+    assert_eq!(width, 0);
+    assert!(0 < height);
+
+    // Between pixels:
+    let between = rect([10.4, 20.6], [20.4, 30.6]);
+    assert_eq!(
+        scissor_rect(screen_size_px, 1.0, between, round),
+        [10, 569, 10, 10]
+    );
+    assert_eq!(
+        scissor_rect(screen_size_px, 1.0, between, ClipRounding::Expand),
+        [10, 569, 11, 11]
+    );
+    assert_eq!(
+        scissor_rect(screen_size_px, 1.0, between, ClipRounding::Nearest),
+        [10, 569, 10, 10]
+    );
+
+    // Rounding each edge changes the size, but `Nearest` keeps it:
+    let between = rect([10.4, 20.0], [20.6, 30.0]);
+    assert_eq!(scissor_rect(screen_size_px, 1.0, between, round)[2], 11);
+    assert_eq!(
+        scissor_rect(screen_size_px, 1.0, between, ClipRounding::Nearest)[2],
+        10
+    );
+}
+
+#[test]
+fn test_transform_rect() {
+    let rect = Rect::from_min_max(egui::pos2(10.0, 20.0), egui::pos2(30.0, 60.0));
+    assert_eq!(transform_rect(&IDENTITY_TRANSFORM, rect), rect);
+
+    // Zoom in around the origin, and pan:
+    let zoom_and_pan = [[2.0, 0.0, 5.0], [0.0, 2.0, -5.0]];
+    assert_eq!(
+        transform_rect(&zoom_and_pan, rect),
+        Rect::from_min_max(egui::pos2(25.0, 35.0), egui::pos2(65.0, 115.0))
+    );
+
+    // Rotate by 90 degrees, so the axes swap:
+    let rotate = [[0.0, -1.0, 0.0], [1.0, 0.0, 0.0]];
+    assert_eq!(
+        transform_rect(&rotate, rect),
+        Rect::from_min_max(egui::pos2(-60.0, 10.0), egui::pos2(-20.0, 30.0))
+    );
+
+    // Clipping nothing keeps clipping nothing:
+    assert_eq!(
+        transform_rect(&zoom_and_pan, Rect::EVERYTHING),
+        Rect::EVERYTHING
+    );
+}
+
+#[test]
+fn test_union_rect() {
+    let rect = |min: [f32; 2], max: [f32; 2]| Rect::from_min_max(min.into(), max.into());
+    assert!(!union_rect(&[]).is_positive());
+    assert_eq!(
+        union_rect(&[
+            rect([10.0, 20.0], [30.0, 40.0]),
+            rect([50.0, 0.0], [60.0, 10.0])
+        ]),
+        rect([10.0, 0.0], [60.0, 40.0])
+    );
+}
+
+#[test]
+fn test_draw_ranges() {
+    let ranges = |index_count, max| draw_ranges(index_count, max).collect::<Vec<_>>();
+    assert_eq!(ranges(0, None), vec![]);
+    assert_eq!(ranges(9, None), vec![(0, 9)]);
+    assert_eq!(ranges(9, Some(9)), vec![(0, 9)]);
+    assert_eq!(ranges(9, Some(6)), vec![(0, 6), (6, 3)]);
+    // Never splits a triangle:
+    assert_eq!(ranges(12, Some(8)), vec![(0, 6), (6, 6)]);
+    assert_eq!(ranges(6, Some(1)), vec![(0, 3), (3, 3)]);
+}
+
+#[test]
+fn test_group_by_texture() {
+    use egui::TextureId::{Managed, User};
+    let ids = [Managed(0), User(1), Managed(0), Managed(2), User(1)];
+    assert_eq!(group_by_texture(ids.into_iter()), vec![0, 2, 1, 4, 3]);
+    assert_eq!(group_by_texture(std::iter::empty()), Vec::<usize>::new());
+}
+
+#[test]
+fn test_shader_compile_error_display() {
+    let err = PainterError::ShaderCompile {
+        stage: ShaderStage::Fragment,
+        source: "#version 140\nvoid main() {\n    oops\n}".to_owned(),
+        log: "0:3(5): error: syntax error\n".to_owned(),
+    };
+    assert_eq!(
+        err.to_string(),
+        "OpenGL: Failed to compile the fragment shader: 0:3(5): error: syntax error\n\
+         \n\
+         Source:\n\
+         \x20  1: #version 140\n\
+         \x20  2: void main() {\n\
+         \x20  3:     oops\n\
+         \x20  4: }"
+    );
 }
@@ -0,0 +1,279 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{BlendMode, Painter, PainterError, ShaderVersion, painter::SharedTextures};
+
+// ----------------------------------------------------------------------------
+
+/// Creates a [`Painter`] with the given options.
+///
+/// ``` no_run
+/// # fn create(gl: std::sync::Arc<egui_glow::glow::Context>) -> Result<(), egui_glow::PainterError> {
+/// let painter = egui_glow::PainterBuilder::new()
+///     .dithering(true)
+///     .msaa_samples(4)
+///     .build(gl)?;
+/// # Ok(()) }
+/// ```
+#[must_use = "You should call .build()"]
+#[derive(Clone, Debug)]
+pub struct PainterBuilder {
+    shader_prefix: String,
+    shader_defines: HashMap<String, String>,
+    shader_version: Option<ShaderVersion>,
+    fragment_postprocess: Option<String>,
+    dithering: bool,
+    msaa_samples: u8,
+    blend_mode: BlendMode,
+    srgb_framebuffer: bool,
+    debug_labels: bool,
+    shared_textures: Option<SharedTextures>,
+    reserve_buffers: [usize; 2],
+}
+
+impl Default for PainterBuilder {
+    fn default() -> Self {
+        Self {
+            shader_prefix: String::new(),
+            shader_defines: HashMap::new(),
+            shader_version: None,
+            fragment_postprocess: None,
+            dithering: true,
+            msaa_samples: 0,
+            blend_mode: BlendMode::default(),
+            srgb_framebuffer: false,
+            debug_labels: false,
+            shared_textures: None,
+            reserve_buffers: [0, 0],
+        }
+    }
+}
+
+impl PainterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Prepended to the egui shaders, e.g. `"#define APPLY_BRIGHTENING_GAMMA\n"`.
+    ///
+    /// Default: empty.
+    #[inline]
+    pub fn shader_prefix(mut self, shader_prefix: impl Into<String>) -> Self {
+        self.shader_prefix = shader_prefix.into();
+        self
+    }
+
+    /// `#define NAME VALUE` lines for the egui shaders, prepended to [`Self::shader_prefix`],
+    /// e.g. `APPLY_BRIGHTENING_GAMMA` with an empty value.
+    ///
+    /// The names must be valid GLSL identifiers, not reserved by GLSL (starting with `GL_` or containing `__`),
+    /// and the values must be on one line, or [`Self::build`] fails.
+    ///
+    /// Default: empty.
+    #[inline]
+    pub fn shader_defines(mut self, shader_defines: HashMap<String, String>) -> Self {
+        self.shader_defines = shader_defines;
+        self
+    }
+
+    /// The version to compile the egui shaders with.
+    ///
+    /// Default: `None`, which detects it from the context.
+    #[inline]
+    pub fn shader_version(mut self, shader_version: Option<ShaderVersion>) -> Self {
+        self.shader_version = shader_version;
+        self
+    }
+
+    /// GLSL defining `vec4 user_postprocess(vec4 color, vec2 uv)`, which the fragment shader calls on every color
+    /// it paints, e.g. to gray out disabled widgets or add scanlines.
+    ///
+    /// `color` has premultiplied alpha and is in gamma space, after [`Painter::set_color_matrix`]
+    /// and before dithering, and `uv` is the texture coordinate of the mesh.
+    /// `gl_FragCoord` and the uniforms of the egui shader can be used too.
+    /// It's compiled into the shader for every [`crate::ShaderVersion`], so stick to GLSL that works with all of them.
+    ///
+    /// ``` no_run
+    /// # fn create(gl: std::sync::Arc<egui_glow::glow::Context>) -> Result<(), egui_glow::PainterError> {
+    /// let painter = egui_glow::PainterBuilder::new()
+    ///     .fragment_postprocess(
+    ///         "vec4 user_postprocess(vec4 color, vec2 uv) {
+    ///             float gray = dot(color.rgb, vec3(0.299, 0.587, 0.114));
+    ///             return vec4(vec3(gray), color.a);
+    ///         }",
+    ///     )
+    ///     .build(gl)?;
+    /// # Ok(()) }
+    /// ```
+    ///
+    /// Default: `None`, which leaves the colors as they are.
+    #[inline]
+    pub fn fragment_postprocess(mut self, snippet: impl Into<String>) -> Self {
+        self.fragment_postprocess = Some(snippet.into());
+        self
+    }
+
+    /// See [`Painter::set_dithering`].
+    ///
+    /// Default: `true`.
+    #[inline]
+    pub fn dithering(mut self, dithering: bool) -> Self {
+        self.dithering = dithering;
+        self
+    }
+
+    /// See [`Painter::new_with_msaa`].
+    ///
+    /// Default: `0` (no multisampling).
+    #[inline]
+    pub fn msaa_samples(mut self, msaa_samples: u8) -> Self {
+        self.msaa_samples = msaa_samples;
+        self
+    }
+
+    /// See [`Painter::set_blend_mode`].
+    ///
+    /// Default: [`BlendMode::default`].
+    #[inline]
+    pub fn blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    /// See [`Painter::set_srgb_framebuffer`].
+    ///
+    /// Default: `false`.
+    #[inline]
+    pub fn srgb_framebuffer(mut self, srgb_framebuffer: bool) -> Self {
+        self.srgb_framebuffer = srgb_framebuffer;
+        self
+    }
+
+    /// See [`Painter::set_debug_labels`].
+    ///
+    /// Default: `false`.
+    #[inline]
+    pub fn debug_labels(mut self, debug_labels: bool) -> Self {
+        self.debug_labels = debug_labels;
+        self
+    }
+
+    /// Share the textures of another painter, see [`Painter::shared_textures`].
+    ///
+    /// Only for painters whose OpenGL contexts share objects!
+    ///
+    /// Default: `None` (the painter has textures of its own).
+    #[inline]
+    pub fn shared_textures(mut self, shared_textures: SharedTextures) -> Self {
+        self.shared_textures = Some(shared_textures);
+        self
+    }
+
+    /// See [`Painter::reserve_buffers`].
+    ///
+    /// Default: `0, 0` (the buffers grow as needed).
+    #[inline]
+    pub fn reserve_buffers(mut self, vertices: usize, indices: usize) -> Self {
+        self.reserve_buffers = [vertices, indices];
+        self
+    }
+
+    /// Create the painter.
+    ///
+    /// # Errors
+    /// Same as [`Painter::new`], and if [`Self::shader_defines`] are invalid.
+    pub fn build(self, gl: Arc<glow::Context>) -> Result<Painter, PainterError> {
+        let Self {
+            shader_prefix,
+            shader_defines,
+            shader_version,
+            fragment_postprocess,
+            dithering,
+            msaa_samples,
+            blend_mode,
+            srgb_framebuffer,
+            debug_labels,
+            shared_textures,
+            reserve_buffers: [vertices, indices],
+        } = self;
+
+        let shader_prefix = format!("{}{shader_prefix}", define_lines(&shader_defines)?);
+        let mut painter = Painter::new_impl(
+            gl,
+            &shader_prefix,
+            shader_version,
+            fragment_postprocess.as_deref(),
+            dithering,
+        )?;
+        if let Some(shared_textures) = shared_textures {
+            painter.use_shared_textures(shared_textures);
+        }
+        painter.init_msaa(msaa_samples);
+        painter.set_blend_mode(blend_mode);
+        if srgb_framebuffer {
+            painter.set_srgb_framebuffer(true);
+        }
+        if debug_labels {
+            painter.set_debug_labels(true);
+        }
+        painter.reserve_buffers(vertices, indices);
+        Ok(painter)
+    }
+}
+
+/// The `#define` lines of [`PainterBuilder::shader_defines`], sorted by name so the source doesn't vary.
+fn define_lines(defines: &HashMap<String, String>) -> Result<String, PainterError> {
+    let mut defines: Vec<_> = defines.iter().collect();
+    defines.sort();
+
+    for &(name, value) in &defines {
+        let mut chars = name.chars();
+        let is_identifier = chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !is_identifier || name.starts_with("GL_") || name.contains("__") {
+            return Err(PainterError::Other(format!(
+                "Invalid shader define name {name:?}: expected a GLSL identifier not reserved by GLSL"
+            )));
+        }
+        if value.contains(['\n', '\r']) {
+            return Err(PainterError::Other(format!(
+                "Invalid value of shader define {name}: {value:?} is not on one line"
+            )));
+        }
+    }
+    Ok(defines
+        .into_iter()
+        .map(|(name, value)| format!("#define {name} {value}\n"))
+        .collect())
+}
+
+#[test]
+fn test_define_lines() {
+    let defines = |pairs: &[(&str, &str)]| {
+        let defines = pairs
+            .iter()
+            .map(|&(name, value)| (name.to_owned(), value.to_owned()))
+            .collect();
+        define_lines(&defines)
+    };
+
+    assert_eq!(
+        defines(&[
+            ("SCANLINES", "1"),
+            ("_GAMMA_2", "2.2"),
+            ("APPLY_BRIGHTENING_GAMMA", "")
+        ])
+        .unwrap(),
+        "#define APPLY_BRIGHTENING_GAMMA \n#define SCANLINES 1\n#define _GAMMA_2 2.2\n"
+    );
+    assert!(defines(&[]).unwrap().is_empty());
+
+    for name in ["", "2D", "MY-DEFINE", "GL_FOO", "MY__DEFINE", "ÄPFEL"] {
+        assert!(
+            defines(&[(name, "1")]).is_err(),
+            "{name:?} should be invalid"
+        );
+    }
+    assert!(defines(&[("MULTILINE", "1\n#define OTHER 2")]).is_err());
+}
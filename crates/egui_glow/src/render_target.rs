@@ -0,0 +1,46 @@
+#![expect(unsafe_code)]
+
+use glow::HasContext as _;
+
+// ----------------------------------------------------------------------------
+
+/// A texture that egui can paint as an image, with a framebuffer to render into it,
+/// e.g. for a minimap or picture-in-picture widget.
+///
+/// Create it with [`crate::Painter::create_render_target`],
+/// and delete it with [`crate::Painter::destroy_render_target`].
+///
+/// The framebuffer only has the color attachment. Attach a depth buffer yourself if you need one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderTarget {
+    /// Show the texture with e.g. [`egui::Image`].
+    pub texture_id: egui::TextureId,
+
+    /// The texture is attached to this as `COLOR_ATTACHMENT0`.
+    pub framebuffer: glow::Framebuffer,
+
+    /// The size of the texture, in pixels.
+    pub size: [usize; 2],
+}
+
+impl RenderTarget {
+    /// Bind [`Self::framebuffer`], and set the viewport to the whole texture.
+    pub fn bind(&self, gl: &glow::Context) {
+        unsafe {
+            gl.bind_framebuffer(glow::FRAMEBUFFER, Some(self.framebuffer));
+            gl.viewport(0, 0, self.size[0] as i32, self.size[1] as i32);
+        }
+    }
+
+    /// Bind the default framebuffer again, if [`Self::framebuffer`] is bound.
+    ///
+    /// In a paint callback, use [`crate::CallbackTarget::restore`] instead,
+    /// which also works when egui isn't painting into the default framebuffer.
+    pub fn unbind(&self, gl: &glow::Context) {
+        unsafe {
+            if gl.get_parameter_framebuffer(glow::FRAMEBUFFER_BINDING) == Some(self.framebuffer) {
+                gl.bind_framebuffer(glow::FRAMEBUFFER, None);
+            }
+        }
+    }
+}
@@ -0,0 +1,157 @@
+/// OpenGL-specific options for how a texture is sampled,
+/// on top of the [`egui::TextureOptions`] it was uploaded with.
+///
+/// Set them for a texture with [`crate::Painter::set_sampler_options`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SamplerOptions {
+    /// How to wrap the texture along the horizontal (S) axis.
+    ///
+    /// `None` means [`egui::TextureOptions::wrap_mode`] is used.
+    pub wrap_mode_s: Option<WrapMode>,
+
+    /// How to wrap the texture along the vertical (T) axis.
+    ///
+    /// `None` means [`egui::TextureOptions::wrap_mode`] is used.
+    pub wrap_mode_t: Option<WrapMode>,
+
+    /// The color sampled outside the texture along the axes that use [`WrapMode::ClampToBorder`].
+    ///
+    /// Premultiplied, like the texels of the texture.
+    ///
+    /// Default: [`egui::Color32::TRANSPARENT`].
+    pub border_color: egui::Color32,
+
+    /// The maximum degree of anisotropic filtering, e.g. `16`.
+    ///
+    /// Makes textures seen at oblique angles (e.g. in a 3D scene) sharper.
+    /// Best combined with mipmapping, see [`egui::TextureOptions::mipmap_mode`].
+    ///
+    /// Clamped to [`crate::Painter::max_anisotropy`],
+    /// and ignored on contexts without `EXT_texture_filter_anisotropic`.
+    ///
+    /// Default: `None` (no anisotropic filtering).
+    pub anisotropy: Option<u8>,
+
+    /// The first mipmap level that is sampled (`GL_TEXTURE_BASE_LEVEL`).
+    ///
+    /// Set together with [`Self::mipmap_max_level`] to limit the mipmap chain,
+    /// e.g. to use only a couple of mip levels for an atlas.
+    /// Only levels from the base to the max level are generated on upload.
+    ///
+    /// Ignored on WebGL1 and OpenGL ES 2.0.
+    ///
+    /// Default: `None` (level 0).
+    pub mipmap_base_level: Option<u8>,
+
+    /// The last mipmap level that is sampled and generated (`GL_TEXTURE_MAX_LEVEL`).
+    ///
+    /// Ignored on WebGL1 and OpenGL ES 2.0.
+    ///
+    /// Default: `None` (as many levels as the texture size allows).
+    pub mipmap_max_level: Option<u8>,
+
+    /// Bias added to the mipmap level of detail before sampling (`GL_TEXTURE_LOD_BIAS`).
+    ///
+    /// Negative values give sharper (but more aliased) textures, positive values blurrier ones.
+    ///
+    /// Only supported on desktop OpenGL, and ignored elsewhere.
+    ///
+    /// Default: `None` (no bias).
+    pub lod_bias: Option<f32>,
+}
+
+// Compares `lod_bias` like it is hashed, so that a NaN bias still finds its sampler object in the cache.
+impl PartialEq for SamplerOptions {
+    fn eq(&self, other: &Self) -> bool {
+        let Self {
+            wrap_mode_s,
+            wrap_mode_t,
+            border_color,
+            anisotropy,
+            mipmap_base_level,
+            mipmap_max_level,
+            lod_bias,
+        } = *self;
+        wrap_mode_s == other.wrap_mode_s
+            && wrap_mode_t == other.wrap_mode_t
+            && border_color == other.border_color
+            && anisotropy == other.anisotropy
+            && mipmap_base_level == other.mipmap_base_level
+            && mipmap_max_level == other.mipmap_max_level
+            && lod_bias.map(egui::emath::OrderedFloat)
+                == other.lod_bias.map(egui::emath::OrderedFloat)
+    }
+}
+
+impl Eq for SamplerOptions {}
+
+impl std::hash::Hash for SamplerOptions {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let Self {
+            wrap_mode_s,
+            wrap_mode_t,
+            border_color,
+            anisotropy,
+            mipmap_base_level,
+            mipmap_max_level,
+            lod_bias,
+        } = *self;
+        wrap_mode_s.hash(state);
+        wrap_mode_t.hash(state);
+        border_color.hash(state);
+        anisotropy.hash(state);
+        mipmap_base_level.hash(state);
+        mipmap_max_level.hash(state);
+        lod_bias.map(egui::emath::OrderedFloat).hash(state);
+    }
+}
+
+impl SamplerOptions {
+    /// Does either axis use [`WrapMode::ClampToBorder`]?
+    pub(crate) fn uses_border(&self) -> bool {
+        self.wrap_mode_s == Some(WrapMode::ClampToBorder)
+            || self.wrap_mode_t == Some(WrapMode::ClampToBorder)
+    }
+}
+
+/// How to wrap a texture when the texture coordinates are outside the [0, 1] range.
+///
+/// A superset of [`egui::TextureWrapMode`], with the modes that only OpenGL supports.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum WrapMode {
+    /// See [`egui::TextureWrapMode::ClampToEdge`].
+    ClampToEdge,
+
+    /// See [`egui::TextureWrapMode::Repeat`].
+    Repeat,
+
+    /// See [`egui::TextureWrapMode::MirroredRepeat`].
+    MirroredRepeat,
+
+    /// Sample [`SamplerOptions::border_color`] outside the texture,
+    /// e.g. for transparent padding around a sprite.
+    ///
+    /// Requires OpenGL 1.3, OpenGL ES 3.2, or the `texture_border_clamp` extension.
+    /// Not available on WebGL, where we fall back to [`Self::ClampToEdge`].
+    ClampToBorder,
+}
+
+impl From<egui::TextureWrapMode> for WrapMode {
+    fn from(wrap_mode: egui::TextureWrapMode) -> Self {
+        match wrap_mode {
+            egui::TextureWrapMode::ClampToEdge => Self::ClampToEdge,
+            egui::TextureWrapMode::Repeat => Self::Repeat,
+            egui::TextureWrapMode::MirroredRepeat => Self::MirroredRepeat,
+        }
+    }
+}
+
+#[test]
+fn test_nan_lod_bias_equals_itself() {
+    let options = SamplerOptions {
+        lod_bias: Some(f32::NAN),
+        ..Default::default()
+    };
+    assert_eq!(options, options);
+    assert_ne!(options, SamplerOptions::default());
+}
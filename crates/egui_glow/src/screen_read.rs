@@ -0,0 +1,124 @@
+#![expect(unsafe_code)]
+
+use glow::HasContext as _;
+
+use crate::{PainterError, check_for_gl_error, texture_upload::flip_rows};
+
+// ----------------------------------------------------------------------------
+
+/// Identifies a screen read started with [`crate::Painter::begin_screen_read`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScreenReadId(pub(crate) u64);
+
+/// The pixels of a screen read on their way from the GPU, in a pixel buffer object.
+pub(crate) struct PendingScreenRead {
+    size: [u32; 2],
+    buffer: glow::Buffer,
+
+    /// Signaled once the pixels are in [`Self::buffer`].
+    fence: glow::Fence,
+}
+
+impl PendingScreenRead {
+    /// Start reading the bound framebuffer into a new pixel buffer object.
+    pub(crate) unsafe fn new(gl: &glow::Context, [w, h]: [u32; 2]) -> Result<Self, PainterError> {
+        let byte_len = (w as usize)
+            .checked_mul(h as usize)
+            .and_then(|len| len.checked_mul(4))
+            .and_then(|len| i32::try_from(len).ok())
+            .ok_or_else(|| {
+                PainterError::Other(format!(
+                    "A screen read of {w}x{h} pixels is too large for a pixel buffer"
+                ))
+            })?;
+
+        unsafe {
+            let buffer = gl.create_buffer()?;
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(buffer));
+            gl.buffer_data_size(glow::PIXEL_PACK_BUFFER, byte_len, glow::STREAM_READ);
+            gl.read_pixels(
+                0,
+                0,
+                w as i32,
+                h as i32,
+                glow::RGBA,
+                glow::UNSIGNED_BYTE,
+                glow::PixelPackData::BufferOffset(0),
+            );
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+            let fence = match gl.fence_sync(glow::SYNC_GPU_COMMANDS_COMPLETE, 0) {
+                Ok(fence) => fence,
+                Err(err) => {
+                    gl.delete_buffer(buffer);
+                    return Err(err.into());
+                }
+            };
+            // Make sure the fence is signaled without anyone waiting on it:
+            gl.flush();
+            check_for_gl_error!(gl, "PendingScreenRead::new");
+
+            Ok(Self {
+                size: [w, h],
+                buffer,
+                fence,
+            })
+        }
+    }
+
+    /// Have the pixels arrived?
+    pub(crate) unsafe fn is_ready(&self, gl: &glow::Context) -> bool {
+        unsafe { gl.get_sync_status(self.fence) == glow::SIGNALED }
+    }
+
+    /// The pixels, once [`Self::is_ready`].
+    ///
+    /// The buffer and fence are deleted either way.
+    pub(crate) unsafe fn finish(
+        self,
+        gl: &glow::Context,
+    ) -> Result<egui::ColorImage, PainterError> {
+        let [w, h] = [self.size[0] as usize, self.size[1] as usize];
+        // Checked to fit an `i32` by `Self::new`:
+        let len = w * h * 4;
+        let mut pixels = vec![0_u8; len];
+
+        let result = unsafe {
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, Some(self.buffer));
+            let result = if cfg!(target_arch = "wasm32") {
+                // WebGL can't map buffers:
+                gl.get_buffer_sub_data(glow::PIXEL_PACK_BUFFER, 0, &mut pixels);
+                Ok(())
+            } else {
+                let mapped =
+                    gl.map_buffer_range(glow::PIXEL_PACK_BUFFER, 0, len as i32, glow::MAP_READ_BIT);
+                if mapped.is_null() {
+                    Err(PainterError::Other(
+                        "Failed to map the pixel buffer of a screen read".to_owned(),
+                    ))
+                } else {
+                    pixels.copy_from_slice(std::slice::from_raw_parts(mapped, len));
+                    gl.unmap_buffer(glow::PIXEL_PACK_BUFFER);
+                    Ok(())
+                }
+            };
+            gl.bind_buffer(glow::PIXEL_PACK_BUFFER, None);
+            check_for_gl_error!(gl, "PendingScreenRead::finish");
+            self.destroy(gl);
+            result
+        };
+        result?;
+
+        flip_rows(&mut pixels, w * 4);
+        Ok(egui::ColorImage::new(
+            [w, h],
+            bytemuck::cast_slice(&pixels).to_vec(),
+        ))
+    }
+
+    pub(crate) unsafe fn destroy(&self, gl: &glow::Context) {
+        unsafe {
+            gl.delete_buffer(self.buffer);
+            gl.delete_sync(self.fence);
+        }
+    }
+}
@@ -1,3 +1,12 @@
+#if EXTERNAL_OES
+    // Must come before any non-preprocessor tokens.
+    #if NEW_SHADER_INTERFACE
+        #extension GL_OES_EGL_image_external_essl3 : require
+    #else
+        #extension GL_OES_EGL_image_external : require
+    #endif
+#endif
+
 #ifdef GL_ES
     // To avoid weird distortion issues when rendering text etc, we want highp if possible.
     // But apparently some devices don't support it, so we have to check first.
@@ -8,7 +17,38 @@
     #endif
 #endif
 
-uniform sampler2D u_sampler;
+#if EXTERNAL_OES
+    uniform samplerExternalOES u_sampler;
+#else
+    uniform sampler2D u_sampler;
+#endif
+
+// Whether to dither, as 0.0 or 1.0. See `Painter::set_dithering`.
+uniform float u_dithering;
+
+// The colors are raised to the power of `1.0 / u_gamma`. See `Painter::set_gamma`.
+uniform float u_gamma;
+
+// Applied to the colors without premultiplied alpha. See `Painter::set_color_matrix`.
+uniform mat4 u_color_matrix;
+
+// Whether to output linear colors for an sRGB framebuffer, as 0.0 or 1.0. See `Painter::set_srgb_framebuffer`.
+uniform float u_linear_output;
+
+// Whether to call `user_postprocess`, as 0.0 or 1.0.
+// Off when painting the intermediate framebuffer, whose colors were already postprocessed.
+uniform float u_postprocess;
+
+// Multiplied into the premultiplied colors of the mesh, normally white. See `Painter::paint_user_mesh_tinted`.
+uniform vec4 u_tint;
+
+#if COLOR_LUT
+    // Only compiled with the new shader interface. See `Painter::set_color_lut`.
+    #ifdef GL_ES
+        precision mediump sampler3D;
+    #endif
+    uniform sampler3D u_color_lut;
+#endif
 
 #if NEW_SHADER_INTERFACE
     in vec4 v_rgba_in_gamma;
@@ -43,16 +83,63 @@ vec3 dither_interleaved(vec3 rgb, float levels) {
     return rgb + noise / (levels - 1.0);
 }
 
+// Defined after this file, by `PainterBuilder::fragment_postprocess` or as the identity.
+vec4 user_postprocess(vec4 color, vec2 uv);
+
+// 0-1 sRGB gamma to 0-1 linear
+vec3 linear_from_gamma(vec3 srgb) {
+    vec3 cutoff = vec3(lessThan(srgb, vec3(0.04045)));
+    vec3 lower = srgb / vec3(12.92);
+    vec3 higher = pow((srgb + vec3(0.055)) / vec3(1.055), vec3(2.4));
+    return mix(higher, lower, cutoff);
+}
+
 void main() {
     vec4 texture_in_gamma = texture2D(u_sampler, v_tc);
 
     // We multiply the colors in gamma space, because that's the only way to get text to look right.
-    vec4 frag_color_gamma = v_rgba_in_gamma * texture_in_gamma;
+    // The tint is premultiplied too, so a translucent one dims the color and its alpha alike.
+    vec4 frag_color_gamma = v_rgba_in_gamma * texture_in_gamma * u_tint;
+
+    if (u_gamma != 1.0 && frag_color_gamma.a > 0.0) {
+        // The colors are premultiplied, so adjust them without the alpha:
+        vec3 rgb = frag_color_gamma.rgb / frag_color_gamma.a;
+        frag_color_gamma.rgb = pow(rgb, vec3(1.0 / u_gamma)) * frag_color_gamma.a;
+    }
+
+    if (frag_color_gamma.a > 0.0) {
+        vec4 color = u_color_matrix * vec4(frag_color_gamma.rgb / frag_color_gamma.a, frag_color_gamma.a);
+        color.a = clamp(color.a, 0.0, 1.0);
+        frag_color_gamma = vec4(color.rgb * color.a, color.a);
+    } else {
+        // Nothing to unmultiply, e.g. when painting additively:
+        frag_color_gamma.rgb = (u_color_matrix * vec4(frag_color_gamma.rgb, 0.0)).rgb;
+    }
+
+#if COLOR_LUT
+    if (frag_color_gamma.a > 0.0) {
+        // Sample the centers of the outermost texels for the extreme colors:
+        vec3 lut_size = vec3(textureSize(u_color_lut, 0));
+        vec3 rgb = clamp(frag_color_gamma.rgb / frag_color_gamma.a, 0.0, 1.0);
+        vec3 coord = (rgb * (lut_size - 1.0) + 0.5) / lut_size;
+        frag_color_gamma.rgb = texture(u_color_lut, coord).rgb * frag_color_gamma.a;
+    }
+#endif
+
+    if (u_postprocess > 0.5) {
+        frag_color_gamma = user_postprocess(frag_color_gamma, v_tc);
+    }
 
     // Dither the float color down to eight bits to reduce banding.
     // This step is optional for egui backends.
-#if DITHERING
-    frag_color_gamma.rgb = dither_interleaved(frag_color_gamma.rgb, 256.);
-#endif
+    if (u_dithering > 0.5) {
+        frag_color_gamma.rgb = dither_interleaved(frag_color_gamma.rgb, 256.);
+    }
+
+    if (u_linear_output > 0.5 && frag_color_gamma.a > 0.0) {
+        // The hardware encodes the colors as sRGB again when writing them:
+        vec3 rgb = clamp(frag_color_gamma.rgb / frag_color_gamma.a, 0.0, 1.0);
+        frag_color_gamma.rgb = linear_from_gamma(rgb) * frag_color_gamma.a;
+    }
     gl_FragColor = frag_color_gamma;
 }
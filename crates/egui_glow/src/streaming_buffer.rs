@@ -0,0 +1,194 @@
+#![expect(unsafe_code)]
+
+use glow::HasContext as _;
+
+use crate::check_for_gl_error;
+
+// ----------------------------------------------------------------------------
+
+/// The smallest size a [`StreamingBuffer`] grows to, in bytes.
+const MIN_CAPACITY: usize = 1 << 16;
+
+/// A vertex or index buffer that the meshes of a frame are streamed into.
+///
+/// Rather than reallocating the buffer for every mesh, each mesh is written
+/// with `buffer_sub_data` after the previous one, so the GPU never has to wait
+/// for a draw call to finish before we can write the next mesh.
+/// The storage is orphaned at the start of each frame, and grows (which also orphans it)
+/// when a frame doesn't fit.
+///
+/// Without streaming (WebGL1), each mesh is instead written to the start of the buffer,
+/// which is only reallocated when a mesh is larger than any before it.
+pub(crate) struct StreamingBuffer {
+    pub buffer: glow::Buffer,
+    target: u32,
+
+    /// The usage hint the storage is allocated with, e.g. `STREAM_DRAW`.
+    usage: u32,
+
+    space: BufferSpace,
+}
+
+impl StreamingBuffer {
+    pub(crate) fn new(buffer: glow::Buffer, target: u32, streaming: bool) -> Self {
+        Self {
+            buffer,
+            target,
+            usage: glow::STREAM_DRAW,
+            space: BufferSpace::new(streaming),
+        }
+    }
+
+    pub(crate) fn is_streaming(&self) -> bool {
+        self.space.streaming
+    }
+
+    /// Set the usage hint of the storage, from the next time it is allocated.
+    pub(crate) fn set_usage(&mut self, usage: u32) {
+        if !self.space.streaming && usage != self.usage {
+            // Otherwise it's only allocated again once a mesh doesn't fit:
+            self.space.capacity = 0;
+        }
+        self.usage = usage;
+    }
+
+    pub(crate) fn usage(&self) -> u32 {
+        self.usage
+    }
+
+    /// The allocated size of the buffer, in bytes.
+    pub(crate) fn capacity(&self) -> usize {
+        self.space.capacity
+    }
+
+    /// Allocate room for at least `bytes` up front, instead of growing to it while painting.
+    ///
+    /// Must not be called while painting a frame.
+    /// The buffer must be bound to its target.
+    pub(crate) unsafe fn reserve(&mut self, gl: &glow::Context, bytes: usize) {
+        if self.space.reserve(bytes) {
+            unsafe {
+                gl.buffer_data_size(self.target, self.space.capacity as i32, self.usage);
+            }
+            check_for_gl_error!(gl, "buffer_data_size");
+        }
+    }
+
+    /// Start writing from the beginning of fresh storage.
+    ///
+    /// The buffer must be bound to its target.
+    pub(crate) unsafe fn begin_frame(&mut self, gl: &glow::Context) {
+        if self.space.streaming && 0 < self.space.offset {
+            unsafe {
+                gl.buffer_data_size(self.target, self.space.capacity as i32, self.usage);
+            }
+            check_for_gl_error!(gl, "buffer_data_size");
+        }
+        self.space.offset = 0;
+    }
+
+    /// Upload `data` after what was uploaded before, and return its offset in the buffer, in bytes.
+    ///
+    /// The offset is a multiple of four bytes.
+    /// The buffer must be bound to its target.
+    pub(crate) unsafe fn upload(&mut self, gl: &glow::Context, data: &[u8]) -> usize {
+        let (offset, reallocate) = self.space.place(data.len());
+        if reallocate {
+            // Orphans the storage still in use by the previous draw calls:
+            unsafe {
+                gl.buffer_data_size(self.target, self.space.capacity as i32, self.usage);
+            }
+            check_for_gl_error!(gl, "buffer_data_size");
+        }
+
+        unsafe { gl.buffer_sub_data_u8_slice(self.target, offset as i32, data) };
+        offset
+    }
+}
+
+/// Where the uploads to a [`StreamingBuffer`] go.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BufferSpace {
+    streaming: bool,
+
+    /// The allocated size of the buffer, in bytes.
+    capacity: usize,
+
+    /// Where the next upload goes, in bytes.
+    offset: usize,
+}
+
+impl BufferSpace {
+    fn new(streaming: bool) -> Self {
+        Self {
+            streaming,
+            capacity: 0,
+            offset: 0,
+        }
+    }
+
+    /// Grow to at least `bytes`, and return whether the storage has to be allocated again.
+    fn reserve(&mut self, bytes: usize) -> bool {
+        let reallocate = self.capacity < bytes;
+        if reallocate {
+            self.capacity = bytes.next_power_of_two().max(MIN_CAPACITY);
+            self.offset = 0;
+        }
+        reallocate
+    }
+
+    /// Make room for an upload of `len` bytes, and return its offset,
+    /// and whether the storage has to be allocated again with the new [`Self::capacity`] first.
+    fn place(&mut self, len: usize) -> (usize, bool) {
+        if !self.streaming {
+            let reallocate = self.capacity < len;
+            if reallocate {
+                self.capacity = len.next_power_of_two().max(MIN_CAPACITY);
+            }
+            return (0, reallocate);
+        }
+
+        let mut offset = self.offset.next_multiple_of(4);
+        let reallocate = self.capacity < offset + len;
+        if reallocate {
+            // Make room for the rest of the frame too:
+            self.capacity = (2 * self.capacity)
+                .max(len.next_power_of_two())
+                .max(MIN_CAPACITY);
+            offset = 0;
+        }
+        self.offset = offset + len;
+        (offset, reallocate)
+    }
+}
+
+#[test]
+fn test_buffer_space() {
+    // Meshes of growing and shrinking sizes, each replacing the one before:
+    let mut space = BufferSpace::new(false);
+    assert_eq!(space.place(100), (0, true));
+    assert_eq!(space.capacity, MIN_CAPACITY);
+    assert_eq!(space.place(MIN_CAPACITY), (0, false));
+    assert_eq!(space.place(MIN_CAPACITY + 1), (0, true));
+    assert_eq!(space.capacity, 2 * MIN_CAPACITY);
+    assert_eq!(space.place(10), (0, false));
+    assert_eq!(space.place(2 * MIN_CAPACITY), (0, false));
+    assert_eq!(space.capacity, 2 * MIN_CAPACITY);
+
+    // Meshes of a frame after each other, four-byte aligned:
+    let mut space = BufferSpace::new(true);
+    assert_eq!(space.place(6), (0, true));
+    assert_eq!(space.place(6), (8, false));
+    assert_eq!(space.place(MIN_CAPACITY - 16), (16, false));
+    assert_eq!(space.place(1), (0, true));
+    assert_eq!(space.capacity, 2 * MIN_CAPACITY);
+
+    // A reservation that turns out too small still grows:
+    let mut space = BufferSpace::new(true);
+    assert!(space.reserve(3 * MIN_CAPACITY));
+    assert_eq!(space.capacity, 4 * MIN_CAPACITY);
+    assert!(!space.reserve(MIN_CAPACITY));
+    assert_eq!(space.place(3 * MIN_CAPACITY), (0, false));
+    assert_eq!(space.place(2 * MIN_CAPACITY), (0, true));
+    assert_eq!(space.capacity, 8 * MIN_CAPACITY);
+}
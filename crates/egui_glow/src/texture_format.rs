@@ -0,0 +1,124 @@
+use std::borrow::Cow;
+
+use egui::Color32;
+
+/// How the texels of a texture are stored on the GPU.
+///
+/// egui only ever produces [`Self::Rgba8`] textures, but some textures (like the font atlas)
+/// carry no color information, and can be stored more compactly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    /// Four bytes of premultiplied sRGBA per texel.
+    ///
+    /// This is the default, and what [`egui::ImageData`] contains.
+    #[default]
+    Rgba8,
+
+    /// Four bytes of premultiplied RGBA per texel, stored as sRGB.
+    ///
+    /// The GPU will decode the texels to linear space when sampling.
+    /// egui does all its blending in gamma space, so with the default egui shader
+    /// these textures will look too dark. Only use this if you know you want linear values.
+    ///
+    /// Requires OpenGL 2.1, OpenGL ES 3.0 / WebGL2, or the `EXT_sRGB` extension.
+    Srgba8,
+
+    /// Three bytes of RGB per texel, with no alpha.
+    ///
+    /// Useful for opaque images, like photos or video frames. The alpha of the image is ignored.
+    Rgb8,
+
+    /// Two bytes per texel: the red and green channels of the image.
+    ///
+    /// Sampled as `(r, g, 0, 1)`, so mostly useful for non-color data.
+    ///
+    /// Requires OpenGL 3.0, OpenGL ES 3.0 / WebGL2, or the `ARB_texture_rg` extension.
+    Rg8,
+
+    /// A single byte of coverage per texel.
+    ///
+    /// Only the alpha channel of the image is uploaded, and the texture is swizzled (`RRRR`)
+    /// so that it is sampled as premultiplied white-with-alpha, just like the RGBA version would be.
+    /// This uses a quarter of the memory of [`Self::Rgba8`], which adds up for large font atlases.
+    ///
+    /// Texture swizzling is not available on WebGL nor on OpenGL before 3.3,
+    /// so there uploading an `R8` texture returns an error.
+    /// [`crate::Painter::set_texture_r8`] uploads as [`Self::Rgba8`] there instead.
+    R8,
+
+    /// Four 16-bit floats (`f16`) of premultiplied RGBA per texel, e.g. for HDR content.
+    ///
+    /// Requires OpenGL 3.0, OpenGL ES 3.0 / WebGL2, or the `OES_texture_half_float` extension.
+    ///
+    /// Note that egui will still blend the result into an 8-bit framebuffer,
+    /// so you need to tone-map the content into the `[0, 1]` gamma range yourself.
+    Rgba16F,
+
+    /// Four 32-bit floats (`f32`) of premultiplied RGBA per texel, e.g. for HDR content.
+    ///
+    /// Requires OpenGL 3.0, OpenGL ES 3.0 / WebGL2, or the `OES_texture_float` extension.
+    /// Linear filtering of these textures on OpenGL ES and WebGL also needs `OES_texture_float_linear`.
+    /// Without it, they are filtered with `NEAREST` instead, whatever [`egui::TextureOptions`] they are given.
+    ///
+    /// Note that egui will still blend the result into an 8-bit framebuffer,
+    /// so you need to tone-map the content into the `[0, 1]` gamma range yourself.
+    Rgba32F,
+}
+
+impl TextureFormat {
+    /// Bytes per texel of the source data.
+    pub fn bytes_per_texel(&self) -> usize {
+        match self {
+            Self::Rgba8 | Self::Srgba8 => 4,
+            Self::Rgb8 => 3,
+            Self::Rg8 => 2,
+            Self::R8 => 1,
+            Self::Rgba16F => 8,
+            Self::Rgba32F => 16,
+        }
+    }
+
+    /// Convert the premultiplied sRGBA colors of an egui image to texels of this format,
+    /// dropping the channels that the format does not store.
+    ///
+    /// Returns `None` for the float formats, which egui images can't be converted to.
+    pub(crate) fn texels_from_colors(self, colors: &[Color32]) -> Option<Cow<'_, [u8]>> {
+        match self {
+            Self::Rgba8 | Self::Srgba8 => Some(Cow::Borrowed(bytemuck::cast_slice(colors))),
+            Self::Rgb8 => Some(Cow::Owned(
+                colors
+                    .iter()
+                    .flat_map(|color| [color.r(), color.g(), color.b()])
+                    .collect(),
+            )),
+            Self::Rg8 => Some(Cow::Owned(
+                colors
+                    .iter()
+                    .flat_map(|color| [color.r(), color.g()])
+                    .collect(),
+            )),
+            Self::R8 => Some(Cow::Owned(colors.iter().map(|color| color.a()).collect())),
+            Self::Rgba16F | Self::Rgba32F => None,
+        }
+    }
+}
+
+#[test]
+fn test_texels_from_colors() {
+    let colors = [
+        Color32::from_rgba_premultiplied(1, 2, 3, 4),
+        Color32::from_rgba_premultiplied(5, 6, 7, 8),
+    ];
+    for (format, expected) in [
+        (TextureFormat::Rgba8, vec![1, 2, 3, 4, 5, 6, 7, 8]),
+        (TextureFormat::Srgba8, vec![1, 2, 3, 4, 5, 6, 7, 8]),
+        (TextureFormat::Rgb8, vec![1, 2, 3, 5, 6, 7]),
+        (TextureFormat::Rg8, vec![1, 2, 5, 6]),
+        (TextureFormat::R8, vec![4, 8]),
+    ] {
+        let texels = format.texels_from_colors(&colors).unwrap();
+        assert_eq!(texels.len(), colors.len() * format.bytes_per_texel());
+        assert_eq!(*texels, *expected, "{format:?}");
+    }
+    assert!(TextureFormat::Rgba32F.texels_from_colors(&colors).is_none());
+}
@@ -0,0 +1,315 @@
+use egui::Color32;
+
+use crate::{PainterError, TextureFormat};
+
+/// Options for how [`crate::Painter`] uploads texel data to a texture.
+///
+/// Unlike [`egui::TextureOptions`], these only affect the upload itself,
+/// not how the texture is sampled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct UploadOptions {
+    /// How the texels are stored on the GPU.
+    ///
+    /// Default: [`TextureFormat::Rgba8`].
+    pub format: TextureFormat,
+
+    /// The data has straight (unmultiplied) alpha, and should be premultiplied before uploading.
+    ///
+    /// egui expects premultiplied alpha, so images with straight alpha (e.g. most decoded PNGs)
+    /// get dark halos around their translucent edges unless they are premultiplied.
+    /// This is done on the CPU, and only for the texels being uploaded.
+    ///
+    /// Only supported for the 8-bit RGBA formats.
+    ///
+    /// Default: `false`.
+    pub premultiply_alpha: bool,
+
+    /// Reverse the order of the rows before uploading.
+    ///
+    /// egui treats the first row of an image as the top, so this is useful for sources that
+    /// start at the bottom row, like OpenGL framebuffers and some video decoders.
+    /// For partial updates, only the rows of the updated region are flipped,
+    /// so the region stays at the same position in the texture.
+    ///
+    /// This costs a copy of the texel data on the CPU.
+    ///
+    /// Default: `false`.
+    pub flip_y: bool,
+
+    /// The data is a region of a larger image with rows of this many texels.
+    ///
+    /// This lets you upload e.g. a small dirty rectangle of a large image directly,
+    /// without first copying it into a tightly packed buffer.
+    /// Where the region starts in the larger image is given by [`Self::skip_px`].
+    ///
+    /// Uses `GL_UNPACK_ROW_LENGTH` where available, and packs the rows on the CPU on WebGL1.
+    ///
+    /// Only supported by [`crate::Painter::set_texture_data`].
+    ///
+    /// Default: `None` (tightly packed).
+    pub row_length_px: Option<usize>,
+
+    /// The `[x, y]` texel offset of the region to upload within the larger image.
+    ///
+    /// See [`Self::row_length_px`].
+    ///
+    /// Default: `[0, 0]`.
+    pub skip_px: [usize; 2],
+}
+
+/// What [`crate::Painter`] does with images larger than [`crate::Painter::max_texture_side`].
+///
+/// See [`crate::Painter::set_oversize_policy`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum OversizePolicy {
+    /// Shrink egui images on the CPU (with a box filter) until they fit, and log a warning.
+    ///
+    /// Only for full uploads of [`egui::ImageData`]. Raw texel data
+    /// (see [`crate::Painter::set_texture_data`]) is treated as [`Self::Error`].
+    Downscale,
+
+    /// Return an error, which [`crate::Painter::set_texture`] turns into a panic.
+    #[default]
+    Error,
+
+    /// Panic, even in [`crate::Painter::try_set_texture`].
+    Panic,
+}
+
+impl UploadOptions {
+    /// Is the data a region of a larger image?
+    pub(crate) fn is_strided(&self, width: usize) -> bool {
+        self.row_length_px
+            .is_some_and(|row_length| row_length != width)
+            || self.skip_px != [0, 0]
+    }
+
+    /// Fails unless `data_len` bytes are exactly the texels of an image of `[w, h]`,
+    /// or (for strided data) hold the region of the larger image that is uploaded.
+    pub(crate) fn check_data_len(
+        &self,
+        [w, h]: [usize; 2],
+        data_len: usize,
+    ) -> Result<(), PainterError> {
+        let bytes_per_texel = self.format.bytes_per_texel();
+        if self.is_strided(w) {
+            let row_length = self.row_length_px.unwrap_or(w);
+            let [x, y] = self.skip_px;
+            if row_length < x.saturating_add(w) {
+                return Err(PainterError::Other(format!(
+                    "A region of width {w} at x = {x} doesn't fit in rows of {row_length} texels"
+                )));
+            }
+            // The last row only needs to reach the end of the region:
+            let required_len = if h == 0 {
+                Some(0)
+            } else {
+                y.checked_add(h - 1)
+                    .and_then(|last_row| last_row.checked_mul(row_length))
+                    .and_then(|len| len.checked_add(x + w))
+                    .and_then(|len| len.checked_mul(bytes_per_texel))
+            };
+            return if required_len.is_some_and(|required_len| required_len <= data_len) {
+                Ok(())
+            } else {
+                Err(PainterError::Other(format!(
+                    "A region of size {w}x{h} at [{x}, {y}] in rows of {row_length} texels is out of bounds of the {data_len} bytes of data"
+                )))
+            };
+        }
+
+        let expected_len = w
+            .checked_mul(h)
+            .and_then(|len| len.checked_mul(bytes_per_texel));
+        if expected_len == Some(data_len) {
+            Ok(())
+        } else {
+            Err(PainterError::Other(format!(
+                "A {w}x{h} texture of {:?} needs {w}*{h}*{bytes_per_texel} bytes of data, but got {data_len}",
+                self.format
+            )))
+        }
+    }
+}
+
+/// Premultiply tightly packed straight-alpha RGBA8 texels, in-place.
+pub(crate) fn premultiply_alpha(texels: &mut [u8]) {
+    for texel in texels.chunks_exact_mut(4) {
+        let color = Color32::from_rgba_unmultiplied(texel[0], texel[1], texel[2], texel[3]);
+        texel.copy_from_slice(&color.to_array());
+    }
+}
+
+/// Turn tightly packed premultiplied RGBA8 texels into straight-alpha ones, in-place.
+///
+/// Fully transparent texels become transparent black, since their color is undefined.
+pub(crate) fn unmultiply_alpha(texels: &mut [u8]) {
+    for texel in texels.chunks_exact_mut(4) {
+        let color = Color32::from_rgba_premultiplied(texel[0], texel[1], texel[2], texel[3]);
+        if color.a() == 0 {
+            texel.fill(0);
+        } else {
+            texel.copy_from_slice(&color.to_srgba_unmultiplied());
+        }
+    }
+}
+
+/// Shrink an image by an integer `factor`, averaging each `factor`x`factor` block of texels.
+///
+/// The colors must be premultiplied for the averages to be right.
+/// Returns the new size and texels.
+pub(crate) fn downscale(
+    pixels: &[Color32],
+    [w, h]: [usize; 2],
+    factor: usize,
+) -> ([usize; 2], Vec<Color32>) {
+    let size = [w.div_ceil(factor), h.div_ceil(factor)];
+    let mut downscaled = Vec::with_capacity(size[0] * size[1]);
+    for y in 0..size[1] {
+        for x in 0..size[0] {
+            let mut sum = [0_u32; 4];
+            let mut count = 0;
+            for src_y in y * factor..((y + 1) * factor).min(h) {
+                for src_x in x * factor..((x + 1) * factor).min(w) {
+                    for (sum, channel) in sum.iter_mut().zip(pixels[src_y * w + src_x].to_array()) {
+                        *sum += u32::from(channel);
+                    }
+                    count += 1;
+                }
+            }
+            let [r, g, b, a] = sum.map(|sum| ((sum + count / 2) / count) as u8);
+            downscaled.push(Color32::from_rgba_premultiplied(r, g, b, a));
+        }
+    }
+    (size, downscaled)
+}
+
+#[test]
+fn test_downscale() {
+    let black = Color32::BLACK;
+    let white = Color32::WHITE;
+    #[rustfmt::skip]
+    let pixels = [
+        black, white, black,
+        white, black, white,
+    ];
+    let (size, downscaled) = downscale(&pixels, [3, 2], 2);
+    assert_eq!(size, [2, 1]);
+    assert_eq!(
+        downscaled,
+        [Color32::from_gray(128), Color32::from_gray(128)],
+        "edge blocks only average the texels inside the image"
+    );
+}
+
+/// Copy a `[w, h]` region starting at texel `[x, y]` out of a larger image with rows of `row_length` texels,
+/// into a tightly packed buffer.
+pub(crate) fn pack_rows(
+    data: &[u8],
+    [w, h]: [usize; 2],
+    row_length: usize,
+    [x, y]: [usize; 2],
+    bytes_per_texel: usize,
+) -> Vec<u8> {
+    let mut packed = Vec::with_capacity(w * h * bytes_per_texel);
+    for row in y..y + h {
+        let start = (row * row_length + x) * bytes_per_texel;
+        packed.extend_from_slice(&data[start..start + w * bytes_per_texel]);
+    }
+    packed
+}
+
+#[test]
+fn test_pack_rows() {
+    #[rustfmt::skip]
+    let data = [
+        0, 1, 2, 3,
+        4, 5, 6, 7,
+        8, 9, 10, 11,
+    ];
+    assert_eq!(pack_rows(&data, [2, 2], 4, [1, 1], 1), [5, 6, 9, 10]);
+    assert_eq!(pack_rows(&data, [2, 1], 2, [0, 1], 2), [4, 5, 6, 7]);
+}
+
+/// Reverse the order of the rows of an image, in-place.
+pub(crate) fn flip_rows(texels: &mut [u8], row_size: usize) {
+    if row_size == 0 {
+        return;
+    }
+    let num_rows = texels.len() / row_size;
+    for top in 0..num_rows / 2 {
+        let bottom = num_rows - 1 - top;
+        let (upper, lower) = texels.split_at_mut(bottom * row_size);
+        upper[top * row_size..(top + 1) * row_size].swap_with_slice(&mut lower[..row_size]);
+    }
+}
+
+#[test]
+fn test_flip_rows() {
+    let mut texels = [1, 1, 2, 2, 3, 3];
+    flip_rows(&mut texels, 2);
+    assert_eq!(texels, [3, 3, 2, 2, 1, 1]);
+
+    let mut texels = [1, 1, 2, 2, 3, 3, 4, 4];
+    flip_rows(&mut texels, 2);
+    assert_eq!(texels, [4, 4, 3, 3, 2, 2, 1, 1]);
+}
+
+#[test]
+fn test_premultiply_alpha() {
+    let mut texels = [255, 128, 0, 255, 255, 128, 0, 0, 200, 100, 50, 128];
+    premultiply_alpha(&mut texels);
+    assert_eq!(
+        texels[..4],
+        [255, 128, 0, 255],
+        "opaque texels are unchanged"
+    );
+    assert_eq!(
+        texels[4..8],
+        [0, 0, 0, 0],
+        "transparent texels become black"
+    );
+    assert_eq!(
+        texels[8..],
+        Color32::from_rgba_unmultiplied(200, 100, 50, 128).to_array()
+    );
+}
+
+#[test]
+fn test_unmultiply_alpha() {
+    let mut texels = [255, 128, 0, 255, 10, 20, 30, 0, 100, 50, 25, 128];
+    unmultiply_alpha(&mut texels);
+    assert_eq!(texels[..4], [255, 128, 0, 255]);
+    assert_eq!(texels[4..8], [0, 0, 0, 0]);
+    assert_eq!(texels[8..], [199, 100, 50, 128]);
+}
+
+#[test]
+fn test_check_data_len() {
+    let tight = UploadOptions::default();
+    assert!(tight.check_data_len([2, 2], 16).is_ok());
+    assert!(tight.check_data_len([2, 2], 15).is_err());
+    assert!(tight.check_data_len([0, 0], 4).is_err());
+
+    let strided = UploadOptions {
+        row_length_px: Some(4),
+        skip_px: [1, 1],
+        ..Default::default()
+    };
+    // The last row ends at texel 1 + 2 of row 2:
+    assert!(strided.check_data_len([2, 2], (2 * 4 + 3) * 4).is_ok());
+    assert!(strided.check_data_len([2, 2], (2 * 4 + 3) * 4 - 1).is_err());
+    assert!(
+        strided.check_data_len([4, 2], 1000).is_err(),
+        "wider than the rows"
+    );
+    assert!(
+        UploadOptions {
+            skip_px: [0, usize::MAX],
+            ..strided
+        }
+        .check_data_len([2, 2], 1000)
+        .is_err(),
+        "overflowing offsets"
+    );
+}
@@ -1,5 +1,4 @@
 #![expect(unsafe_code)]
-#![expect(clippy::unwrap_used)]
 
 use glow::HasContext as _;
 
@@ -34,8 +33,22 @@ impl VertexArrayObject {
         buffer_infos: Vec<BufferInfo>,
     ) -> Self {
         let vao = if supports_vao(gl) {
+            unsafe { gl.create_vertex_array() }
+                .inspect_err(|err| {
+                    log::warn!(
+                        "Failed to create a vertex array object, emulating it instead: {err}"
+                    );
+                })
+                .ok()
+        } else {
+            log::debug!(
+                "Vertex array objects are not supported. Emulating them by setting up the vertex attributes before each frame."
+            );
+            None
+        };
+
+        if let Some(vao) = vao {
             unsafe {
-                let vao = gl.create_vertex_array().unwrap();
                 check_for_gl_error!(gl, "create_vertex_array");
 
                 // Store state in the VAO:
@@ -57,13 +70,8 @@ impl VertexArrayObject {
                 }
 
                 gl.bind_vertex_array(None);
-
-                Some(vao)
             }
-        } else {
-            log::debug!("VAO not supported");
-            None
-        };
+        }
 
         Self {
             vao,
@@ -72,6 +80,16 @@ impl VertexArrayObject {
         }
     }
 
+    /// The vertex array object, unless emulated.
+    pub(crate) fn vertex_array(&self) -> Option<glow::VertexArray> {
+        self.vao
+    }
+
+    /// Are the vertex attributes set up again each time this is bound?
+    pub(crate) fn is_emulated(&self) -> bool {
+        self.vao.is_none()
+    }
+
     pub(crate) unsafe fn bind(&self, gl: &glow::Context) {
         unsafe {
             if let Some(vao) = self.vao {
@@ -98,6 +116,32 @@ impl VertexArrayObject {
         }
     }
 
+    /// Point the attributes at the vertices starting `offset` bytes into `vbo`,
+    /// which is usually the VBO this was created with.
+    ///
+    /// Must be called while bound.
+    pub(crate) unsafe fn set_vertex_buffer(
+        &self,
+        gl: &glow::Context,
+        vbo: glow::Buffer,
+        offset: i32,
+    ) {
+        unsafe {
+            gl.bind_buffer(glow::ARRAY_BUFFER, Some(vbo));
+            for attribute in &self.buffer_infos {
+                gl.vertex_attrib_pointer_f32(
+                    attribute.location,
+                    attribute.vector_size,
+                    attribute.data_type,
+                    attribute.normalized,
+                    attribute.stride,
+                    attribute.offset + offset,
+                );
+            }
+        }
+        check_for_gl_error!(gl, "vertex_attrib_pointer_f32");
+    }
+
     pub(crate) unsafe fn unbind(&self, gl: &glow::Context) {
         unsafe {
             if self.vao.is_some() {
@@ -129,10 +173,7 @@ fn supports_vao(gl: &glow::Context) -> bool {
         let version_str = &version_string[pos + WEBGL_PREFIX.len()..];
         if version_str.contains("1.0") {
             // need to test OES_vertex_array_object .
-            let supported_extensions = gl.supported_extensions();
-            log::debug!("Supported OpenGL extensions: {supported_extensions:?}");
-            supported_extensions.contains("OES_vertex_array_object")
-                || supported_extensions.contains("GL_OES_vertex_array_object")
+            has_extension(gl, "OES_vertex_array_object")
         } else {
             true
         }
@@ -140,10 +181,7 @@ fn supports_vao(gl: &glow::Context) -> bool {
         // glow targets es2.0+ so we don't concern about OpenGL ES-CM,OpenGL ES-CL
         if version_string.contains("2.0") {
             // need to test OES_vertex_array_object .
-            let supported_extensions = gl.supported_extensions();
-            log::debug!("Supported OpenGL extensions: {supported_extensions:?}");
-            supported_extensions.contains("OES_vertex_array_object")
-                || supported_extensions.contains("GL_OES_vertex_array_object")
+            has_extension(gl, "OES_vertex_array_object")
         } else {
             true
         }
@@ -152,12 +190,16 @@ fn supports_vao(gl: &glow::Context) -> bool {
         if version_string.starts_with('2') {
             // I found APPLE_vertex_array_object , GL_ATI_vertex_array_object ,ARB_vertex_array_object
             // but APPLE's and ATI's very old extension.
-            let supported_extensions = gl.supported_extensions();
-            log::debug!("Supported OpenGL extensions: {supported_extensions:?}");
-            supported_extensions.contains("ARB_vertex_array_object")
-                || supported_extensions.contains("GL_ARB_vertex_array_object")
+            has_extension(gl, "ARB_vertex_array_object")
         } else {
             true
         }
     }
 }
+
+/// Is `GL_{name}` (or plain `name`, as WebGL names it) supported?
+fn has_extension(gl: &glow::Context, name: &str) -> bool {
+    gl.supported_extensions()
+        .iter()
+        .any(|extension| extension.ends_with(name))
+}
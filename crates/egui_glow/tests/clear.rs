@@ -0,0 +1,91 @@
+//! Clearing, and the alpha it leaves for compositing, on a real (headless) OpenGL context.
+
+mod common;
+
+use std::sync::Arc;
+
+use egui::{
+    ClippedPrimitive, Color32, ColorImage, Mesh, Rect, TextureId, TextureOptions,
+    epaint::{ImageDelta, Primitive},
+};
+
+const SIZE: [u32; 2] = [4, 4];
+
+fn painter_with_render_target(gl: &Arc<glow::Context>) -> egui_glow::Painter {
+    let mut painter = egui_glow::Painter::new(Arc::clone(gl), "", None, false)
+        .expect("Failed to create the painter");
+    let render_target = painter
+        .create_render_target(
+            [SIZE[0] as usize, SIZE[1] as usize],
+            TextureOptions::NEAREST,
+        )
+        .expect("Failed to create the render target");
+    render_target.bind(gl);
+    painter.set_render_target(Some(render_target.framebuffer));
+    painter
+}
+
+fn assert_color_near(actual: Color32, expected: Color32) {
+    let close = actual
+        .to_array()
+        .into_iter()
+        .zip(expected.to_array())
+        .all(|(a, e)| a.abs_diff(e) <= 2);
+    assert!(close, "Expected {expected:?}, got {actual:?}");
+}
+
+#[test]
+fn clearing_to_transparent_leaves_the_alpha_of_what_is_painted() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let mut painter = painter_with_render_target(&headless.gl);
+
+    // The white texel meshes sample at `egui::epaint::WHITE_UV`:
+    let white = ColorImage::new([1, 1], vec![Color32::WHITE]);
+    painter.set_texture(
+        TextureId::default(),
+        &ImageDelta::full(white, TextureOptions::NEAREST),
+    );
+
+    // Half-transparent red, then half-transparent blue on top, as in the `BlendMode::Normal` docs:
+    let screen_rect = Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(4.0, 4.0));
+    let layers = [
+        Color32::from_rgba_unmultiplied(255, 0, 0, 128),
+        Color32::from_rgba_unmultiplied(0, 0, 255, 128),
+    ]
+    .map(|color| {
+        let mut mesh = Mesh::default();
+        mesh.add_colored_rect(screen_rect, color);
+        ClippedPrimitive {
+            clip_rect: screen_rect,
+            primitive: Primitive::Mesh(mesh),
+        }
+    });
+
+    painter.clear(SIZE, [0.0; 4]);
+    painter.paint_primitives(SIZE, 1.0, &layers);
+    assert_color_near(
+        painter.read_pixel(SIZE, 1, 1),
+        Color32::from_rgba_premultiplied(64, 0, 128, 192),
+    );
+
+    painter.destroy();
+}
+
+#[test]
+fn clear_rgb_keeps_the_alpha() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let mut painter = painter_with_render_target(&headless.gl);
+
+    painter.clear(SIZE, [0.0, 0.0, 0.0, 0.5]);
+    painter.clear_rgb(SIZE, [1.0, 0.0, 0.0]);
+    assert_color_near(
+        painter.read_pixel(SIZE, 1, 1),
+        Color32::from_rgba_premultiplied(255, 0, 0, 128),
+    );
+
+    painter.destroy();
+}
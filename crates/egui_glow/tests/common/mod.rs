@@ -0,0 +1,47 @@
+//! A headless OpenGL context to test the painter with, where the machine has one.
+
+#![expect(unsafe_code)]
+
+use std::sync::Arc;
+
+use glutin::{
+    api::egl::{context::PossiblyCurrentContext, device::Device, display::Display},
+    config::{ConfigSurfaceTypes, ConfigTemplateBuilder},
+    context::ContextAttributesBuilder,
+    display::GlDisplay as _,
+};
+
+pub struct HeadlessGl {
+    pub gl: Arc<glow::Context>,
+    _context: PossiblyCurrentContext,
+    _display: Display,
+}
+
+/// A context without a window, made current on this thread.
+///
+/// `None` if there's no EGL device to create one on, e.g. on CI without a GPU or Mesa,
+/// in which case the test should be skipped.
+pub fn headless_gl() -> Option<HeadlessGl> {
+    let device = Device::query_devices().ok()?.next()?;
+    // SAFETY: no native display handle to outlive.
+    let display = unsafe { Display::with_device(&device, None) }.ok()?;
+    let template = ConfigTemplateBuilder::new()
+        .with_surface_type(ConfigSurfaceTypes::empty())
+        .build();
+    // SAFETY: the display is alive for the whole call.
+    let config = unsafe { display.find_configs(template) }.ok()?.next()?;
+    // SAFETY: the config comes from this display.
+    let context =
+        unsafe { display.create_context(&config, &ContextAttributesBuilder::new().build(None)) }
+            .ok()?
+            .make_current_surfaceless()
+            .ok()?;
+    // SAFETY: the context is current, and the display outlives `gl` in `HeadlessGl`.
+    let gl =
+        unsafe { glow::Context::from_loader_function_cstr(|name| display.get_proc_address(name)) };
+    Some(HeadlessGl {
+        gl: Arc::new(gl),
+        _context: context,
+        _display: display,
+    })
+}
@@ -0,0 +1,63 @@
+//! Painting a frame in parts with `begin_frame` and `end_frame`, on a real (headless) OpenGL context.
+
+mod common;
+
+use std::sync::Arc;
+
+use egui::{
+    ClippedPrimitive, Color32, ColorImage, Mesh, Rect, TextureId, TextureOptions,
+    epaint::{ImageDelta, Primitive},
+};
+
+const SIZE: [u32; 2] = [4, 4];
+
+fn textured_rect(texture_id: TextureId) -> ClippedPrimitive {
+    let rect = Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(4.0, 4.0));
+    let mut mesh = Mesh::with_texture(texture_id);
+    mesh.add_rect_with_uv(
+        rect,
+        Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+        Color32::WHITE,
+    );
+    ClippedPrimitive {
+        clip_rect: rect,
+        primitive: Primitive::Mesh(mesh),
+    }
+}
+
+#[test]
+fn uploading_mid_frame_doesnt_change_the_texture_of_the_next_mesh() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let gl = &headless.gl;
+    let mut painter = egui_glow::Painter::new(Arc::clone(gl), "", None, false)
+        .expect("Failed to create the painter");
+    let render_target = painter
+        .create_render_target(
+            [SIZE[0] as usize, SIZE[1] as usize],
+            TextureOptions::NEAREST,
+        )
+        .expect("Failed to create the render target");
+    render_target.bind(gl);
+    painter.set_render_target(Some(render_target.framebuffer));
+
+    let [red, blue] = [1, 2].map(TextureId::Managed);
+    for (id, color) in [(red, Color32::RED), (blue, Color32::BLUE)] {
+        let image = ColorImage::new([1, 1], vec![color]);
+        painter.set_texture(id, &ImageDelta::full(image, TextureOptions::NEAREST));
+    }
+
+    painter.begin_frame(SIZE, 1.0);
+    painter.paint_frame_primitives(&[textured_rect(red)]);
+    // Binds the blue texture to upload to it:
+    let image = ColorImage::new([1, 1], vec![Color32::BLUE]);
+    painter.set_texture(blue, &ImageDelta::full(image, TextureOptions::NEAREST));
+    painter.paint_frame_primitives(&[textured_rect(red)]);
+    painter.end_frame().expect("Failed to paint the frame");
+
+    assert_eq!(painter.read_pixel(SIZE, 1, 1), Color32::RED);
+
+    painter.destroy_render_target(render_target);
+    painter.destroy();
+}
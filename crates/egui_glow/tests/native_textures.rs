@@ -0,0 +1,107 @@
+//! Which textures the painter treats as the app's own, on a real (headless) OpenGL context.
+
+#![expect(unsafe_code)]
+
+mod common;
+
+use std::sync::Arc;
+
+use egui::TextureOptions;
+use glow::HasContext as _;
+
+#[test]
+fn only_registered_textures_are_native() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let gl = &headless.gl;
+    let mut painter = egui_glow::Painter::new(Arc::clone(gl), "", None, false)
+        .expect("Failed to create the painter");
+
+    // A `TextureId::User`, but created by the painter:
+    let allocated = painter
+        .allocate_texture([4, 4], TextureOptions::LINEAR)
+        .expect("Failed to allocate the texture");
+    assert!(!painter.is_native_texture(allocated));
+    assert!(painter.free_managed_texture(allocated));
+    assert!(painter.texture(allocated).is_none());
+
+    // SAFETY: the context is current on this thread.
+    let native = unsafe { gl.create_texture() }.expect("Failed to create the texture");
+    let registered = painter.register_native_texture(native, None);
+    assert!(painter.is_native_texture(registered));
+    assert!(!painter.free_managed_texture(registered));
+    assert_eq!(painter.texture(registered), Some(native));
+
+    painter.destroy();
+    // SAFETY: the painter doesn't delete native textures.
+    unsafe { gl.delete_texture(native) };
+}
+
+#[test]
+fn reading_back_a_native_texture_ignores_the_size_hint() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let gl = &headless.gl;
+    let mut painter = egui_glow::Painter::new(Arc::clone(gl), "", None, false)
+        .expect("Failed to create the painter");
+
+    // SAFETY: the context is current on this thread.
+    let native = unsafe {
+        let native = gl.create_texture().expect("Failed to create the texture");
+        gl.bind_texture(glow::TEXTURE_2D, Some(native));
+        gl.tex_image_2d(
+            glow::TEXTURE_2D,
+            0,
+            glow::RGBA8 as i32,
+            8,
+            8,
+            0,
+            glow::RGBA,
+            glow::UNSIGNED_BYTE,
+            glow::PixelUnpackData::Slice(Some(&[255; 8 * 8 * 4])),
+        );
+        native
+    };
+    // Smaller than the texture really is:
+    let registered = painter.register_native_texture(native, Some([2, 2]));
+
+    if !gl.version().is_embedded {
+        let image = painter
+            .read_texture_rgba(registered)
+            .expect("Failed to read back the texture");
+        assert_eq!(image.size, [8, 8]);
+    }
+
+    painter.destroy();
+    // SAFETY: the painter doesn't delete native textures.
+    unsafe { gl.delete_texture(native) };
+}
+
+#[test]
+fn only_registered_textures_are_lost_with_the_context() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let gl = &headless.gl;
+    let mut painter = egui_glow::Painter::new(Arc::clone(gl), "", None, false)
+        .expect("Failed to create the painter");
+
+    painter
+        .allocate_texture([4, 4], TextureOptions::LINEAR)
+        .expect("Failed to allocate the texture");
+    // SAFETY: the context is current on this thread.
+    let native = unsafe { gl.create_texture() }.expect("Failed to create the texture");
+    let registered = painter.register_native_texture(native, None);
+
+    // The same context stands in for a new one, leaking what the painter made in it:
+    let lost = painter
+        .on_context_restored(Arc::clone(gl))
+        .expect("Failed to restore the painter");
+    assert_eq!(lost, vec![registered]);
+
+    painter.destroy();
+    // SAFETY: the painter doesn't delete native textures.
+    unsafe { gl.delete_texture(native) };
+}
@@ -0,0 +1,75 @@
+//! Asynchronous screen reads, on a real (headless) OpenGL context.
+
+mod common;
+
+use std::sync::Arc;
+
+use egui::{Color32, TextureOptions};
+
+#[test]
+fn screen_reads_arrive_or_fail_cleanly() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let gl = &headless.gl;
+    let mut painter = egui_glow::Painter::new(Arc::clone(gl), "", None, false)
+        .expect("Failed to create the painter");
+    let render_target = painter
+        .create_render_target([4, 4], TextureOptions::NEAREST)
+        .expect("Failed to create the render target");
+    render_target.bind(gl);
+    painter.set_render_target(Some(render_target.framebuffer));
+
+    assert!(
+        painter.begin_screen_read([u32::MAX, u32::MAX]).is_err(),
+        "A read too large for a pixel buffer should be rejected"
+    );
+
+    painter.clear([4, 4], [1.0, 0.0, 0.0, 1.0]);
+    let id = painter
+        .begin_screen_read([4, 4])
+        .expect("Failed to begin the screen read");
+    let image = loop {
+        if let Some(image) = painter
+            .try_finish_screen_read(id)
+            .expect("Failed to finish the screen read")
+        {
+            break image;
+        }
+    };
+    assert_eq!(image.size, [4, 4]);
+    assert!(image.pixels.iter().all(|&pixel| pixel == Color32::RED));
+    assert!(
+        painter
+            .try_finish_screen_read(id)
+            .is_ok_and(|image| image.is_none()),
+        "The id should be forgotten once finished"
+    );
+
+    painter.destroy_render_target(render_target);
+    painter.destroy();
+}
+
+#[test]
+fn rgb_rows_are_tightly_packed() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let gl = &headless.gl;
+    let mut painter = egui_glow::Painter::new(Arc::clone(gl), "", None, false)
+        .expect("Failed to create the painter");
+    // Rows of 3 * 3 bytes, which aren't a multiple of the default alignment of 4:
+    let render_target = painter
+        .create_render_target([3, 3], TextureOptions::NEAREST)
+        .expect("Failed to create the render target");
+    render_target.bind(gl);
+    painter.set_render_target(Some(render_target.framebuffer));
+
+    painter.clear([3, 3], [0.0, 0.0, 1.0, 1.0]);
+    let mut pixels = Vec::new();
+    painter.read_screen_rgb_into([3, 3], &mut pixels);
+    assert_eq!(pixels, [0, 0, 255].repeat(9));
+
+    painter.destroy_render_target(render_target);
+    painter.destroy();
+}
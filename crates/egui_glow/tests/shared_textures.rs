@@ -0,0 +1,37 @@
+//! Painters sharing their textures, on a real (headless) OpenGL context.
+
+#![expect(unsafe_code)]
+
+mod common;
+
+use std::sync::Arc;
+
+use egui::{Color32, ColorImage, TextureId, TextureOptions, epaint::ImageDelta};
+use glow::HasContext as _;
+
+#[test]
+fn the_last_painter_destroyed_deletes_the_shared_textures() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let gl = &headless.gl;
+    let mut first = egui_glow::Painter::new(Arc::clone(gl), "", None, false)
+        .expect("Failed to create the painter");
+    let mut second = egui_glow::PainterBuilder::new()
+        .shared_textures(first.shared_textures())
+        .build(Arc::clone(gl))
+        .expect("Failed to create the painter");
+
+    let id = TextureId::Managed(1);
+    let image = ColorImage::new([4, 4], vec![Color32::WHITE; 16]);
+    second.set_texture(id, &ImageDelta::full(image, TextureOptions::LINEAR));
+    let texture = first.texture(id).expect("The texture should be shared");
+    // SAFETY: the context is current on this thread.
+    let is_alive = || unsafe { gl.is_texture(texture) };
+
+    // Both destroyed before either is dropped:
+    first.destroy();
+    assert!(is_alive(), "The other painter still uses the texture");
+    second.destroy();
+    assert!(!is_alive(), "The last painter should delete the texture");
+}
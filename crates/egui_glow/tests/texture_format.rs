@@ -0,0 +1,71 @@
+//! Partial updates of textures stored in other formats than RGBA, on a real (headless) OpenGL context.
+
+mod common;
+
+use std::sync::Arc;
+
+use egui::{Color32, ColorImage, TextureId, TextureOptions, epaint::ImageDelta};
+
+#[test]
+fn partial_updates_must_match_the_stored_format() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let mut painter = egui_glow::Painter::new(Arc::clone(&headless.gl), "", None, false)
+        .expect("Failed to create the painter");
+
+    let id = TextureId::Managed(1);
+    let image = ColorImage::new([4, 4], vec![Color32::WHITE; 16]);
+    painter.set_texture_r8(id, &ImageDelta::full(image, TextureOptions::LINEAR));
+
+    let patch = || {
+        ImageDelta::partial(
+            [0, 0],
+            ColorImage::new([1, 1], vec![Color32::RED]),
+            TextureOptions::LINEAR,
+        )
+    };
+    assert!(
+        painter.try_set_texture(id, &patch()).is_err(),
+        "An RGBA update of an R8 texture should be rejected"
+    );
+    assert!(
+        painter.try_set_texture_r8(id, &patch()).is_ok(),
+        "An R8 update of an R8 texture should be uploaded"
+    );
+
+    painter.destroy();
+}
+
+#[test]
+fn raw_data_of_the_wrong_length_is_rejected() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let mut painter = egui_glow::Painter::new(Arc::clone(&headless.gl), "", None, false)
+        .expect("Failed to create the painter");
+
+    let id = TextureId::Managed(1);
+    let upload = egui_glow::UploadOptions::default();
+    for (size, data_len) in [([4, 4], 4 * 4 * 4 - 1), ([0, 0], 4)] {
+        assert!(
+            painter
+                .set_texture_data(
+                    id,
+                    None,
+                    size,
+                    TextureOptions::LINEAR,
+                    upload,
+                    &vec![0; data_len]
+                )
+                .is_err(),
+            "{data_len} bytes for a {size:?} texture should be rejected"
+        );
+        assert!(
+            painter.texture(id).is_none(),
+            "A rejected texture shouldn't be created"
+        );
+    }
+
+    painter.destroy();
+}
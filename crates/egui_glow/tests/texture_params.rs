@@ -0,0 +1,59 @@
+//! How the painter sets the parameters of textures, on a real (headless) OpenGL context.
+
+#![expect(unsafe_code)]
+
+mod common;
+
+use std::sync::Arc;
+
+use egui::{Color32, ColorImage, TextureFilter, TextureId, TextureOptions, epaint::ImageDelta};
+use glow::HasContext as _;
+
+#[test]
+fn partial_updates_only_set_changed_parameters() {
+    let Some(headless) = common::headless_gl() else {
+        return; // No OpenGL to test with
+    };
+    let gl = &headless.gl;
+    let mut painter = egui_glow::Painter::new(Arc::clone(gl), "", None, false)
+        .expect("Failed to create the painter");
+
+    let id = TextureId::Managed(1);
+    let image = ColorImage::new([4, 4], vec![Color32::WHITE; 16]);
+    // With mipmaps, so that immutable storage has more than one level to limit the sampling to:
+    let options = TextureOptions::LINEAR.with_mipmap_mode(Some(TextureFilter::Linear));
+    painter.set_texture(id, &ImageDelta::full(image, options));
+    let texture = painter.texture(id).expect("The texture should exist");
+    // The filtering may live in a sampler object, but the mipmap levels are always on the texture.
+    // SAFETY: the context is current on this thread, and the texture is alive.
+    let max_level = || unsafe {
+        gl.bind_texture(glow::TEXTURE_2D, Some(texture));
+        gl.get_tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAX_LEVEL)
+    };
+    assert_ne!(max_level(), 1);
+
+    // Changed behind the back of the painter, so we can tell whether it sets the parameter again:
+    // SAFETY: the texture is bound by `max_level`.
+    unsafe {
+        gl.tex_parameter_i32(glow::TEXTURE_2D, glow::TEXTURE_MAX_LEVEL, 1);
+    }
+    let patch = || ColorImage::new([1, 1], vec![Color32::RED]);
+    painter.set_texture(id, &ImageDelta::partial([0, 0], patch(), options));
+    assert_eq!(
+        max_level(),
+        1,
+        "A partial update with the same options shouldn't set the parameters again"
+    );
+
+    painter.set_texture(
+        id,
+        &ImageDelta::partial([1, 1], patch(), TextureOptions::NEAREST),
+    );
+    assert_ne!(
+        max_level(),
+        1,
+        "A partial update with other options should set the parameters"
+    );
+
+    painter.destroy();
+}